@@ -146,9 +146,8 @@ export RUST_BACKTRACE=1
 export RUST_LOG=TRACE
 
 # Whether or not to skip experiments that have already been run
-# Note: You can set this to true if you don't want to re-run a bunch of experiments after a hang somewhere. You
-#       will probably want to delete the "half-finished" logfiles first or those experiments will be skipped
-#       as well.
+# Note: You can set this to true if you don't want to re-run a bunch of experiments after a hang somewhere. Only
+#       repetitions whose log ends with a successful '# Result:' line are skipped ("half-finished" ones are re-run).
 export SKIP_FINISHED=TRUE
 # export DRY_RUN=TRUE
 
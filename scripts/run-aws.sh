@@ -65,16 +65,23 @@ export OPENMPI_PATH="/opt/amazon/openmpi"
 export MPI_HOME="${OPENMPI_PATH}"
 # export AWS_OFI_NCCL_PATH="/mnt/sharedfs/ly-experiments/aws-ofi-nccl-lyd"
 export AWS_OFI_NCCL_PATH="/opt/aws-ofi-nccl/"
+# Optionally A/B several aws-ofi-nccl plugin builds in one sweep (every experiment is run with each; results are tagged `np<name>`)
+# export NCCL_NET_PLUGINS="stock=/opt/aws-ofi-nccl/lib/libnccl-net.so,lyd=/mnt/sharedfs/ly-experiments/aws-ofi-nccl-lyd/lib/libnccl-net.so"
 export MSCCL_PATH="/mnt/sharedfs/ly-experiments/msccl-lyd/build"
 export NCCL_HOME="/mnt/sharedfs/ly-experiments/msccl-lyd/build"
 export NCCL_PATH="${NCCL_HOME}"
 export NCCL_TESTS_HOME="/mnt/sharedfs/ly-experiments/nccl-tests-lyd/build"
+# export NCCL_TESTS_BUILDS="stock=/mnt/sharedfs/ly-experiments/nccl-tests/build,lyd=${NCCL_TESTS_HOME}"  # Sweep several builds (overrides NCCL_TESTS_HOME)
 export MSCCL_XMLS="/mnt/sharedfs/ly-experiments/msccl-tools-lyd/examples/xml/xml_lyd/aws-test/32nic/32gpus"
 
 # Config
 export MPI_HOSTFILE="/home/ec2-user/hostfile"
+# Optionally run every experiment on each of several node groups (e.g., racks) to compare them (replaces MPI_HOSTFILE;
+# each hostfile must fit the topology on its own; results are tagged `hg<name>`)
+# export MPI_HOSTFILES="rack1=/home/ec2-user/hostfile-rack1,rack2=/home/ec2-user/hostfile-rack2"
 export NUM_NODES=4
-export GPUS_PER_NODE=8
+export GPUS_PER_NODE=8  # Or 'auto' (or unset) to count the GPUs on this node with 'nvidia-smi -L'
+# export MPI_PROC_PER_NODE=8  # Defaults to GPUS_PER_NODE; use fewer to have each process drive multiple GPUs
 export EXPERIMENTS_OUTPUT_DIR="/mnt/sharedfs/ly-experiments/experiments_output"
 export LOGS_DIR="/mnt/sharedfs/ly-experiments/experiments_output/raw_logs"
 
@@ -138,13 +145,192 @@ mkdir -p "${LOGS_DIR}"
 # Rust Environment
 export RUST_BACKTRACE=1
 export RUST_LOG=TRACE
+# export LOG_FORMAT=json  # Emit structured JSON log lines (with the experiment's context fields)
 
 # Whether or not to skip experiments that have already been run
-# Note: You can set this to true if you don't want to re-run a bunch of experiments after a hang somewhere. You
-#       will probably want to delete the "half-finished" logfiles first or those experiments will be skipped
-#       as well.
+# Note: You can set this to true if you don't want to re-run a bunch of experiments after a hang somewhere. This
+#       works per repetition: only logs that end with a successful '# Result:' line are treated as finished, so
+#       "half-finished" and failed repetitions are re-run while the others of the same experiment are kept. Logs are
+#       also only treated as finished if the hash of the params and XML file contents recorded at their top still
+#       matches, so experiments whose XML file was regenerated under the same name are re-run.
 export SKIP_FINISHED=TRUE
 
+# Optionally drop (and mark as skipped) permutations whose XML file is missing instead of quitting
+# export SKIP_MISSING_XMLS=TRUE
+
+# Optionally run permutations whose XML file is missing without MSCCL (NCCL's own algorithms) instead of quitting
+# Note: Takes precedence over SKIP_MISSING_XMLS; these experiments are tagged in the manifest's notes
+# export NCCL_FALLBACK_FOR_MISSING_XMLS=TRUE
+
+# Optionally keep permutations whose XML file is missing, but skip them (marked as skipped) if it is still missing when
+# they are run, reporting the sweep as degraded at the end
+# export SKIP_MISSING_XMLS_AT_RUN_TIME=TRUE
+
+# Optionally change which communication algorithms each collective is swept with (replaces the collective's entry of
+# the built-in table; collectives without an entry get every algorithm; use "*" to allow every algorithm)
+# export COLLECTIVE_ALGORITHMS="broadcast=binary-tree|ring,all-gather=*"
+
+# Optionally run the experiments that share an MSCCL XML file back-to-back (the order stays deterministic, so
+# '--start-index' and '--profile' indices refer to the reordered list)
+# export EXPERIMENT_ORDER=xml
+
+# Optionally sample GPU power draw and utilization with 'nvidia-smi' during each experiment
+# export SAMPLE_POWER=TRUE
+
+# Priority of the library paths in the LD_LIBRARY_PATH given to the tests (MSCCL first so its NCCL wins)
+# export LD_LIBRARY_PATH_ORDER="msccl,aws_ofi_nccl,efa,cuda,openmpi"
+
+# Launcher used to start the NCCL tests ('mpirun' or 'srun'; defaults to 'mpirun')
+# export LAUNCHER=mpirun
+
+# Optionally organize outputs into '<output dir>/<collective>/<algorithm>/' subdirectories
+# export NESTED_OUTPUT_DIRS=TRUE
+
+# Optionally treat the first repetition of each experiment as a warmup and exclude it from the results
+# export SKIP_WARMUP_REPETITION=TRUE
+
+# Optionally flag message sizes whose bus bandwidth is below this fraction of the experiment's median
+# export ANOMALY_BW_FRACTION=0.5
+
+# Optionally export all parsed results to a Parquet file (written incrementally unless PARQUET_SINGLE_SHOT is set)
+# Note: Needs the "dataframe" feature (on by default; build with "--no-default-features" for a lean binary without Polars)
+# export PARQUET_OUTPUT_PATH="${EXPERIMENTS_OUTPUT_DIR}/results.$(date +%Y%m%d%H%M%S).parquet"
+# export PARQUET_SINGLE_SHOT=TRUE
+
+# Optionally export only some of the parsed columns (defaults to all; the columns identifying the experiment are always kept)
+# export RESULT_COLUMNS="size,oop_bus_bw,ip_bus_bw"
+
+# Optionally write all parsed results and the manifest to a SQLite database (build with '--features sqlite')
+# Note: The tables are created if absent, so several sweeps can be collected in one database (tagged with SWEEP_RUN_ID)
+# export SQLITE_OUTPUT_PATH="${EXPERIMENTS_OUTPUT_DIR}/results.sqlite"
+
+# Optionally flag message sizes where in-place and out-of-place bus bandwidth differ by more than this ratio
+# export INPLACE_DIVERGENCE_RATIO=1.5
+
+# Optionally mark experiments as partial failures if a table doesn't have one row per message size (a truncated run can
+# still exit cleanly). With a step factor f there are floor(log_f(max / min)) + 1 sizes, with a fixed step s there are
+# floor((max - min) / s) + 1
+# export CHECK_ROW_COUNT=TRUE
+
+# Optionally export the bandwidth columns in Gbit/s instead of GB/s (as reported by NCCL tests; added with a unit suffix)
+# export BANDWIDTH_UNIT="Gbit/s"
+
+# Optionally mirror the output of the NCCL tests to the harness's stdout as it arrives (prefixed; '--quiet' turns it off)
+# export TEE_STDOUT=TRUE
+
+# Optionally re-emit the parsed rows of each repetition as an nccl-tests table ('*.table.txt') for tools that expect that format
+# export NCCL_TABLE_OUTPUT=TRUE
+
+# Optionally write a CSV per config with the bus bandwidth of every repetition side by side (one row per message size)
+# export PER_SIZE_CSV=TRUE
+
+# Optionally write an index of every experiment's params, result, and output files (`index.csv`, updated as experiments complete)
+# export LOG_INDEX=TRUE
+
+# Optionally tag the sweep with metadata (written to the manifest, the Markdown report, and the top of each log)
+# Note: The git commit is detected with 'git rev-parse HEAD' unless SWEEP_GIT_COMMIT is set
+# export SWEEP_RUN_ID="sweep-$(date +%Y%m%d%H%M%S)"
+# export SWEEP_CLUSTER_NAME="p4d-8node"
+# export SWEEP_NOTES="Baseline with 4x buffer size"
+
+# Optionally write a Markdown report of the sweep (configs, results, and pass/fail counts)
+# export MARKDOWN_REPORT_PATH="${EXPERIMENTS_OUTPUT_DIR}/report.$(date +%Y%m%d%H%M%S).md"
+
+# Optionally push bus bandwidth metrics to a Prometheus pushgateway after each experiment (unset to disable)
+# export PUSHGATEWAY_URL="http://localhost:9091"
+
+# Optionally run a tiny throwaway all-reduce before the sweep so the first experiments don't pay one-time setup costs
+# export CLUSTER_WARMUP=TRUE
+
+# Whether MSCCL also generates XML files ('GENMSCCLXML=1') next to the harness's own 'MSCCL_XML_FILES' (defaults to true)
+# Note: Set to false to skip the redundant generation; it is still requested for experiments whose XML file is missing
+# export GEN_MSCCL_XML=FALSE
+
+# Optionally kill experiments that run longer than this many seconds in total, or print nothing for this many seconds
+# Note: The idle timeout resets with every line of output, so it catches hangs without killing slow large-message runs
+# export EXPERIMENT_TIMEOUT_SECS=1800
+# export EXPERIMENT_IDLE_TIMEOUT_SECS=300
+# export TIMEOUT_POLL_INTERVAL_MS=100  # How often the timeouts are checked
+
+# Optionally log a heartbeat (elapsed time and time since the last output) this often while an experiment runs
+# export HEARTBEAT_INTERVAL_SECS=60
+
+# Optionally warn when an experiment fell back from EFA to sockets even though EFA_PATH/AWS_OFI_NCCL_PATH are set
+# (needs NCCL_DEBUG=INFO)
+# export CHECK_EFA_TRANSPORT=TRUE
+
+# Optionally write a compact JSON summary of each experiment's bus bandwidths (peak, peak size, mean, min, and max for
+# out-of-place and in-place) in a "*.summary.json" file next to its log
+# export SUMMARY_JSON=TRUE
+
+# Optionally export the per-iteration timings of each experiment as a time series per message size ("*.iterations.csv"
+# next to its log) to study jitter. Needs an nccl-tests build that prints "# iter <n> size <bytes> inplace <0|1> time <us>"
+# lines (upstream only prints averages); pass its flag through EXTRA_TEST_ARGS
+# export ITERATION_TIMESERIES=TRUE
+
+# Optionally summarize the per-rank topology (rings, trees, connections, and NICs) NCCL reports at NCCL_DEBUG=INFO
+# in a "*.topology.json" file next to each experiment's log
+# export TOPOLOGY_SUMMARY=TRUE
+
+# Kill an experiment as soon as its output contains one of these (comma-separated) patterns instead of waiting for it
+# to end or time out (defaults to NCCL's 'invalid usage', 'unhandled cuda error', 'unhandled system error', and
+# 'internal error' failures; set to "none" to disable)
+# export FATAL_ERROR_PATTERNS="'invalid usage','unhandled cuda error',NCCL WARN"
+
+# Optionally collect the params, exit code, timeout status, and stderr tail of every failed experiment in "${EXPERIMENTS_OUTPUT_DIR}/errors.log"
+# export ERRORS_LOG=TRUE
+# Optionally change how many lines at the end of stderr are kept in memory for that tail (default: 200; bounds the
+# harness's memory on runaway output, the log and stderr files still get every line)
+# export STDERR_TAIL_LINES=200
+
+# How often each config is repeated (defaults to 2)
+# export NUM_REPETITIONS=1
+
+# Optionally change how the tokens of output file names are joined (default: "_"), and leave out the iteration token
+# (only allowed with a single repetition per config, i.e., NUM_REPETITIONS=1)
+# export OUTPUT_FILENAME_SEPARATOR="-"
+# export OUTPUT_FILENAME_ITERATION=FALSE
+
+# How often to retry an experiment after a hang (needs one of the timeouts) or a crash (both default to 0)
+# export HANG_RETRIES=1
+# export CRASH_RETRIES=0
+
+# Optionally run up to this many repetitions of a config at once, each on its own slice of the hostfile (defaults to 1)
+# Note: Needs NUM_NODES spare hosts in MPI_HOSTFILE per extra repetition; the slices are written to "${EXPERIMENTS_OUTPUT_DIR}/hostfiles"
+# export PARALLEL_REPETITIONS=2
+
+# Optionally bind MPI processes to CPUs for reproducible placement (none, core, socket, or numa; defaults to none)
+# export PROCESS_BINDING=numa
+
+# Optionally sweep treating each GPU as its own node (needs both the gan0 and gan1 XML files; uses one process per GPU)
+# export GPUS_AS_NODES="false,true"
+
+# Optionally append extra arguments to the NCCL tests command line (must not collide with flags the harness sets; known
+# incompatible combinations, e.g., "-z 1" with CUDA graphs, are rejected before launching, see `INCOMPATIBLE_TEST_FLAGS`)
+# export EXTRA_TEST_ARGS="-R 1 -N 10"
+
+# Optionally forward extra site-specific envvars to every rank (comma-separated names; values are read from this environment)
+# export PASSTHROUGH_ENV_VARS="NCCL_SOCKET_IFNAME,NCCL_IB_HCA"
+
+# Optionally restrict the GPUs every rank sees (set as CUDA_VISIBLE_DEVICES on every rank; indices or UUIDs). Must list a
+# device for every GPU the ranks on a node drive (MPI_PROC_PER_NODE x GPUs per process)
+# export NCCL_TESTS_VISIBLE_DEVICES="2,3"
+
+# Optionally let the launcher validate every launch command during a dry run (e.g., hostfile and mapping syntax)
+# export DRY_RUN_VALIDATE=TRUE
+
+# Optionally show a live progress bar with an ETA (only when running in a terminal)
+# export PROGRESS_BAR=TRUE
+
+# Optionally compare results against the theoretical peak (GB/s; NVLink per GPU, network per node; set both)
+# export NVLINK_BW_GBPS=300
+# export NIC_BW_GBPS=50
+# Optionally change how far (as a fraction) a bus bandwidth may exceed that peak before the experiment is marked suspect
+# export PEAK_BW_MARGIN=0.05
+
+# Optionally fail experiments whose peak bus bandwidth (GB/s) is below a minimum (per collective, optionally at one size)
+# export MIN_BUS_BW_GATES="all-reduce=150,all-reduce@1G=120,*=40"
+
 # Print commands
 set -x
 
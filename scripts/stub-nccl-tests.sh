@@ -0,0 +1,94 @@
+#!/bin/sh
+# Stub of an nccl-tests executable for `nccl_harness self-test` (no GPUs, MPI, or NCCL needed)
+#
+# Prints canned output in the nccl-tests format for the message sizes it is asked for, so that the harness's launching,
+# parsing, and bookkeeping can be exercised anywhere. Installed under every nccl-tests executable name (e.g.,
+# `all_reduce_perf`); the bandwidths are made up.
+
+name=$(basename "$0")
+
+min_bytes=65536
+max_bytes=1048576
+step_factor=2
+step_bytes=
+num_threads=1
+num_gpus=1
+iters=20
+warmup_iters=5
+dtype=float
+op=sum
+
+# Convert a size with an optional K/M/G suffix to bytes
+to_bytes() {
+    case "$1" in
+        *K|*k) echo $(( ${1%?} * 1024 )) ;;
+        *M|*m) echo $(( ${1%?} * 1024 * 1024 )) ;;
+        *G|*g) echo $(( ${1%?} * 1024 * 1024 * 1024 )) ;;
+        *) echo "$1" ;;
+    esac
+}
+
+while [ $# -gt 0 ]; do
+    case "$1" in
+        -h|--help)
+            echo "USAGE: ${name}"
+            echo "    [-t,--nthreads <num threads>]"
+            echo "    [-g,--ngpus <gpus per thread>]"
+            echo "    [-b,--minbytes <min size in bytes>]"
+            echo "    [-e,--maxbytes <max size in bytes>]"
+            echo "    [-i,--stepbytes <increment size>]"
+            echo "    [-f,--stepfactor <increment factor>]"
+            echo "    [-n,--iters <iteration count>]"
+            echo "    [-w,--warmup_iters <warmup iteration count>]"
+            echo "    [-o,--op <sum/prod/min/max/avg/all>]"
+            echo "    [-d,--datatype <nccltype/all>]"
+            exit 0
+            ;;
+        -b|--minbytes) min_bytes=$(to_bytes "$2"); shift ;;
+        -e|--maxbytes) max_bytes=$(to_bytes "$2"); shift ;;
+        -f|--stepfactor) step_factor=$2; step_bytes=; shift ;;
+        -i|--stepbytes) step_bytes=$(to_bytes "$2"); shift ;;
+        -t|--nthreads) num_threads=$2; shift ;;
+        -g|--ngpus) num_gpus=$2; shift ;;
+        -n|--iters) iters=$2; shift ;;
+        -w|--warmup_iters) warmup_iters=$2; shift ;;
+        -d|--datatype) dtype=$2; shift ;;
+        -o|--op) op=$2; shift ;;
+        *) ;;
+    esac
+    shift
+done
+
+# Only reducing collectives have a reduction op column
+case "$name" in
+    all_reduce_perf|reduce_perf|reduce_scatter_perf) redop=$op ;;
+    *) redop=none ;;
+esac
+
+echo "# nThread ${num_threads} nGpus ${num_gpus} minBytes ${min_bytes} maxBytes ${max_bytes} step: ${step_bytes:-${step_factor}(factor)} warmup iters: ${warmup_iters} iters: ${iters} agg iters: 1 validation: 1 graph: 0"
+echo "#"
+echo "# Using devices"
+echo "#  Rank  0 Group  0 Pid      1 on  localhost device  0 [0x00] Stub GPU"
+echo "#"
+echo "#                                                              out-of-place                       in-place          "
+echo "#       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong"
+echo "#        (B)    (elements)                               (us)  (GB/s)  (GB/s)            (us)  (GB/s)  (GB/s)       "
+
+size=$min_bytes
+while [ "$size" -le "$max_bytes" ]; do
+    # Made-up timings that grow with the size (so the bandwidth grows too)
+    time_us=$(( 10 + size / 100000 ))
+    algbw=$(( size / time_us / 1000 ))
+    echo "$size $((size / 4)) $dtype $redop -1 $time_us.0 $algbw.00 $algbw.50 0 $time_us.5 $algbw.00 $algbw.40 0" \
+        | awk '{ printf "%12s  %12s  %8s  %6s  %6s  %7s  %6s  %6s  %6s  %7s  %6s  %6s  %6s\n", $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13 }'
+
+    if [ -n "$step_bytes" ]; then
+        size=$(( size + step_bytes ))
+    else
+        size=$(( size * step_factor ))
+    fi
+done
+
+echo "# Out of bounds values : 0 OK"
+echo "# Avg bus bandwidth    : 1.0 "
+echo "#"
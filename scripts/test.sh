@@ -30,4 +30,41 @@ export SKIP_FINISHED=FALSE
 
 # ./target/release/test_ideas
 cargo build --features no_check_paths
+
+# Check the setup without running anything (uncomment to only validate)
+# ./target/debug/nccl_harness --validate-only
+
+# Read the envvars from a file instead of exporting them here (envvars exported here take precedence)
+# ./target/debug/nccl_harness --env-file ./env.sh
+
+# Don't mirror the output of the NCCL tests to stdout even if TEE_STDOUT is set
+# ./target/debug/nccl_harness --quiet
+
+# Explain why each permutation is generated or dropped (chunk/channel rules, missing XML files, blacklist, op rules)
+# ./target/debug/nccl_harness --explain
+
+# Stop at the first failed experiment (useful when debugging a broken setup)
+# ./target/debug/nccl_harness --fail-fast
+
+# Resume a sweep that died partway through by skipping the permutations that already ran (0-based index of the first to run)
+# ./target/debug/nccl_harness --start-index 412
+
+# Override the collectives, reduction ops, and data types to sweep for a quick one-off run (each flag is repeatable)
+# ./target/debug/nccl_harness --collective all-reduce --collective all-gather --op sum --dtype float --dtype half
+
+# Profile a single permutation (by its index in the printed list) with Nsight Systems instead of running the sweep
+# Note: Reports and logs go to "${EXPERIMENTS_OUTPUT_DIR}/profile"; set NSYS_ARGS to change what nsys traces
+# ./target/debug/nccl_harness --profile 3
+
+# Rerun only the failed configs of a prior sweep (uncomment to use the manifest written by the last sweep)
+# ./target/debug/nccl_harness rerun-failed "${EXPERIMENTS_OUTPUT_DIR}/manifest.json"
+
+# Replay exactly one experiment from its params (written as '*.params.json' next to the logs of failed configs)
+# Note: Results go to "${EXPERIMENTS_OUTPUT_DIR}/replay"
+# ./target/debug/nccl_harness replay "${EXPERIMENTS_OUTPUT_DIR}/<config>.params.json"
+
+# Check the harness itself by running a small sweep against a stub NCCL tests executable (scripts/stub-nccl-tests.sh)
+# Note: Needs no GPUs, MPI, or NCCL; sets up its own paths in a temp dir and exits non-zero if any experiment failed
+# ./target/debug/nccl_harness self-test
+
 ./target/debug/nccl_harness 2>&1 | tee "${LOGS_DIR}/dry_run.$(date +%Y%m%d%H%M%S).log"
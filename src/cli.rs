@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+
+/// Run sweeps of NCCL tests (with MSCCL algorithms) and collect their results
+///
+/// Note: The sweep itself is configured with envvars; the flags select what to run (and how much of it).
+#[derive(Debug, Parser)]
+#[command(name = "nccl_harness")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+
+    /// Load envvars from a dotenv-style file (explicitly set envvars take precedence)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub env_file: Option<PathBuf>,
+
+    /// Check the setup and exit without running any experiments
+    #[arg(long, global = true)]
+    pub validate_only: bool,
+
+    /// Print the resolved configuration as JSON and exit
+    #[arg(long, global = true)]
+    pub print_config: bool,
+
+    /// Stop the sweep at the first failed experiment
+    #[arg(long, global = true)]
+    pub fail_fast: bool,
+
+    /// Explain why each permutation is generated or dropped
+    #[arg(long, global = true)]
+    pub explain: bool,
+
+    /// Don't mirror the output of the NCCL tests to stdout (overrides 'TEE_STDOUT')
+    #[arg(long, global = true)]
+    pub quiet: bool,
+
+    /// Skip the first N permutations (to resume a sweep)
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    pub start_index: usize,
+
+    /// Only run permutation N once, wrapped with 'nsys profile'
+    #[arg(long, global = true, value_name = "N")]
+    pub profile: Option<usize>,
+
+    /// Only sweep the given collective (repeatable, e.g., '--collective all-reduce --collective all-gather')
+    #[arg(long = "collective", global = true, value_name = "COLLECTIVE")]
+    pub collectives: Vec<String>,
+
+    /// Only sweep the given reduction op (repeatable)
+    #[arg(long = "op", global = true, value_name = "OP")]
+    pub ops: Vec<String>,
+
+    /// Only sweep the given data type (repeatable)
+    #[arg(long = "dtype", global = true, value_name = "DTYPE")]
+    pub dtypes: Vec<String>,
+}
+
+/// Modes other than running the full sweep
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Run a small sweep against a stub NCCL tests executable (no GPUs, MPI, or NCCL needed)
+    SelfTest,
+
+    /// List the XML files the harness expects
+    ListXmls {
+        /// Also report whether each XML file exists
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Only rerun the failed configs of a prior sweep
+    RerunFailed {
+        /// The manifest of the prior sweep
+        manifest: PathBuf,
+    },
+
+    /// Only run the experiment with the given params (as written for failed configs)
+    Replay {
+        /// The params of the experiment
+        params: PathBuf,
+    },
+}
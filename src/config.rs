@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use serde::Serialize;
+
+use crate::environment::EnvPaths;
+use crate::launcher::ProcessBinding;
+#[cfg(feature = "dataframe")]
+use crate::parse::BandwidthUnit;
+use crate::stats::BandwidthGate;
+use crate::theoretical::HardwareSpec;
+use crate::util::{CollectiveAlgorithms, ExperimentOrder, FilenameFormat, IterRegime, SweepMetadata};
+use crate::wrapper::{RetryPolicy, Timeouts};
+
+/// The configuration a sweep runs with, after envvars, CLI overrides, and defaults are resolved (see `--print-config`)
+///
+/// Note: Every field holds the resolved value itself (not its `Debug` form), so the JSON can be diffed between
+///       clusters and read back by other tools.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentConfig {
+    pub env_paths: EnvPaths,
+    pub metadata: SweepMetadata,
+
+    // The sweep
+    pub num_repetitions: usize,
+    pub collectives: Vec<String>,
+    pub reduction_ops: Vec<String>,
+    pub data_types: Vec<String>,
+    pub comm_algorithms: Vec<String>,
+    pub collective_algorithms: Vec<CollectiveAlgorithms>,
+    pub buffer_sizes: Vec<u64>,
+    pub message_size_range: (String, String),
+    pub message_size_step_factor: Option<String>,
+    pub message_size_step_bytes: Option<String>,
+    pub num_iters: u64,
+    pub num_warmup_iters: u64,
+    pub test_timeout_secs: Option<u64>,
+    pub iteration_regimes: Vec<IterRegime>,
+    pub gpus_as_nodes: Vec<bool>,
+    pub threads_per_process: Vec<u64>,
+    pub nccl_protos: Vec<Option<String>>,
+    pub nccl_nchannels: Vec<(Option<u64>, Option<u64>)>,
+    pub roots: Vec<u64>,
+    pub cudagraph_launches: Vec<Option<u64>>,
+    pub procs_per_node_overrides: Vec<Option<u64>>,
+    pub blacklist: Vec<String>,
+    pub nccl_debug_level: String,
+
+    // How the experiments are run
+    pub launcher: String,
+    pub experiment_order: ExperimentOrder,
+    pub ld_library_path_order: Vec<String>,
+    pub extra_test_args: Vec<String>,
+    pub passthrough_env_vars: Vec<String>,
+    pub cuda_visible_devices: Option<Vec<String>>,
+    pub mpi_binding: ProcessBinding,
+    pub ms_gen_xml: bool,
+    pub timeouts: Timeouts,
+    pub retry_policy: RetryPolicy,
+    pub fatal_error_patterns: Vec<String>,
+    pub stderr_tail_lines: usize,
+    pub parallel_repetitions: u64,
+    pub cluster_warmup: bool,
+    pub skip_warmup_repetition: bool,
+    pub dry_run: bool,
+    pub dry_run_validate: bool,
+    pub skip_finished: bool,
+    pub fail_fast: bool,
+    pub start_index: usize,
+    pub profile_index: Option<usize>,
+    pub nsys_args: Vec<String>,
+    pub skip_missing_xmls: bool,
+    pub skip_missing_xmls_at_run_time: bool,
+    pub nccl_fallback_for_missing_xmls: bool,
+
+    // How the results are checked
+    pub hardware_spec: Option<HardwareSpec>,
+    pub peak_margin: f64,
+    pub bandwidth_gates: Vec<BandwidthGate>,
+    pub anomaly_bw_fraction: Option<f64>,
+    pub inplace_divergence_ratio: Option<f64>,
+    pub check_row_count: bool,
+    pub check_efa_transport: bool,
+
+    // What is written
+    pub output_filename_format: FilenameFormat,
+    pub nested_output_dirs: bool,
+    pub tee_stdout: bool,
+    pub summary_json: bool,
+    pub log_index: bool,
+    pub per_size_csv: bool,
+    pub errors_log: bool,
+    pub nccl_table_output: bool,
+    pub iteration_timeseries: bool,
+    pub topology_summary: bool,
+    pub sample_power: bool,
+    pub markdown_report_path: Option<PathBuf>,
+    pub parquet_output_path: Option<PathBuf>,
+    #[cfg(feature = "dataframe")]
+    pub result_columns: Option<Vec<String>>,
+    #[cfg(feature = "dataframe")]
+    pub bandwidth_unit: BandwidthUnit,
+    pub sqlite_output_path: Option<PathBuf>,
+    pub pushgateway_url: Option<String>,
+}
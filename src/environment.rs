@@ -0,0 +1,278 @@
+use std::path::PathBuf;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::launcher::NetPlugin;
+use crate::util::probe_gpus_per_node;
+use crate::wrapper::HarnessError;
+
+/// An NCCL tests build to sweep: its name (only set with `NCCL_TESTS_BUILDS`) and the directory of its executables
+pub type NcclTestBuild = (Option<String>, PathBuf);
+
+/// A group of nodes to sweep (e.g., a rack): its name and the hostfile that lists its nodes
+pub type HostGroup = (String, PathBuf);
+
+/// The paths and cluster topology the harness runs with (from the envvars set by, e.g., `scripts/run-aws.sh`)
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvPaths {
+    pub cuda_path: String,
+    pub efa_path: Option<String>,
+    pub aws_ofi_nccl_path: Option<String>,
+    pub openmpi_path: String,
+    pub msccl_path: String,
+    pub nccl_test_builds: Vec<NcclTestBuild>,
+    pub nccl_net_plugins: Vec<NetPlugin>, // Swept in addition to the builds (empty to use `AWS_OFI_NCCL_PATH`'s plugin)
+    pub msccl_xmls_directory: PathBuf,
+    pub mpi_hostfile_path: PathBuf, // The first group's hostfile if `host_groups` is set
+    pub host_groups: Vec<HostGroup>, // Every experiment runs on each group (empty to just use `mpi_hostfile_path`)
+    pub num_nodes: u64,
+    pub gpus_per_node: u64,
+    pub gpus_per_node_probed: bool, // `GPUS_PER_NODE` was unset (or `auto`), so it was detected on this node
+    pub mpi_proc_per_node: u64,
+    pub experiments_output_dir: PathBuf,
+}
+
+/// Get a configuration error with the given reason
+fn config_error(reason: String) -> HarnessError {
+    HarnessError::Config { reason }
+}
+
+/// Get a required envvar
+fn required_var(name: &str) -> Result<String, HarnessError> {
+    match std::env::var(name) {
+        Ok(v) => {
+            debug!("{} set to: {}", name, v);
+            Ok(v)
+        }
+        Err(_) => Err(config_error(format!("Envvar {} not set!", name))),
+    }
+}
+
+/// Get a required envvar that holds a count
+fn required_count(name: &str) -> Result<u64, HarnessError> {
+    let v = required_var(name)?;
+    v.parse::<u64>().map_err(|e| config_error(format!("Invalid {} value '{}': {}", name, v, e)))
+}
+
+/// Get an optional path envvar that the EFA tests need (warns if it is unset)
+fn efa_var(name: &str) -> Option<String> {
+    match std::env::var(name) {
+        Ok(v) => {
+            debug!("{} set to: {}", name, v);
+            Some(v)
+        }
+        Err(_) => {
+            warn!("{} was not set! You will not be able to run tests that use the EFA!", name);
+            None
+        }
+    }
+}
+
+/// Parse a list of named paths ("name=/path,name=/path", e.g., `NCCL_TESTS_BUILDS`)
+///
+/// Note: The names end up in file names, so they must be unique and must not contain the `_` separator.
+///
+/// # Arguments
+/// * `envvar` - The name of the envvar (for error messages)
+/// * `value` - The value of the envvar
+/// * `example` - An example path (for error messages)
+fn parse_named_paths(envvar: &str, value: &str, example: &str) -> Result<Vec<(String, PathBuf)>, HarnessError> {
+    let mut paths: Vec<(String, PathBuf)> = Vec::new();
+    for entry in value.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let (name, path) = match entry.split_once('=') {
+            Some((name, path)) if !name.trim().is_empty() && !path.trim().is_empty() => (name.trim(), path.trim()),
+            _ => return Err(config_error(format!("Invalid {} entry '{}', expected 'name={}'!", envvar, entry, example))),
+        };
+        if name.contains('_') {
+            return Err(config_error(format!("{} name '{}' must not contain '_' (it is used as the file name separator)!", envvar, name)));
+        }
+        if paths.iter().any(|(n, _)| n == name) {
+            return Err(config_error(format!("Duplicate {} name '{}'!", envvar, name)));
+        }
+        paths.push((name.to_string(), PathBuf::from(path)));
+    }
+    if paths.is_empty() {
+        return Err(config_error(format!("Envvar {} is set but contains no entries!", envvar)));
+    }
+
+    Ok(paths)
+}
+
+/// Get the number of MPI processes per node (`MPI_PROC_PER_NODE`, defaults to one process per GPU)
+fn proc_per_node_var(gpus_per_node: u64) -> Result<u64, HarnessError> {
+    match std::env::var("MPI_PROC_PER_NODE") {
+        Ok(_) => required_count("MPI_PROC_PER_NODE"),
+        Err(_) => {
+            debug!("MPI_PROC_PER_NODE not set, will use one process per GPU.");
+            Ok(gpus_per_node)
+        }
+    }
+}
+
+impl EnvPaths {
+    /// Read the paths and topology from the environment
+    ///
+    /// Note: `GPUS_PER_NODE` is detected with `nvidia-smi -L` if unset (or `auto`), and `MPI_PROC_PER_NODE` defaults to
+    ///       one process per GPU. The EFA/OFI paths are optional (a warning is logged if unset).
+    ///
+    /// # Arguments
+    /// * `check_hostfile` - Fail if the hostfile doesn't exist (unless built with `no_check_paths`)
+    pub fn from_env(check_hostfile: bool) -> Result<EnvPaths, HarnessError> {
+        let cuda_path = required_var("CUDA_HOME")?;
+        let efa_path = efa_var("EFA_PATH");
+        let aws_ofi_nccl_path = efa_var("AWS_OFI_NCCL_PATH");
+        let openmpi_path = required_var("OPENMPI_PATH")?;
+        let msccl_path = required_var("MSCCL_PATH")?;
+
+        // NCCL tests executable binary location(s). `NCCL_TESTS_BUILDS` sweeps several builds and tags each result
+        // with its build name; otherwise the single `NCCL_TESTS_HOME` build is used untagged.
+        let nccl_test_builds = match std::env::var("NCCL_TESTS_BUILDS") {
+            Ok(v) => {
+                debug!("NCCL_TESTS_BUILDS set to: {}", v);
+                parse_named_paths("NCCL_TESTS_BUILDS", v.as_str(), "/path/to/nccl-tests/build")?
+                    .into_iter()
+                    .map(|(name, dir)| (Some(name), dir))
+                    .collect()
+            }
+            Err(_) => vec![(None, PathBuf::from(required_var("NCCL_TESTS_HOME")?))],
+        };
+
+        // NCCL net plugin builds to A/B ("name=/path/to/libnccl-net.so,..."; each replaces the plugin in AWS_OFI_NCCL_PATH)
+        let nccl_net_plugins = match std::env::var("NCCL_NET_PLUGINS") {
+            Ok(v) => {
+                debug!("NCCL_NET_PLUGINS set to: {}", v);
+                let plugins = parse_named_paths("NCCL_NET_PLUGINS", v.as_str(), "/path/to/lib/libnccl-net.so")?
+                    .into_iter()
+                    .map(|(name, library)| NetPlugin { name, library })
+                    .collect::<Vec<NetPlugin>>();
+                for plugin in plugins.iter() {
+                    if !cfg!(feature = "no_check_paths") && !plugin.library.is_file() {
+                        return Err(config_error(format!("NCCL net plugin '{}' not found at: {:?}", plugin.name, plugin.library)));
+                    }
+                }
+                plugins
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let msccl_xmls_directory = PathBuf::from(required_var("MSCCL_XMLS")?);
+
+        // Node groups to compare ("name=/path/to/hostfile,..."; replaces MPI_HOSTFILE)
+        let host_groups = match std::env::var("MPI_HOSTFILES") {
+            Ok(v) => {
+                debug!("MPI_HOSTFILES set to: {}", v);
+                parse_named_paths("MPI_HOSTFILES", v.as_str(), "/path/to/hostfile")?
+            }
+            Err(_) => Vec::new(),
+        };
+        for (name, hostfile) in host_groups.iter() {
+            if check_hostfile && !cfg!(feature = "no_check_paths") && !hostfile.exists() {
+                return Err(config_error(format!("Hostfile of node group '{}' not found at: {:?}", name, hostfile)));
+            }
+        }
+
+        let mpi_hostfile_path = match host_groups.first() {
+            Some((_, hostfile)) => hostfile.clone(),
+            None => PathBuf::from(required_var("MPI_HOSTFILE")?),
+        };
+        if check_hostfile && !cfg!(feature = "no_check_paths") && !mpi_hostfile_path.exists() {
+            return Err(config_error(format!("Envvar MPI_HOSTFILE not found at: {}", mpi_hostfile_path.to_str().unwrap())));
+        }
+
+        let num_nodes = required_count("NUM_NODES")?;
+
+        // GPUs per Node (probed with 'nvidia-smi -L' on this node if unset or 'auto')
+        let (gpus_per_node, gpus_per_node_probed) = match std::env::var("GPUS_PER_NODE") {
+            Ok(v) if v.to_lowercase() != "auto" => (required_count("GPUS_PER_NODE")?, false),
+            _ => {
+                let count = probe_gpus_per_node()
+                    .map_err(|e| config_error(format!("GPUS_PER_NODE not set (or 'auto') and could not be detected: {}", e)))?;
+                info!("GPUS_PER_NODE not set (or 'auto'), detected {} GPU(s) on this node with 'nvidia-smi -L'.", count);
+                (count, true)
+            }
+        };
+
+        let mpi_proc_per_node = proc_per_node_var(gpus_per_node)?;
+
+        // Experiments Output Directory (see `create_output_dir`)
+        let experiments_output_dir = PathBuf::from(required_var("EXPERIMENTS_OUTPUT_DIR")?);
+
+        Ok(EnvPaths {
+            cuda_path,
+            efa_path,
+            aws_ofi_nccl_path,
+            openmpi_path,
+            msccl_path,
+            nccl_test_builds,
+            nccl_net_plugins,
+            msccl_xmls_directory,
+            mpi_hostfile_path,
+            host_groups,
+            num_nodes,
+            gpus_per_node,
+            gpus_per_node_probed,
+            mpi_proc_per_node,
+            experiments_output_dir,
+        })
+    }
+
+    /// Create the output directory if it doesn't exist
+    ///
+    /// Note: Separate from `from_env`, so that the configuration can be resolved without touching the disk
+    pub fn create_output_dir(&self) -> Result<(), HarnessError> {
+        if !self.experiments_output_dir.exists() {
+            std::fs::create_dir(self.experiments_output_dir.as_path()).map_err(|e| {
+                config_error(format!("Could not create EXPERIMENTS_OUTPUT_DIR at {:?}: {}", self.experiments_output_dir, e))
+            })?;
+            debug!("Created experiment log output directory at: {:?}", self.experiments_output_dir);
+        } else {
+            debug!("Experiment log output directory already exists at: {:?}", self.experiments_output_dir);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_paths_are_parsed_in_order() {
+        let paths = parse_named_paths("NCCL_TESTS_BUILDS", " old=/opt/old , new=/opt/new,", "/path").unwrap();
+        let expected = vec![("old".to_string(), PathBuf::from("/opt/old")), ("new".to_string(), PathBuf::from("/opt/new"))];
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let result = parse_named_paths("NCCL_TESTS_BUILDS", "a=/opt/a,a=/opt/b", "/path");
+        assert!(matches!(result, Err(HarnessError::Config { reason }) if reason.contains("Duplicate")));
+    }
+
+    #[test]
+    fn names_with_the_separator_are_rejected() {
+        let result = parse_named_paths("MPI_HOSTFILES", "rack_1=/etc/hosts", "/path");
+        assert!(matches!(result, Err(HarnessError::Config { reason }) if reason.contains("must not contain '_'")));
+    }
+
+    #[test]
+    fn empty_names_paths_and_lists_are_rejected() {
+        for value in ["=/opt/a", "a=", "a", "", " , "] {
+            let result = parse_named_paths("NCCL_NET_PLUGINS", value, "/path");
+            assert!(matches!(result, Err(HarnessError::Config { .. })), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn proc_per_node_defaults_to_one_per_gpu() {
+        // The only test that touches this envvar (tests run in parallel)
+        std::env::remove_var("MPI_PROC_PER_NODE");
+        assert_eq!(proc_per_node_var(8).unwrap(), 8);
+        std::env::set_var("MPI_PROC_PER_NODE", "2");
+        assert_eq!(proc_per_node_var(8).unwrap(), 2);
+        std::env::set_var("MPI_PROC_PER_NODE", "two");
+        assert!(matches!(proc_per_node_var(8), Err(HarnessError::Config { .. })));
+        std::env::remove_var("MPI_PROC_PER_NODE");
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use crate::{Row, ManifestEntry, MscclExperimentParams};
+use crate::parse::IterationTiming;
+
+#[cfg(feature = "dataframe")]
+use std::fs::File;
+#[cfg(feature = "dataframe")]
+use polars::prelude::*;
+#[cfg(feature = "dataframe")]
+use polars::io::parquet::BatchedWriter;
+#[cfg(feature = "dataframe")]
+use tracing::{debug, info};
+#[cfg(feature = "dataframe")]
+use crate::util::create_output_file;
+#[cfg(feature = "dataframe")]
+use crate::parse::{add_bandwidth_unit_columns, rows_to_df, select_row_columns, BandwidthUnit};
+
+/// Convert the rows of a single experiment to a DataFrame, adding columns that identify the experiment
+///
+/// # Arguments
+/// * `params` - The experiment parameters the rows were collected with
+/// * `iteration` - The repetition the rows were collected in
+/// * `rows` - The parsed rows of the NCCL output table
+/// * `unit` - The unit of the added bandwidth columns with explicit unit suffixes
+/// * `columns` - The `Row` columns to keep (all of them if `None`; the columns identifying the experiment are always added)
+#[cfg(feature = "dataframe")]
+pub fn experiment_df(
+    params: &MscclExperimentParams,
+    iteration: u64,
+    rows: Vec<Row>,
+    unit: BandwidthUnit,
+    columns: Option<&[String]>,
+) -> Result<DataFrame, Box<dyn std::error::Error>> {
+    let num_rows = rows.len();
+    let mut df = rows_to_df(rows)?;
+    if let Some(columns) = columns {
+        df = select_row_columns(df, columns)?;
+    }
+    let mut df = add_bandwidth_unit_columns(df, unit)?;
+
+    df.with_column(Series::new("collective", vec![params.nc_collective.clone(); num_rows]))?;
+    df.with_column(Series::new("algorithm", vec![params.algorithm.clone(); num_rows]))?;
+    df.with_column(Series::new("channels", vec![params.ms_channels; num_rows]))?;
+    df.with_column(Series::new("chunks", vec![params.ms_chunks; num_rows]))?;
+    df.with_column(Series::new("gpus", vec![params.total_gpus; num_rows]))?;
+    df.with_column(Series::new("buffer_size", vec![params.buffer_size; num_rows]))?;
+    df.with_column(Series::new("iteration", vec![iteration; num_rows]))?;
+    if let Some(build) = &params.nc_build {
+        // Either every config of a sweep has a build name or none does, so the schema stays the same across a sweep
+        df.with_column(Series::new("build", vec![build.clone(); num_rows]))?;
+    }
+    if let Some(plugin) = &params.nccl_net_plugin {
+        // Same as the build: either every config of a sweep has a net plugin or none does
+        df.with_column(Series::new("net_plugin", vec![plugin.name.clone(); num_rows]))?;
+    }
+    if let Some(group) = &params.mpi_host_group {
+        // Same as the build: either every config of a sweep has a host group or none does
+        df.with_column(Series::new("host_group", vec![group.clone(); num_rows]))?;
+    }
+
+    Ok(df)
+}
+
+/// Exports the results of a sweep to a Parquet file
+///
+/// Note: In incremental mode, every DataFrame is written as its own row group as soon as it is given, so memory use
+///       stays bounded no matter how large the sweep is. In single-shot mode, the DataFrames are kept in memory and
+///       written all at once when finished (simpler, but only suitable for small sweeps).
+#[cfg(feature = "dataframe")]
+pub enum ParquetExporter {
+    Incremental {
+        path: PathBuf,
+        writer: Option<Box<BatchedWriter<File>>>, // Boxed because the writer is large
+    },
+    SingleShot {
+        path: PathBuf,
+        frames: Vec<DataFrame>,
+    },
+}
+
+#[cfg(feature = "dataframe")]
+impl ParquetExporter {
+    pub fn new(path: &Path, incremental: bool) -> ParquetExporter {
+        if incremental {
+            ParquetExporter::Incremental { path: path.to_path_buf(), writer: None }
+        } else {
+            ParquetExporter::SingleShot { path: path.to_path_buf(), frames: Vec::new() }
+        }
+    }
+
+    /// Add the DataFrame of an experiment to the export
+    pub fn write(&mut self, mut df: DataFrame) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ParquetExporter::Incremental { path, writer } => {
+                // Create the writer on the first batch (it needs the schema)
+                if writer.is_none() {
+                    debug!("Creating incremental Parquet writer at: {:?}", path);
+                    *writer = Some(Box::new(ParquetWriter::new(create_output_file(path.as_path())?).batched(&df.schema())?));
+                }
+
+                // Note: The batched writer requires aligned chunks
+                df.align_chunks();
+                writer.as_mut().unwrap().write_batch(&df)?;
+            }
+            ParquetExporter::SingleShot { frames, .. } => {
+                frames.push(df);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish writing the Parquet file
+    pub fn finish(self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            ParquetExporter::Incremental { path, writer } => {
+                match writer {
+                    Some(writer) => {
+                        let size = writer.finish()?;
+                        info!("Finished writing {} bytes of results to Parquet file at: {:?}", size, path);
+                    }
+                    None => info!("No results to write to Parquet file at: {:?}", path),
+                }
+            }
+            ParquetExporter::SingleShot { path, frames } => {
+                let mut frames = frames.into_iter();
+                let mut df = match frames.next() {
+                    Some(v) => v,
+                    None => {
+                        info!("No results to write to Parquet file at: {:?}", path);
+                        return Ok(());
+                    }
+                };
+                for frame in frames {
+                    df.vstack_mut(&frame)?;
+                }
+
+                let size = ParquetWriter::new(create_output_file(path.as_path())?).finish(&mut df)?;
+                info!("Finished writing {} bytes of results to Parquet file at: {:?}", size, path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Write the out-of-place bus bandwidth of every repetition of a config side by side as CSV
+///
+/// Note: There is one row per message size and one `rep<i>` column per repetition. Repetitions that are missing a size
+///       (e.g., because they failed early or were skipped) get an empty cell.
+///
+/// # Arguments
+/// * `repetition_rows` - The parsed rows of each repetition, along with the repetition's index
+/// * `num_repetitions` - The total number of repetitions of the config (one column each)
+/// * `path` - Where to write the CSV
+pub fn write_bus_bw_csv(
+    repetition_rows: &[(u64, Vec<Row>)],
+    num_repetitions: u64,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Pivot the rows into message size -> bus bandwidth of each repetition
+    let mut bus_bws: BTreeMap<u64, Vec<Option<f64>>> = BTreeMap::new();
+    for (i, rows) in repetition_rows {
+        for row in rows {
+            let cells = bus_bws.entry(row.size).or_insert_with(|| vec![None; num_repetitions as usize]);
+            if let Some(cell) = cells.get_mut(*i as usize) {
+                cell.get_or_insert(row.oop_bus_bw);
+            }
+        }
+    }
+
+    let mut csv = String::from("size");
+    for i in 0..num_repetitions {
+        write!(csv, ",rep{}", i)?;
+    }
+    csv.push('\n');
+    for (size, cells) in bus_bws.iter() {
+        write!(csv, "{}", size)?;
+        for cell in cells {
+            match cell {
+                Some(v) => write!(csv, ",{}", v)?,
+                None => csv.push(','),
+            }
+        }
+        csv.push('\n');
+    }
+
+    std::fs::write(path, csv)?;
+
+    Ok(())
+}
+
+/// Writes the per-iteration timings of a run as a time series per message size (one row per iteration)
+///
+/// Note: Rows are ordered by message size, then out-of-place before in-place, then iteration, so each size's series is
+///       contiguous. The bandwidth cells are empty if the build didn't print them.
+pub fn write_iteration_timeseries_csv(timings: &[IterationTiming], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut timings = timings.iter().collect::<Vec<&IterationTiming>>();
+    timings.sort_by_key(|t| (t.size, t.in_place, t.iteration));
+
+    let optional = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+    let mut csv = String::from("size,placement,iteration,time_us,alg_bw,bus_bw\n");
+    for timing in timings {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{}",
+            timing.size,
+            if timing.in_place { "ip" } else { "oop" },
+            timing.iteration,
+            timing.time,
+            optional(timing.alg_bw),
+            optional(timing.bus_bw)
+        )?;
+    }
+
+    std::fs::write(path, csv)?;
+
+    Ok(())
+}
+
+/// The params shown by `pretty_print_configs`, which lead the columns of the planned experiments CSV
+const PLANNED_LEADING_COLUMNS: [&str; 6] = ["nc_collective", "nc_op", "nc_dtype", "algorithm", "ms_channels", "ms_chunks"];
+
+/// Writes every field of the planned experiment configs to a CSV (one row per config, in run order)
+///
+/// Note: The columns are the serialized field names of `MscclExperimentParams` (the ones shown by
+///       `pretty_print_configs` first, then the rest alphabetically), so new fields show up without changes here. Fields
+///       that aren't plain values (e.g., lists) are written as JSON.
+///
+/// # Arguments
+/// * `configs` - The experiment configs of the sweep
+/// * `path` - Where to write the CSV
+pub fn write_planned_experiments_csv(configs: &[MscclExperimentParams], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let rows = configs
+        .iter()
+        .map(|config| match serde_json::to_value(config)? {
+            serde_json::Value::Object(fields) => Ok(fields),
+            _ => Err("experiment params did not serialize to an object".into()),
+        })
+        .collect::<Result<Vec<serde_json::Map<String, serde_json::Value>>, Box<dyn std::error::Error>>>()?;
+
+    let mut columns = PLANNED_LEADING_COLUMNS.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+    for row in rows.iter() {
+        for name in row.keys() {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+    }
+
+    let mut csv = String::from("index");
+    for column in columns.iter() {
+        write!(csv, ",{}", column)?;
+    }
+    csv.push('\n');
+    for (i, row) in rows.iter().enumerate() {
+        write!(csv, "{}", i)?;
+        for column in columns.iter() {
+            let cell = match row.get(column) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(v)) => v.clone(),
+                Some(v) => v.to_string(),
+            };
+            write!(csv, ",{}", csv_field(&cell))?;
+        }
+        csv.push('\n');
+    }
+
+    std::fs::write(path, csv)?;
+
+    Ok(())
+}
+
+/// Columns of the log index (see `LogIndex`)
+const LOG_INDEX_COLUMNS: [&str; 17] = [
+    "collective", "op", "dtype", "algorithm", "num_channels", "num_chunks", "num_gpus", "buffer_size_factor", "build",
+    "net_plugin", "host_group", "repetition", "result", "log", "stderr", "bus_bw_csv", "notes",
+];
+
+/// Index of where the output files of every experiment are (`index.csv`), for finding the log of a config
+///
+/// Note: Rows are appended as experiments complete (the file is reopened every time), so a crashed sweep still leaves
+///       a usable index. File paths are relative to the experiments output directory; they are empty for experiments
+///       that never ran (e.g., skipped for a missing XML file).
+pub struct LogIndex {
+    path: PathBuf,
+}
+
+impl LogIndex {
+    /// Start a new index (replacing any index of a previous sweep)
+    pub fn create(path: &Path) -> Result<LogIndex, Box<dyn std::error::Error>> {
+        std::fs::write(path, format!("{}\n", LOG_INDEX_COLUMNS.join(",")))?;
+
+        Ok(LogIndex { path: path.to_path_buf() })
+    }
+
+    /// Append the row of an experiment
+    ///
+    /// # Arguments
+    /// * `entry` - The manifest entry of the experiment
+    /// * `repetition` - The repetition the entry belongs to (`None` if the experiment never ran)
+    /// * `log` - The experiment's log file
+    /// * `stderr` - The experiment's stderr file
+    /// * `bus_bw_csv` - The per-size bus bandwidth CSV of the experiment's config (if written)
+    pub fn append(
+        &self,
+        entry: &ManifestEntry,
+        repetition: Option<u64>,
+        log: Option<&Path>,
+        stderr: Option<&Path>,
+        bus_bw_csv: Option<&Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path_cell = |p: Option<&Path>| p.map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        let cells = [
+            entry.collective.clone(),
+            entry.op.clone(),
+            entry.dtype.clone(),
+            entry.algorithm.clone(),
+            entry.num_channels.to_string(),
+            entry.num_chunks.to_string(),
+            entry.num_gpus.to_string(),
+            entry.buffer_size_factor.to_string(),
+            entry.build.clone().unwrap_or_default(),
+            entry.net_plugin.clone().unwrap_or_default(),
+            entry.host_group.clone().unwrap_or_default(),
+            repetition.map(|r| r.to_string()).unwrap_or_default(),
+            entry.overall_result.to_string(),
+            path_cell(log),
+            path_cell(stderr),
+            path_cell(bus_bw_csv),
+            entry.notes.join("; "),
+        ];
+        let line = cells.iter().map(|c| csv_field(c)).collect::<Vec<String>>().join(",");
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(self.path.as_path())?;
+        std::io::Write::write_all(&mut file, format!("{}\n", line).as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote, or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,745 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::MscclExperimentParams;
+use crate::wrapper::is_launcher_error;
+
+/// Builds the command that launches NCCL tests across the nodes of a cluster
+///
+/// Note: Launchers are shared between the threads that run repetitions in parallel, so they must be `Sync`
+pub trait TestLauncher: Sync {
+    /// Build the full launch command, including the NCCL tests executable and its arguments
+    fn build_command(&self, params: &MscclExperimentParams) -> Command;
+
+    /// Build a command that makes the launcher check the launch command (hostfile, mapping, etc.) without actually
+    /// starting the NCCL tests
+    fn build_validation_command(&self, params: &MscclExperimentParams) -> Command;
+}
+
+/// How the launched processes are bound to CPUs
+///
+/// Note: NUMA locality matters for NCCL benchmarks because it affects host-staging bandwidth
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProcessBinding {
+    None,
+    Core,
+    Socket,
+    Numa,
+}
+
+impl ProcessBinding {
+    /// Get a binding from its name (`none`, `core`, `socket`, or `numa`)
+    pub fn from_name(name: &str) -> Result<ProcessBinding, Box<dyn std::error::Error>> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(ProcessBinding::None),
+            "core" => Ok(ProcessBinding::Core),
+            "socket" => Ok(ProcessBinding::Socket),
+            "numa" => Ok(ProcessBinding::Numa),
+            _ => Err(format!("Unknown process binding: {} (expected one of: none, core, socket, numa)", name).into()),
+        }
+    }
+
+    /// Get the value of mpirun's `--bind-to` for this binding
+    pub fn mpirun_name(&self) -> &'static str {
+        match self {
+            ProcessBinding::None => "none",
+            ProcessBinding::Core => "core",
+            ProcessBinding::Socket => "socket",
+            ProcessBinding::Numa => "numa",
+        }
+    }
+
+    /// Get the value of srun's `--cpu-bind` for this binding (NUMA domains are "locality domains" in Slurm)
+    pub fn srun_name(&self) -> &'static str {
+        match self {
+            ProcessBinding::None => "none",
+            ProcessBinding::Core => "cores",
+            ProcessBinding::Socket => "sockets",
+            ProcessBinding::Numa => "ldoms",
+        }
+    }
+}
+
+/// Nsight Systems profiling of an experiment (the test executable is wrapped with `nsys profile`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NsysProfile {
+    pub output: PathBuf,   // Report path without extension (each rank writes `<output>.rank<N>.nsys-rep`)
+    pub args: Vec<String>, // Extra `nsys profile` arguments (e.g., `--trace=cuda,nvtx,mpi`)
+}
+
+impl NsysProfile {
+    /// Get the `nsys profile ...` prefix of the test executable
+    ///
+    /// # Arguments
+    /// * `rank_var` - The envvar the launcher sets to the global rank of each process (used in the report name)
+    fn command_prefix(&self, rank_var: &str) -> Vec<String> {
+        let mut prefix = vec![
+            "nsys".to_string(),
+            "profile".to_string(),
+            "--force-overwrite=true".to_string(),
+            "-o".to_string(),
+            format!("{}.rank%q{{{}}}", self.output.to_str().unwrap(), rank_var),
+        ];
+        prefix.extend(self.args.iter().cloned());
+
+        prefix
+    }
+}
+
+/// An NCCL net plugin build (e.g., aws-ofi-nccl) that an experiment runs with instead of the one in `AWS_OFI_NCCL_PATH`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetPlugin {
+    pub name: String, // Tags the results (adds a `_np` token to file names)
+    pub library: PathBuf, // The plugin library (e.g., `.../lib/libnccl-net.so`), set as `NCCL_NET_PLUGIN`
+}
+
+impl NetPlugin {
+    /// Get the directory of the plugin library (put on the LD_LIBRARY_PATH in place of the `aws_ofi_nccl` component)
+    pub fn lib_dir(&self) -> String {
+        self.library.parent().map(|d| d.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
+
+/// Check whether `nsys` (Nsight Systems) can be run on this machine
+pub fn nsys_available() -> bool {
+    match Command::new("nsys").arg("--version").output() {
+        Ok(output) => {
+            debug!("Found nsys: {}", String::from_utf8_lossy(&output.stdout).trim());
+            output.status.success()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Launch NCCL tests with OpenMPI's `mpirun`
+pub struct Mpirun;
+
+/// Launch NCCL tests with Slurm's `srun`
+///
+/// Note: Must be run from inside an allocation (e.g., `salloc` or an `sbatch` script). The hostfile is ignored.
+pub struct Srun;
+
+/// Run the NCCL tests executable directly as a single local process (no MPI or Slurm)
+///
+/// Note: Meant for `self-test` and single-node debugging. The hostfile, process mapping, binding, and `nsys` profiling
+///       are ignored.
+pub struct Local;
+
+impl TestLauncher for Mpirun {
+    fn build_command(&self, params: &MscclExperimentParams) -> Command {
+        mpirun_command(params, false)
+    }
+
+    fn build_validation_command(&self, params: &MscclExperimentParams) -> Command {
+        mpirun_command(params, true)
+    }
+}
+
+/// Build an `mpirun` command for the given experiment
+///
+/// Note: With `do_not_launch`, mpirun parses the arguments and maps the processes, but doesn't start anything.
+fn mpirun_command(params: &MscclExperimentParams, do_not_launch: bool) -> Command {
+    let mut command = Command::new("mpirun");
+    if do_not_launch {
+        command.arg("--do-not-launch");
+    }
+    command
+        .args(["--hostfile", params.mpi_hostfile_path.to_str().unwrap()])
+        .args([
+            "--map-by",
+            format!("ppr:{}:node", params.mpi_proc_per_node).as_str(),
+        ]);
+
+    // Pass through the environment to every rank
+    for (key, value) in nccl_env_vars(params) {
+        command.args(["-x", format!("{}={}", key, value).as_str()]);
+    }
+
+    // Forward the requested variables from the harness's own environment (`-x NAME` reads the current value)
+    for name in params.passthrough_env_vars.iter() {
+        command.args(["-x", name.as_str()]);
+    }
+
+    command
+        .args([
+            "--mca",
+            "btl",
+            "tcp,self",
+            "--mca",
+            "btl_tcp_if_exclude",
+            "lo,docker0",
+            "--bind-to",
+            params.mpi_binding.mpirun_name(),
+        ]);
+
+    // Show where every rank was bound (printed to stderr by mpirun)
+    if params.mpi_binding != ProcessBinding::None {
+        command.arg("--report-bindings");
+    }
+
+    // Wrap the test executable with nsys when profiling
+    if let Some(profile) = &params.nsys_profile {
+        command.args(profile.command_prefix("OMPI_COMM_WORLD_RANK"));
+    }
+
+    command
+        .arg(params.executable.to_str().unwrap())
+        .args(nccl_tests_args(params));
+
+    command
+}
+
+impl TestLauncher for Srun {
+    fn build_command(&self, params: &MscclExperimentParams) -> Command {
+        srun_command(params, false)
+    }
+
+    fn build_validation_command(&self, params: &MscclExperimentParams) -> Command {
+        srun_command(params, true)
+    }
+}
+
+/// Build an `srun` command for the given experiment
+///
+/// Note: With `test_only`, srun validates the request against the allocation without running any job steps.
+fn srun_command(params: &MscclExperimentParams, test_only: bool) -> Command {
+    let mut command = Command::new("srun");
+    if test_only {
+        command.arg("--test-only");
+    }
+    command
+        .args(["--nodes", params.num_nodes.to_string().as_str()])
+        .args([
+            "--ntasks-per-node",
+            params.mpi_proc_per_node.to_string().as_str(),
+        ])
+        .arg(format!("--cpu-bind={}", params.mpi_binding.srun_name()));
+
+    // Note: srun exports the full environment by default, so set the variables on the command itself (using
+    //       `--export` would break on values containing commas, like `NCCL_ALGO`). This also forwards the
+    //       passthrough variables without any extra flags.
+    command.envs(nccl_env_vars(params));
+
+    // Wrap the test executable with nsys when profiling
+    if let Some(profile) = &params.nsys_profile {
+        command.args(profile.command_prefix("SLURM_PROCID"));
+    }
+
+    command
+        .arg(params.executable.to_str().unwrap())
+        .args(nccl_tests_args(params));
+
+    command
+}
+
+impl TestLauncher for Local {
+    fn build_command(&self, params: &MscclExperimentParams) -> Command {
+        let mut command = Command::new(params.executable.as_os_str());
+        command.envs(nccl_env_vars(params)).args(nccl_tests_args(params));
+
+        command
+    }
+
+    fn build_validation_command(&self, params: &MscclExperimentParams) -> Command {
+        // Note: There is no launcher to ask, so just check that the executable runs
+        let mut command = Command::new(params.executable.as_os_str());
+        command.env("LD_LIBRARY_PATH", ld_library_path(params)).arg("-h");
+
+        command
+    }
+}
+
+/// Render a command as a (copy-pasteable) shell command line
+///
+/// Note: Variables set on the command itself (e.g., by `srun`) are rendered as `KEY=VALUE` assignments in front of it.
+///       Arguments with whitespace or shell metacharacters are single-quoted.
+pub fn render_command(command: &Command) -> String {
+    let quote = |arg: &str| {
+        if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%^".contains(c)) {
+            arg.to_string()
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    };
+
+    let envs = command.get_envs().filter_map(|(key, value)| {
+        value.map(|v| format!("{}={}", key.to_string_lossy(), quote(v.to_string_lossy().as_ref())))
+    });
+    let program = std::iter::once(quote(command.get_program().to_string_lossy().as_ref()));
+    let args = command.get_args().map(|a| quote(a.to_string_lossy().as_ref()));
+
+    envs.chain(program).chain(args).collect::<Vec<String>>().join(" ")
+}
+
+/// Describes why a launch command would not be accepted by the launcher
+#[derive(Debug)]
+pub enum LaunchValidationError {
+    /// The launcher itself could not be run (e.g., it isn't on the PATH)
+    LauncherNotFound { launcher: String, reason: String },
+    /// The launcher rejected the launch command
+    Rejected { command: String, status: Option<i32>, stderr: String },
+}
+
+impl fmt::Display for LaunchValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LaunchValidationError::LauncherNotFound { launcher, reason } => {
+                write!(f, "Could not run launcher '{}': {}", launcher, reason)
+            }
+            LaunchValidationError::Rejected { command, status, stderr } => {
+                write!(f, "Launcher rejected the command (status: {:?}): {}\n{}", status, command, stderr.trim_end())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LaunchValidationError {}
+
+/// Check that the launcher would accept the launch command of an experiment, without running the NCCL tests
+///
+/// Note: First checks that the launcher runs at all (`--version`), then runs its validation command.
+pub fn validate_launch(launcher: &dyn TestLauncher, params: &MscclExperimentParams) -> Result<(), LaunchValidationError> {
+    let mut validation_command = launcher.build_validation_command(params);
+    let launcher_name = validation_command.get_program().to_string_lossy().to_string();
+
+    // Check that the launcher is available
+    match Command::new(validation_command.get_program()).arg("--version").output() {
+        Ok(output) => debug!("Found launcher: {}", String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or_default()),
+        Err(e) => {
+            return Err(LaunchValidationError::LauncherNotFound { launcher: launcher_name, reason: e.to_string() });
+        }
+    }
+
+    // Let the launcher check the full command
+    let command_string = render_command(&validation_command);
+    debug!("Validating launch command: {}", command_string);
+    let output = validation_command
+        .output()
+        .map_err(|e| LaunchValidationError::LauncherNotFound { launcher: launcher_name.clone(), reason: e.to_string() })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if !output.status.success() || stderr.lines().any(is_launcher_error) {
+        return Err(LaunchValidationError::Rejected { command: command_string, status: output.status.code(), stderr });
+    }
+
+    Ok(())
+}
+
+/// Flags every nccl-tests binary lists in its usage text (used to recognize a valid build)
+const NCCL_TESTS_USAGE_MARKERS: [&str; 3] = ["--minbytes", "--maxbytes", "--ngpus"];
+
+/// Check that the executable of an experiment is an nccl-tests binary by running `<exe> -h` locally
+///
+/// Note: The usage text must mention the executable's name and the common nccl-tests flags. This catches pointing
+///       `NCCL_TESTS_HOME` at the wrong build without launching anything across the cluster. Only needs to be run once
+///       per unique executable.
+pub fn validate_test_executable(params: &MscclExperimentParams) -> Result<(), Box<dyn std::error::Error>> {
+    let exe_name = params.executable.file_name().unwrap_or_default().to_string_lossy().to_string();
+    debug!("Checking that {:?} is an NCCL tests executable...", params.executable);
+
+    let output = match Command::new(params.executable.as_os_str())
+        .arg("-h")
+        .env("LD_LIBRARY_PATH", ld_library_path(params))
+        .output()
+    {
+        Ok(v) => v,
+        Err(e) => return Err(format!("Could not run NCCL tests executable {:?}: {}", params.executable, e).into()),
+    };
+
+    // Note: Older nccl-tests builds print the usage to stderr (and some exit with a non-zero status after it)
+    let usage = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let missing_markers = NCCL_TESTS_USAGE_MARKERS
+        .iter()
+        .filter(|m| !usage.contains(*m))
+        .collect::<Vec<&&str>>();
+    if !usage.contains(exe_name.as_str()) || !missing_markers.is_empty() {
+        return Err(format!(
+            "{:?} does not look like the NCCL tests executable for '{}' (missing {:?} in its '-h' output; is NCCL_TESTS_HOME correct?): {}",
+            params.executable,
+            params.nc_collective,
+            missing_markers,
+            usage.lines().take(3).collect::<Vec<&str>>().join(" | ")
+        ).into());
+    }
+    debug!("Found NCCL tests executable for '{}' at: {:?}", params.nc_collective, params.executable);
+
+    Ok(())
+}
+
+/// Get the library `libnccl.so` resolves to in the output of `ldd`
+///
+/// # Returns
+/// `None` if the executable doesn't link NCCL dynamically, otherwise what it resolves to (`not found` if unresolved)
+fn ldd_nccl_library(ldd_output: &str) -> Option<String> {
+    ldd_output
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| l.starts_with("libnccl.so"))
+        .map(|l| match l.split_once("=>") {
+            Some((_, resolved)) => resolved.split(" (0x").next().unwrap_or_default().trim().to_string(),
+            None => l.to_string(),
+        })
+}
+
+/// Check that the executable of an experiment loads the NCCL of the MSCCL build (`MSCCL_PATH`) by running `ldd` locally
+///
+/// Note: nccl-tests linked against (and run with) another NCCL silently benchmark that NCCL instead of MSCCL. This is
+///       the mistake the old hardcoded `LD_LIBRARY_PATH` fought. The check resolves the libraries with the experiment's
+///       own `LD_LIBRARY_PATH` on this node only, so nodes with different installs are not covered.
+pub fn check_nccl_library(params: &MscclExperimentParams) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("ldd")
+        .arg(params.executable.as_os_str())
+        .env("LD_LIBRARY_PATH", ld_library_path(params))
+        .output()
+        .map_err(|e| format!("Could not run 'ldd' on {:?}: {}", params.executable, e))?;
+    if !output.status.success() {
+        // E.g., a static binary or a script (`not a dynamic executable`)
+        debug!("Skipping the NCCL library check of {:?}, 'ldd' failed: {}", params.executable, String::from_utf8_lossy(&output.stderr).trim());
+        return Ok(());
+    }
+    let ldd_output = String::from_utf8_lossy(&output.stdout).to_string();
+
+    let remediation = format!(
+        "Rebuild nccl-tests with 'NCCL_HOME={}' (or fix LD_LIBRARY_PATH_ORDER so that 'msccl' comes before any other NCCL)",
+        params.msccl_path
+    );
+    let resolved = match ldd_nccl_library(ldd_output.as_str()) {
+        Some(v) if v != "not found" => PathBuf::from(v),
+        Some(_) => {
+            return Err(format!("{:?} links libnccl.so, but it could not be found (MSCCL_PATH is {}). {}", params.executable, params.msccl_path, remediation).into());
+        }
+        None => {
+            return Err(format!("{:?} does not link libnccl.so dynamically, so it can't use the MSCCL build. {}", params.executable, remediation).into());
+        }
+    };
+
+    // Compare the canonical paths so that symlinks (e.g., `libnccl.so.2` -> `libnccl.so.2.18.3`) don't matter
+    let msccl_dir = std::fs::canonicalize(params.msccl_path.as_str()).unwrap_or_else(|_| PathBuf::from(params.msccl_path.as_str()));
+    let resolved_canonical = std::fs::canonicalize(&resolved).unwrap_or_else(|_| resolved.clone());
+    if !resolved_canonical.starts_with(&msccl_dir) {
+        return Err(format!(
+            "{:?} loads NCCL from {:?}, not from the MSCCL build under MSCCL_PATH ({}), so MSCCL's algorithms won't be used. {}",
+            params.executable, resolved, params.msccl_path, remediation
+        ).into());
+    }
+    debug!("NCCL tests executable {:?} loads NCCL from: {:?}", params.executable, resolved);
+
+    Ok(())
+}
+
+/// Get a launcher from its name
+///
+/// # Arguments
+/// * `name` - The name of the launcher (e.g., `mpirun`, `srun`, `local`)
+pub fn launcher_from_name(name: &str) -> Result<Box<dyn TestLauncher>, Box<dyn std::error::Error>> {
+    match name {
+        "mpirun" => Ok(Box::new(Mpirun)),
+        "srun" => Ok(Box::new(Srun)),
+        "local" => Ok(Box::new(Local)),
+        _ => Err(format!("Unknown launcher: {}", name).into()),
+    }
+}
+
+/// Default priority of the library components in the LD_LIBRARY_PATH
+///
+/// Note: MSCCL comes first so that its `libnccl.so` wins over any other NCCL on the path (e.g., one in the CUDA dir).
+pub const DEFAULT_LD_LIBRARY_PATH_ORDER: [&str; 5] = ["msccl", "aws_ofi_nccl", "efa", "cuda", "openmpi"];
+
+/// Validate the names of the components in an LD_LIBRARY_PATH priority order
+pub fn validate_ld_library_path_order(order: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    for component in order {
+        if !DEFAULT_LD_LIBRARY_PATH_ORDER.contains(&component.as_str()) {
+            return Err(format!(
+                "Unknown LD_LIBRARY_PATH component '{}' (expected one of: {})",
+                component,
+                DEFAULT_LD_LIBRARY_PATH_ORDER.join(", ")
+            ).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the LD_LIBRARY_PATH for the launched processes from the experiment's environment params
+///
+/// Note: Components are added in the order given by `ld_library_path_order` and repeated entries are removed (the
+///       first occurrence wins).
+pub fn ld_library_path(params: &MscclExperimentParams) -> String {
+    let mut entries: Vec<String> = Vec::new();
+    for component in params.ld_library_path_order.iter() {
+        let dirs = match component.as_str() {
+            "msccl" => vec![format!("{}/lib64", params.msccl_path), format!("{}/lib", params.msccl_path)],
+            "cuda" => vec![format!("{}/lib64", params.cuda_path), format!("{}/lib", params.cuda_path)],
+            "openmpi" => vec![format!("{}/lib64", params.openmpi_path), format!("{}/lib", params.openmpi_path)],
+            "efa" => params.efa_path.iter().map(|p| format!("{}/lib", p)).collect(),
+            // Note: A net plugin of the experiment's own replaces the one in `AWS_OFI_NCCL_PATH`, so the two never mix
+            "aws_ofi_nccl" => match &params.nccl_net_plugin {
+                Some(plugin) => vec![plugin.lib_dir()],
+                None => params.aws_ofi_nccl_path.iter().map(|p| format!("{}/lib", p)).collect(),
+            },
+            _ => Vec::new(), // Validated when the order is configured
+        };
+
+        for dir in dirs {
+            // Normalize trailing/double slashes so equivalent paths are deduplicated
+            let dir = dir.replace("//", "/");
+            if !entries.contains(&dir) {
+                entries.push(dir);
+            }
+        }
+    }
+
+    debug!("Resolved `LD_LIBRARY_PATH` order: {:#?}", entries);
+
+    entries.join(":")
+}
+
+/// Get the environment variables that must be set for every launched process
+///
+/// Note: `MSCCL_XML_FILES` points MSCCL at the experiment's XML file, while `GENMSCCLXML=1` additionally makes MSCCL
+///       generate XML files of its own. Generation is redundant when the XML file exists, so `GENMSCCLXML` is only set
+///       if `ms_gen_xml` asks for it or the XML file is missing. Neither is set for experiments that fall back to NCCL's
+///       own algorithms (`ms_xml_fallback`), so MSCCL stays out of the way.
+pub fn nccl_env_vars(params: &MscclExperimentParams) -> Vec<(String, String)> {
+    let ld_library_path = ld_library_path(params);
+    debug!("Will use `LD_LIBRARY_PATH`: {}", ld_library_path);
+
+    let mut env_vars = vec![
+        ("LD_LIBRARY_PATH".to_string(), ld_library_path),
+        ("NCCL_DEBUG".to_string(), params.nccl_debug_level.clone()),
+        ("NCCL_ALGO".to_string(), params.nccl_algo.clone()),
+        ("FI_EFA_USE_DEVICE_RDMA".to_string(), "1".to_string()),
+        ("FI_EFA_FORK_SAFE".to_string(), "1".to_string()),
+    ];
+    if params.ms_xml_fallback {
+        debug!("XML file is missing, will run without MSCCL.");
+    } else {
+        debug!("Using MSCCL XML file at: {}", params.ms_xml_file.to_str().unwrap());
+        env_vars.insert(1, ("MSCCL_XML_FILES".to_string(), params.ms_xml_file.to_str().unwrap().to_string()));
+        if params.ms_gen_xml || !params.ms_xml_file.exists() {
+            env_vars.insert(2, ("GENMSCCLXML".to_string(), "1".to_string()));
+        }
+    }
+    if let Some(nccl_proto) = &params.nccl_proto {
+        env_vars.push(("NCCL_PROTO".to_string(), nccl_proto.clone()));
+    }
+    if let Some(min) = params.nccl_min_nchannels {
+        env_vars.push(("NCCL_MIN_NCHANNELS".to_string(), min.to_string()));
+    }
+    if let Some(max) = params.nccl_max_nchannels {
+        env_vars.push(("NCCL_MAX_NCHANNELS".to_string(), max.to_string()));
+    }
+    if let Some(plugin) = &params.nccl_net_plugin {
+        env_vars.push(("NCCL_NET_PLUGIN".to_string(), plugin.library.to_string_lossy().to_string()));
+    }
+    if let Some(devices) = &params.cuda_visible_devices {
+        env_vars.push(("CUDA_VISIBLE_DEVICES".to_string(), devices.clone()));
+    }
+
+    env_vars
+}
+
+/// Flags of the NCCL tests executable that the harness sets itself, as (short, long) pairs
+pub const HARNESS_TEST_FLAGS: [(&str, &str); 13] = [
+    ("-t", "--nthreads"),
+    ("-g", "--ngpus"),
+    ("-b", "--minbytes"),
+    ("-e", "--maxbytes"),
+    ("-o", "--op"),
+    ("-d", "--datatype"),
+    ("-n", "--iters"),
+    ("-w", "--warmup_iters"),
+    ("-f", "--stepfactor"),
+    ("-i", "--stepbytes"),
+    ("-r", "--root"),
+    ("-G", "--cudagraph"),
+    ("-T", "--timeout"),
+];
+
+/// Validate extra arguments for the NCCL tests executable
+///
+/// Note: Rejects any flag the harness already sets (in either its short or long form, including `--flag=value`)
+pub fn validate_extra_test_args(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    for arg in args {
+        let flag = arg.split('=').next().unwrap_or_default();
+        if let Some((short, long)) = HARNESS_TEST_FLAGS.iter().find(|(short, long)| flag == *short || flag == *long) {
+            return Err(format!(
+                "Extra test argument '{}' collides with a flag the harness already sets ({}/{})",
+                arg, short, long
+            ).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// How bad an incompatible combination of nccl-tests flags is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlagConflict {
+    Error,   // nccl-tests rejects (or hangs on) the combination, so the experiment is never launched
+    Warning, // Only some nccl-tests builds misbehave, or the results are misleading
+}
+
+/// Known incompatible combinations of nccl-tests flags, as (flag, other flag, severity, explanation)
+///
+/// Note: The one place to record incompatibilities (checked by `check_test_flag_compatibility`). Flags are given by
+///       their long name; a flag counts as set if it is passed with a non-zero value (in its short or long form).
+pub const INCOMPATIBLE_TEST_FLAGS: [(&str, &str, FlagConflict, &str); 3] = [
+    (
+        "--cudagraph",
+        "--blocking",
+        FlagConflict::Error,
+        "blocking collectives synchronize the stream after every operation, which is illegal while a CUDA graph is captured",
+    ),
+    (
+        "--cudagraph",
+        "--check",
+        FlagConflict::Warning,
+        "some nccl-tests builds fail the data check of graph-replayed iterations (use '--check 0' or '--cudagraph 0' if the run reports wrong results)",
+    ),
+    (
+        "--cudagraph",
+        "--report_cputime",
+        FlagConflict::Warning,
+        "the CPU time of a graph replay doesn't include launching the individual operations, so the reported times aren't comparable to runs without graphs",
+    ),
+];
+
+/// Short forms of the flags in `INCOMPATIBLE_TEST_FLAGS`
+const TEST_FLAG_SHORT_NAMES: [(&str, &str); 4] = [("-G", "--cudagraph"), ("-z", "--blocking"), ("-c", "--check"), ("-C", "--report_cputime")];
+
+/// Flags of `INCOMPATIBLE_TEST_FLAGS` that nccl-tests enables unless they are set to 0 (e.g., `--check` defaults to 1)
+const TEST_FLAGS_ENABLED_BY_DEFAULT: [&str; 1] = ["--check"];
+
+/// Get the (long) names of the flags enabled in the arguments of an NCCL tests executable (set to a non-zero value,
+/// or left unset for the flags in `TEST_FLAGS_ENABLED_BY_DEFAULT`)
+fn enabled_test_flags(args: &[String]) -> Vec<String> {
+    let mut enabled = Vec::new();
+    let mut disabled = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if !arg.starts_with('-') {
+            continue;
+        }
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), args.get(i + 1).cloned()),
+        };
+        let flag = TEST_FLAG_SHORT_NAMES.iter().find(|(short, _)| *short == flag).map(|(_, long)| *long).unwrap_or(flag);
+        match value {
+            Some(v) if v.trim() == "0" => disabled.push(flag.to_string()),
+            Some(_) => enabled.push(flag.to_string()),
+            None => {}
+        }
+    }
+    for flag in TEST_FLAGS_ENABLED_BY_DEFAULT.iter() {
+        if !enabled.iter().any(|f| f == flag) && !disabled.iter().any(|f| f == flag) {
+            enabled.push(flag.to_string());
+        }
+    }
+
+    enabled
+}
+
+/// Check the arguments of an NCCL tests executable for known incompatible flag combinations (see `INCOMPATIBLE_TEST_FLAGS`)
+///
+/// # Returns
+/// An explanation of every `Warning` combination, or an error for the first `Error` combination
+pub fn check_test_flag_compatibility(args: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let enabled = enabled_test_flags(args);
+    let mut warnings = Vec::new();
+    for (flag, other, severity, explanation) in INCOMPATIBLE_TEST_FLAGS.iter() {
+        if !enabled.iter().any(|f| f == flag) || !enabled.iter().any(|f| f == other) {
+            continue;
+        }
+        let message = format!("'{}' with '{}': {}", flag, other, explanation);
+        match severity {
+            FlagConflict::Error => return Err(format!("Incompatible NCCL tests flags {}", message).into()),
+            FlagConflict::Warning => warnings.push(message),
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Get the arguments for the NCCL tests executable
+pub fn nccl_tests_args(params: &MscclExperimentParams) -> Vec<String> {
+    let mut args = vec![
+        "--nthreads".to_string(),
+        params.nc_num_threads.to_string(),
+        "--ngpus".to_string(),
+        params.nc_num_gpus.to_string(),
+        "--minbytes".to_string(),
+        params.nc_min_bytes.clone(),
+        "--maxbytes".to_string(),
+        params.nc_max_bytes.clone(),
+        "--op".to_string(),
+        params.nc_op.clone(),
+        "--datatype".to_string(),
+        params.nc_dtype.clone(),
+        "--iters".to_string(),
+        params.nc_num_iters.to_string(),
+        "--warmup_iters".to_string(),
+        params.nc_num_warmup_iters.to_string(),
+    ];
+
+    // Step through message sizes by either a factor or a fixed number of bytes (validated to be exclusive)
+    if let Some(step_factor) = &params.nc_step_factor {
+        args.push("--stepfactor".to_string());
+        args.push(step_factor.clone());
+    }
+    if let Some(step_bytes) = &params.nc_step_bytes {
+        args.push("--stepbytes".to_string());
+        args.push(step_bytes.clone());
+    }
+
+    // Note: Required with `--iters 0`, where nccl-tests picks the iteration count of each size within this budget
+    if let Some(timeout) = params.nc_timeout {
+        args.push("--timeout".to_string());
+        args.push(timeout.to_string());
+    }
+
+    // Root rank (only set for rooted collectives)
+    if let Some(root) = params.nc_root {
+        args.push("--root".to_string());
+        args.push(root.to_string());
+    }
+
+    // Note: `--cudagraph` requires a recent nccl-tests build
+    if let Some(cudagraph) = params.nc_cudagraph {
+        args.push("--cudagraph".to_string());
+        args.push(cudagraph.to_string());
+    }
+
+    // Anything the harness doesn't have a structured flag for (validated to not collide with the flags above)
+    args.extend(params.extra_test_args.iter().cloned());
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn check_is_enabled_unless_set_to_zero() {
+        assert!(enabled_test_flags(&args(&["--iters", "20"])).contains(&"--check".to_string()));
+        assert!(enabled_test_flags(&args(&["--check", "1"])).contains(&"--check".to_string()));
+        assert!(!enabled_test_flags(&args(&["--check", "0"])).contains(&"--check".to_string()));
+        assert!(!enabled_test_flags(&args(&["-c=0"])).contains(&"--check".to_string()));
+    }
+
+    #[test]
+    fn cudagraph_warns_about_the_default_check() {
+        let warnings = check_test_flag_compatibility(&args(&["--cudagraph", "1"])).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("'--cudagraph' with '--check'"));
+        assert!(check_test_flag_compatibility(&args(&["--cudagraph", "1", "--check", "0"])).unwrap().is_empty());
+    }
+}
@@ -1,183 +1,889 @@
-use std::io::BufRead;
+use std::io::{BufRead, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use regex::Regex;
-use polars::prelude::*;
-use log::{debug, info, warn, error};
-#[macro_use] extern crate prettytable;
+use tracing::{debug, info, warn, error, info_span};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+mod cli;
+use clap::Parser;
+use cli::{Cli, CliCommand};
+
+mod config;
+use config::ExperimentConfig;
 
 mod util;
-use util::{Row, Permutation, MscclExperimentParams, ManifestEntry, ResultDescription, params_to_xml, verify_env, pretty_print_configs, pretty_print_result_manifest, collective_to_test_exe};
+mod environment;
+use environment::{EnvPaths, HostGroup};
+use util::{load_env_file, FilenameFormat, validate_filename_separator, parse_hostfile, check_hostfile_topology, write_hostfile_slices, print_validation_report, Row, Permutation, MscclExperimentParams, ManifestEntry, ResultDescription, params_to_xml, algorithm_chunks_channels, check_chunks_channels_feasible, parse_collective_algorithms, collective_algorithms_table, collective_algorithm_allowed, verify_env, pretty_print_configs, pretty_print_result_manifest, write_markdown_report, write_manifest_json, Manifest, SweepMetadata, read_manifest_json, read_params_json, write_params_json, manifest_entry_matches, merge_rerun_manifest, collective_to_test_exe, gpus_per_proc, validate_proc_per_node_override, validate_dtype, validate_iters, parse_iter_regimes, message_sizes, validate_op, validate_step, validate_nccl_proto, validate_nchannels, is_rooted_collective, is_reducing_collective, check_xml_gpu_count, ExperimentOrder, order_experiments, parse_visible_devices, check_visible_devices, config_hash, check_log_finished, append_log_result};
 
 mod parse;
-use parse::{rows_to_df, parse_line};
+use parse::{parse_footer_log, parse_iteration_log, format_nccl_table};
+#[cfg(feature = "dataframe")]
+use parse::{rows_to_df, validate_row_columns, BandwidthUnit};
 
 mod wrapper;
-use wrapper::run_msccl_tests;
+use wrapper::{format_error_log_entry, run_cluster_warmup, DEFAULT_FATAL_ERROR_PATTERNS, DEFAULT_STDERR_TAIL_LINES, run_msccl_tests_with_retries, HarnessError, RetryPolicy, Timeouts};
+
+mod launcher;
+use launcher::{check_nccl_library, check_test_flag_compatibility, nccl_tests_args, launcher_from_name, nsys_available, NetPlugin, NsysProfile, ProcessBinding, validate_launch, validate_test_executable, validate_extra_test_args, validate_ld_library_path_order, DEFAULT_LD_LIBRARY_PATH_ORDER};
+
+mod sampler;
+use sampler::PowerSampler;
+
+mod metrics;
+use metrics::push_bus_bw_metrics;
+
+mod export;
+use export::{write_bus_bw_csv, LogIndex, write_iteration_timeseries_csv, write_planned_experiments_csv};
+#[cfg(feature = "dataframe")]
+use export::{experiment_df, ParquetExporter};
+
+mod progress;
+use progress::SweepProgress;
 
-use crate::util::exp_params_to_output_filename;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+use sqlite::SqliteExporter;
+
+mod topology;
+use topology::{parse_network_log, parse_topology_log, write_topology_json};
+
+mod selftest;
+use selftest::{check_self_test, setup_self_test};
+
+mod stats;
+mod theoretical;
+use stats::{bandwidth_gate_failures, parse_bandwidth_gates, flag_inplace_divergence, flag_low_bandwidth_sizes, row_count_mismatches, peak_bus_bw, summarize, summarize_rows};
+use theoretical::{peak_efficiency, sizes_exceeding_peak, HardwareSpec, DEFAULT_PEAK_MARGIN};
+
+use crate::util::{create_output_file, exp_params_to_config_filename, exp_params_to_output_filename};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logger
-    env_logger::init();
+    // Parse the command line (unknown flags and subcommands are an error, so a typo can't silently run the full sweep)
+    let cli = Cli::parse();
+
+    // Load envvars from a dotenv-style file if given (`--env-file <path>`; explicitly set envvars take precedence)
+    // Note: Loaded before the logger is initialized so that the file can also set, e.g., `RUST_LOG`
+    let env_file = cli.env_file.clone();
+    let env_file_vars = match &env_file {
+        Some(path) => load_env_file(path.as_path())?,
+        None => Vec::new(),
+    };
+
+    // Initialize logger (filtered by `RUST_LOG`; set `LOG_FORMAT=json` for JSON lines)
+    let log_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::ERROR.into())
+        .from_env_lossy();
+    let log_json = matches!(std::env::var("LOG_FORMAT"), Ok(v) if v.to_lowercase() == "json");
+
+    // Show a live progress bar for interactive runs (`PROGRESS_BAR=true`; only when stderr is a terminal)
+    // Note: Log lines are then written above the bar
+    let mut sweep_progress = match std::env::var("PROGRESS_BAR") {
+        Ok(v) if (v.to_lowercase() == "true" || v.to_lowercase() == "1") && std::io::stderr().is_terminal() => {
+            Some(SweepProgress::new())
+        }
+        _ => None,
+    };
+    match (&sweep_progress, log_json) {
+        (Some(p), true) => {
+            let p = p.clone();
+            tracing_subscriber::fmt().json().with_env_filter(log_filter).with_writer(move || p.writer()).init();
+        }
+        (Some(p), false) => {
+            let p = p.clone();
+            tracing_subscriber::fmt().with_env_filter(log_filter).with_writer(move || p.writer()).init();
+        }
+        (None, true) => tracing_subscriber::fmt().json().with_env_filter(log_filter).with_writer(std::io::stderr).init(),
+        (None, false) => tracing_subscriber::fmt().with_env_filter(log_filter).with_writer(std::io::stderr).init(),
+    }
+
+    if let Some(path) = &env_file {
+        info!("Loaded {} envvar(s) from env file at {:?}: {}", env_file_vars.len(), path, env_file_vars.join(", "));
+    }
+
+    // Check if running the self-test (`self-test`): the full pipeline runs against a stub NCCL tests executable
+    // Note: Sets up its own environment (overriding the path envvars), so it must come before any of them are read
+    let self_test_dir = if matches!(cli.command, Some(CliCommand::SelfTest)) {
+        let dir = std::env::temp_dir().join(format!("nccl_harness_self_test_{}", std::process::id()));
+        info!("🧪 Found 'self-test', will run a small sweep against a stub NCCL tests executable in: {:?} 🧪", dir);
+        setup_self_test(dir.as_path())?;
+        Some(dir)
+    } else {
+        None
+    };
+
+    // Check if only validating the setup (preflight check) rather than running experiments
+    // Note: Validation collects every problem it finds instead of quitting at the first one
+    let validate_only = cli.validate_only;
+    let mut validation_problems = Vec::new();
+    if validate_only {
+        info!("🔍 Found '--validate-only', will check the setup and exit without running any experiments. 🔍");
+        validation_problems.extend(verify_env());
+    }
+
+    // Check if only printing the effective configuration (after envvars, CLI overrides, and defaults are resolved)
+    let print_config = cli.print_config;
+    if print_config {
+        info!("Found '--print-config', will print the resolved configuration as JSON and exit.");
+    }
+
+    // Check if the sweep should stop at the first failed experiment (separate from ignoring error status codes)
+    let fail_fast = cli.fail_fast;
+    if fail_fast {
+        info!("Found '--fail-fast', will stop the sweep at the first failed experiment.");
+    }
+
+    // Check if the permutation generation should explain why each permutation was generated or dropped
+    let explain = cli.explain;
+    if explain {
+        info!("Found '--explain', will explain why each permutation is generated or dropped.");
+    }
+
+    // Check if the harness should keep its own stdout quiet (overrides 'TEE_STDOUT')
+    let quiet = cli.quiet;
+    if quiet {
+        info!("Found '--quiet', will not mirror the output of the NCCL tests to stdout.");
+    }
+
+    // Check if resuming a sweep from a given permutation index (`--start-index N`; the first N permutations are skipped)
+    let start_index = cli.start_index;
+    if start_index > 0 {
+        info!("Found '--start-index {}', will skip the first {} permutation(s).", start_index, start_index);
+    }
 
-    // CUDA Path
-    let cuda_path = match std::env::var("CUDA_HOME") {
+    // Check if profiling a single permutation with Nsight Systems (`--profile N`; N is the permutation's index)
+    let profile_index = match cli.profile {
+        Some(index) => {
+            if start_index > 0 {
+                return Err("'--profile' runs a single permutation and can't be combined with '--start-index'".into());
+            }
+            info!("🔬 Found '--profile {}', will only run permutation {} once, wrapped with 'nsys profile'. 🔬", index, index);
+            Some(index)
+        }
+        None => None,
+    };
+
+    // Check if only listing the XML files the harness expects (`list-xmls [--status]`)
+    let (list_xmls, list_xmls_status) = match cli.command {
+        Some(CliCommand::ListXmls { status }) => (true, status),
+        _ => (false, false),
+    };
+    let mut expected_xmls = std::collections::BTreeSet::new();
+
+    // Check if only rerunning the failed configs of a prior sweep (`rerun-failed <manifest.json>`)
+    let rerun_failed = matches!(cli.command, Some(CliCommand::RerunFailed { .. }));
+    let prior_manifest = match &cli.command {
+        Some(CliCommand::RerunFailed { manifest: path }) => {
+            let manifest = read_manifest_json(path.as_path())?;
+            info!("🔁 Found 'rerun-failed', will only rerun the failed configs of the manifest at: {:?} 🔁", path);
+            manifest.entries
+        }
+        _ => Vec::new(),
+    };
+
+    // Check if replaying a single experiment from its params (`replay <params.json>`, as written for failed configs)
+    // Note: The params are used exactly as given (paths included), so the permutation generation is skipped
+    let replay_params = match &cli.command {
+        Some(CliCommand::Replay { params: path }) => {
+            if start_index > 0 || profile_index.is_some() {
+                return Err("'replay' runs a single experiment and can't be combined with '--start-index' or '--profile'".into());
+            }
+            let params = read_params_json(path.as_path())?;
+            info!("🔂 Found 'replay', will only run the experiment with the params at: {:?} 🔂", path);
+            Some(params)
+        }
+        _ => None,
+    };
+
+    // Paths and cluster topology
+    let env_paths = EnvPaths::from_env(!validate_only && !list_xmls && !print_config)?;
+    let EnvPaths {
+        cuda_path,
+        efa_path,
+        aws_ofi_nccl_path,
+        openmpi_path,
+        msccl_path,
+        nccl_test_builds,
+        nccl_net_plugins,
+        msccl_xmls_directory,
+        mpi_hostfile_path,
+        host_groups,
+        num_nodes,
+        gpus_per_node,
+        gpus_per_node_probed,
+        mpi_proc_per_node,
+        experiments_output_dir,
+    } = env_paths.clone();
+
+    // Every experiment is run on each node group of `MPI_HOSTFILES` (or just on the nodes of `MPI_HOSTFILE`)
+    // Note: Each group's hostfile must fit the topology on its own, since the groups are compared against each other
+    for (name, hostfile) in host_groups.iter() {
+        if !hostfile.exists() {
+            continue; // Only possible with `no_check_paths` or when validating
+        }
+        let hosts = parse_hostfile(hostfile.as_path()).map_err(|e| format!("Could not parse the hostfile of node group '{}': {}", name, e))?;
+        check_hostfile_topology(&hosts, num_nodes, mpi_proc_per_node).map_err(|e| format!("Node group '{}' ({:?}): {}", name, hostfile, e))?;
+    }
+    let host_group_options: Vec<Option<HostGroup>> = if host_groups.is_empty() {
+        vec![None]
+    } else {
+        info!("Found 'MPI_HOSTFILES', will run every experiment on each of {} node group(s).", host_groups.len());
+        host_groups.iter().cloned().map(Some).collect()
+    };
+
+    // Every experiment is run with each NCCL net plugin of `NCCL_NET_PLUGINS` (or just the default plugin)
+    let net_plugin_options: Vec<Option<NetPlugin>> = if nccl_net_plugins.is_empty() {
+        vec![None]
+    } else {
+        info!("Found 'NCCL_NET_PLUGINS', will run every experiment with {} NCCL net plugin(s).", nccl_net_plugins.len());
+        nccl_net_plugins.into_iter().map(Some).collect()
+    };
+
+    // Priority of the library paths in the LD_LIBRARY_PATH (comma-separated, e.g., "msccl,cuda,openmpi")
+    let ld_library_path_order = match std::env::var("LD_LIBRARY_PATH_ORDER") {
         Ok(v) => {
-            debug!("CUDA_HOME set to: {}", v);
+            debug!("LD_LIBRARY_PATH_ORDER set to: {}", v);
+            let order = v.split(',').map(|c| c.trim().to_lowercase()).collect::<Vec<String>>();
+            validate_ld_library_path_order(&order)?;
+            order
+        },
+        Err(_) => {
+            debug!("LD_LIBRARY_PATH_ORDER not set, will use: {}", DEFAULT_LD_LIBRARY_PATH_ORDER.join(","));
+            DEFAULT_LD_LIBRARY_PATH_ORDER.iter().map(|c| c.to_string()).collect()
+        }
+    };
+
+    // Extra arguments for the NCCL tests executable (whitespace-separated, e.g., "-R 1 -N 10" for newer nccl-tests builds)
+    let extra_test_args = match std::env::var("EXTRA_TEST_ARGS") {
+        Ok(v) => {
+            debug!("EXTRA_TEST_ARGS set to: {}", v);
+            let args = v.split_whitespace().map(|a| a.to_string()).collect::<Vec<String>>();
+            validate_extra_test_args(&args)?;
+            args
+        },
+        Err(_) => Vec::new()
+    };
+
+    // Names of extra environment variables to forward to every rank (comma-separated, e.g., "NCCL_SOCKET_IFNAME,NCCL_IB_HCA")
+    // Note: The values are read from the harness's own environment when launching
+    let passthrough_env_vars = match std::env::var("PASSTHROUGH_ENV_VARS") {
+        Ok(v) => {
+            debug!("PASSTHROUGH_ENV_VARS set to: {}", v);
+            let names = v.split(',').map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect::<Vec<String>>();
+            for name in names.iter() {
+                if std::env::var(name).is_err() {
+                    warn!("Envvar '{}' is set to be forwarded to every rank, but it is not set in the environment!", name);
+                }
+            }
+            names
+        },
+        Err(_) => Vec::new()
+    };
+
+    // GPUs every rank may use, set as `CUDA_VISIBLE_DEVICES` on every rank (e.g., "2,3" to debug a specific GPU pair)
+    let cuda_visible_devices = match std::env::var("NCCL_TESTS_VISIBLE_DEVICES") {
+        Ok(v) => {
+            debug!("NCCL_TESTS_VISIBLE_DEVICES set to: {}", v);
+            let devices = parse_visible_devices(v.as_str())?;
+            if passthrough_env_vars.iter().any(|n| n == "CUDA_VISIBLE_DEVICES") {
+                return Err("NCCL_TESTS_VISIBLE_DEVICES can't be combined with forwarding CUDA_VISIBLE_DEVICES through PASSTHROUGH_ENV_VARS".into());
+            }
+            info!("Found 'NCCL_TESTS_VISIBLE_DEVICES={}', will only make {} device(s) visible to every rank.", v, devices.len());
+            Some(devices)
+        },
+        Err(_) => None
+    };
+
+    // Cross-check the detected GPU count against the slots in the hostfile (the probe only sees this node)
+    if gpus_per_node_probed && mpi_hostfile_path.exists() {
+        match parse_hostfile(mpi_hostfile_path.as_path()) {
+            Ok(hosts) => {
+                for entry in hosts {
+                    if let Some(slots) = entry.slots {
+                        if slots != mpi_proc_per_node {
+                            warn!(
+                                "Host '{}' has {} slot(s) in the hostfile, but {} GPU(s) were detected on this node ({} MPI processes per node). Set GPUS_PER_NODE if the nodes differ.",
+                                entry.host, slots, gpus_per_node, mpi_proc_per_node
+                            );
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("Could not parse MPI_HOSTFILE to cross-check the detected GPU count: {}", e),
+        }
+    }
+
+    // How the MPI processes are bound to CPUs (none, core, socket, or numa; defaults to none)
+    let mpi_binding = match std::env::var("PROCESS_BINDING") {
+        Ok(v) => {
+            debug!("PROCESS_BINDING set to: {}", v);
+            ProcessBinding::from_name(v.as_str())?
+        },
+        Err(_) => {
+            debug!("PROCESS_BINDING not set, will not bind processes.");
+            ProcessBinding::None
+        }
+    };
+
+    // Each process drives the remaining GPUs on its node (nccl-tests `--ngpus`)
+    let nc_num_gpus = match gpus_per_proc(gpus_per_node, mpi_proc_per_node) {
+        Ok(v) => {
+            debug!("Each of the {} MPI processes per node will drive {} GPU(s).", mpi_proc_per_node, v);
             v
         },
+        Err(e) => {
+            if !validate_only {
+                return Err(format!("Invalid MPI_PROC_PER_NODE: {}", e).into());
+            }
+            validation_problems.push(format!("Invalid MPI_PROC_PER_NODE: {}", e));
+            1
+        }
+    };
+
+    // Verify that the hostfile parses and has enough hosts for the topology
+    if validate_only {
+        match parse_hostfile(mpi_hostfile_path.as_path()) {
+            Ok(hosts) => {
+                if (hosts.len() as u64) < num_nodes {
+                    validation_problems.push(format!(
+                        "MPI_HOSTFILE only lists {} host(s), but NUM_NODES is {}",
+                        hosts.len(), num_nodes
+                    ));
+                }
+                for entry in hosts {
+                    if let Some(slots) = entry.slots {
+                        if slots < mpi_proc_per_node {
+                            validation_problems.push(format!(
+                                "Host '{}' only has {} slot(s), but {} MPI processes per node are needed",
+                                entry.host, slots, mpi_proc_per_node
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(e) => validation_problems.push(format!("Could not parse MPI_HOSTFILE: {}", e)),
+        }
+    }
+
+    // Check if should skip previously completed experiments (ala makefile)
+    let skip_finished = match std::env::var("SKIP_FINISHED") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("⏭️ Found 'SKIP_FINISHED=true', will skip experiments that already have an output file! ⏭️");
+                true
+            } else {
+                info!("Found 'SKIP_FINISHED=false', will NOT skip experiments that already have an output file.");
+                false
+            }
+        }
         Err(_) => {
-            panic!("[ERROR] CUDA_HOME not set!");
+            info!("Did not find a setting for 'SKIP_FINISHED', will NOT skip experiments that already have an output file.");
+            false
         }
     };
 
-    // EFA Path
-    let efa_path = match std::env::var("EFA_PATH") {
+    // Check if outputs should be organized into per-collective/per-algorithm subdirectories
+    let nested_output_dirs = match std::env::var("NESTED_OUTPUT_DIRS") {
         Ok(v) => {
-            debug!("EFA_PATH set to: {}", v);
-            Some(v)
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'NESTED_OUTPUT_DIRS=true', will write outputs to '<output dir>/<collective>/<algorithm>/'.");
+                true
+            } else {
+                info!("Found 'NESTED_OUTPUT_DIRS=false', will write all outputs directly to the output directory.");
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Fraction of the median bus bandwidth below which a message size is flagged as anomalous (optional)
+    let anomaly_bw_fraction = match std::env::var("ANOMALY_BW_FRACTION") {
+        Ok(v) => {
+            debug!("ANOMALY_BW_FRACTION set to: {}", v);
+            let fraction = v.parse::<f64>().map_err(|e| format!("Invalid ANOMALY_BW_FRACTION '{}': {}", v, e))?;
+            if !(fraction > 0.0 && fraction < 1.0) {
+                return Err(format!("ANOMALY_BW_FRACTION must be between 0 and 1 (exclusive), got: {}", v).into());
+            }
+            Some(fraction)
         },
         Err(_) => {
-            warn!("EFA_PATH was not set! You will not be able to run tests that use the EFA!");
+            debug!("ANOMALY_BW_FRACTION not set, will not flag anomalous bandwidths.");
             None
         }
     };
 
-    // AWS OFI NCCL Path
-    let aws_ofi_nccl_path = match std::env::var("AWS_OFI_NCCL_PATH") {
+    // Max ratio between in-place and out-of-place bus bandwidth before a message size is flagged (optional)
+    let inplace_divergence_ratio = match std::env::var("INPLACE_DIVERGENCE_RATIO") {
         Ok(v) => {
-            debug!("AWS_OFI_NCCL_PATH set to: {}", v);
-            Some(v)
+            debug!("INPLACE_DIVERGENCE_RATIO set to: {}", v);
+            let ratio = v.parse::<f64>()?;
+            if ratio < 1.0 {
+                return Err(format!("INPLACE_DIVERGENCE_RATIO must be at least 1, got: {}", ratio).into());
+            }
+            Some(ratio)
         },
         Err(_) => {
-            warn!("AWS_OFI_NCCL_PATH was not set! You will not be able to run tests that use the EFA!");
+            debug!("INPLACE_DIVERGENCE_RATIO not set, will not compare in-place and out-of-place bandwidths.");
             None
         }
     };
 
-    // OpenMPI Path
-    let openmpi_path = match std::env::var("OPENMPI_PATH") {
+    // Check if the number of parsed rows should be compared with the number of message sizes (a short table means a
+    // truncated run, even if it exited cleanly)
+    let check_row_count = match std::env::var("CHECK_ROW_COUNT") {
         Ok(v) => {
-            debug!("OPENMPI_PATH set to: {}", v);
-            v
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'CHECK_ROW_COUNT=true', will mark experiments with missing or extra rows as partial failures.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Check if the first repetition of each experiment should be treated as a warmup
+    let skip_warmup_repetition = match std::env::var("SKIP_WARMUP_REPETITION") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'SKIP_WARMUP_REPETITION=true', will exclude the first repetition of each experiment from the results.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Whether MSCCL should still be told to generate XML files (`GENMSCCLXML=1`) when the harness supplies its own
+    // Note: Defaults to true (the previous behavior). Generation is always requested if the XML file is missing.
+    let ms_gen_xml = match std::env::var("GEN_MSCCL_XML") {
+        Ok(v) => {
+            debug!("GEN_MSCCL_XML set to: {}", v);
+            v.to_lowercase() == "true" || v.to_lowercase() == "1"
         },
         Err(_) => {
-            panic!("[ERROR] Envvar OPENMPI_PATH not set!");
+            debug!("GEN_MSCCL_XML not set, will set 'GENMSCCLXML=1' for every experiment.");
+            true
         }
     };
 
-    // MSCCL Path
-    let msccl_path = match std::env::var("MSCCL_PATH") {
+    // Extra arguments of `nsys profile` in profile mode (split on whitespace)
+    let nsys_args = match std::env::var("NSYS_ARGS") {
         Ok(v) => {
-            debug!("MSCCL_PATH set to: {}", v);
-            v
+            debug!("NSYS_ARGS set to: {}", v);
+            v.split_whitespace().map(|a| a.to_string()).collect::<Vec<String>>()
         },
         Err(_) => {
-            panic!("[ERROR] Envvar MSCCL_PATH not set!");
+            debug!("NSYS_ARGS not set, will trace CUDA, NVTX, and MPI when profiling.");
+            vec!["--trace=cuda,nvtx,mpi".to_string()]
         }
     };
 
-    // NCCL tests executable binary location
-    let nccl_test_bins = match std::env::var("NCCL_TESTS_HOME") {
+    // Optionally kill experiments that run longer than this in total, or print nothing for this long (in seconds)
+    // Note: A hang otherwise stalls the whole sweep. The idle timeout resets with every line of output, so it can be
+    //       much shorter than the total timeout without killing slow-but-progressing runs.
+    let experiment_timeouts = Timeouts {
+        total: match std::env::var("EXPERIMENT_TIMEOUT_SECS") {
+            Ok(v) => {
+                debug!("EXPERIMENT_TIMEOUT_SECS set to: {}", v);
+                Some(std::time::Duration::from_secs(v.parse::<u64>()?))
+            },
+            Err(_) => {
+                debug!("EXPERIMENT_TIMEOUT_SECS not set, experiments can run indefinitely.");
+                None
+            }
+        },
+        idle: match std::env::var("EXPERIMENT_IDLE_TIMEOUT_SECS") {
+            Ok(v) => {
+                debug!("EXPERIMENT_IDLE_TIMEOUT_SECS set to: {}", v);
+                Some(std::time::Duration::from_secs(v.parse::<u64>()?))
+            },
+            Err(_) => {
+                debug!("EXPERIMENT_IDLE_TIMEOUT_SECS not set, experiments can go without output indefinitely.");
+                None
+            }
+        },
+        heartbeat: match std::env::var("HEARTBEAT_INTERVAL_SECS") {
+            Ok(v) => {
+                debug!("HEARTBEAT_INTERVAL_SECS set to: {}", v);
+                Some(std::time::Duration::from_secs(v.parse::<u64>()?)).filter(|d| !d.is_zero())
+            },
+            Err(_) => None
+        },
+        poll_interval: match std::env::var("TIMEOUT_POLL_INTERVAL_MS") {
+            Ok(v) => {
+                debug!("TIMEOUT_POLL_INTERVAL_MS set to: {}", v);
+                std::time::Duration::from_millis(v.parse::<u64>()?)
+            },
+            Err(_) => Timeouts::default().poll_interval
+        },
+    };
+
+    // How often to retry an experiment after a hang (timeout) or a crash (launcher/test failure)
+    // Note: Separate because a hang might clear up on a second try, while a crash usually won't
+    let hang_retries = match std::env::var("HANG_RETRIES") {
         Ok(v) => {
-            debug!("NCCL_TESTS_HOME set to: {}", v);
-            PathBuf::from(v)
+            debug!("HANG_RETRIES set to: {}", v);
+            v.parse::<u64>()?
         },
-        Err(_) => {
-            panic!("[ERROR] Envvar NCCL_TESTS_HOME not set!");
+        Err(_) => 0
+    };
+    let crash_retries = match std::env::var("CRASH_RETRIES") {
+        Ok(v) => {
+            debug!("CRASH_RETRIES set to: {}", v);
+            v.parse::<u64>()?
+        },
+        Err(_) => 0
+    };
+    let retry_policy = RetryPolicy { hang_retries, crash_retries };
+
+    // How many repetitions of a config may run at once, each on its own slice of the hostfile (defaults to 1)
+    // Note: Needs a hostfile with enough spare nodes (NUM_NODES per repetition) and a launcher that uses it (mpirun)
+    let parallel_repetitions = match std::env::var("PARALLEL_REPETITIONS") {
+        Ok(v) => {
+            debug!("PARALLEL_REPETITIONS set to: {}", v);
+            let n = v.parse::<u64>()?;
+            if n > 1 && std::env::var("LAUNCHER").as_deref() == Ok("srun") {
+                return Err("PARALLEL_REPETITIONS needs a launcher that uses the hostfile (mpirun), not srun".into());
+            }
+            if n > 1 && !host_groups.is_empty() {
+                return Err("PARALLEL_REPETITIONS splits a single hostfile, so it can't be combined with MPI_HOSTFILES".into());
+            }
+            n.max(1)
+        },
+        Err(_) => 1
+    };
+    if hang_retries > 0 && experiment_timeouts.total.is_none() && experiment_timeouts.idle.is_none() {
+        warn!("HANG_RETRIES is set, but hangs are only detected with EXPERIMENT_TIMEOUT_SECS or EXPERIMENT_IDLE_TIMEOUT_SECS set.");
+    }
+
+    // Check if the cluster should be warmed up with a tiny throwaway run before the sweep
+    let cluster_warmup = match std::env::var("CLUSTER_WARMUP") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'CLUSTER_WARMUP=true', will run a tiny throwaway all-reduce before the sweep.");
+                true
+            } else {
+                false
+            }
         }
+        Err(_) => false
     };
 
-    // MSCCL XML files location
-    let msccl_xmls_directory = match std::env::var("MSCCL_XMLS") {
+    // Launcher used to start the NCCL tests on every node (defaults to mpirun)
+    let launcher = match std::env::var("LAUNCHER") {
         Ok(v) => {
-            debug!("MSCCL_XMLS set to: {}", v);
-            PathBuf::from(v)
+            debug!("LAUNCHER set to: {}", v);
+            launcher_from_name(v.as_str())?
         },
         Err(_) => {
-            panic!("[ERROR] Envvar MSCCL_XMLS not set!");
+            debug!("LAUNCHER not set, will use mpirun.");
+            launcher_from_name("mpirun")?
         }
     };
 
-    // MPI hostfile
-    let mpi_hostfile_path = match std::env::var("MPI_HOSTFILE") {
+    // Order the experiments are run in ('generated' or 'xml' to run the experiments sharing an XML file back-to-back)
+    let experiment_order = match std::env::var("EXPERIMENT_ORDER") {
         Ok(v) => {
-            debug!("MPI_HOSTFILE set to: {}", v);
-            PathBuf::from(v)
+            debug!("EXPERIMENT_ORDER set to: {}", v);
+            let order = ExperimentOrder::from_name(v.as_str())?;
+            if order == ExperimentOrder::ByXml {
+                info!("Found 'EXPERIMENT_ORDER=xml', will run the experiments sharing an XML file back-to-back.");
+            }
+            order
         },
         Err(_) => {
-            panic!("[ERROR] Envvar MPI_HOSTFILE not set!");
+            debug!("EXPERIMENT_ORDER not set, will run the experiments in the generated order.");
+            ExperimentOrder::Generated
         }
-    
     };
 
-    #[cfg(not(feature = "no_check_paths"))]
-    if !mpi_hostfile_path.exists() {
-        panic!(
-            "[ERROR] Envvar MPI_HOSTFILE not found at: {}",
-            mpi_hostfile_path.to_str().unwrap()
-        );
+    // Check if permutations with missing XML files should be dropped instead of quitting
+    let skip_missing_xmls = match std::env::var("SKIP_MISSING_XMLS") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'SKIP_MISSING_XMLS=true', will drop permutations whose XML file is not found.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Check if experiments whose XML file is missing when their turn comes should be skipped instead of launched
+    // Note: Unlike SKIP_MISSING_XMLS, the permutations are kept (and listed in the plan), so XML files that appear while
+    //       the sweep runs are still used. The sweep is reported as degraded if any experiment was skipped.
+    let skip_missing_xmls_at_run_time = match std::env::var("SKIP_MISSING_XMLS_AT_RUN_TIME") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'SKIP_MISSING_XMLS_AT_RUN_TIME=true', will skip experiments whose XML file is missing when they are run.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Check if permutations with missing XML files should run with NCCL's own algorithms instead (without MSCCL)
+    // Note: Takes precedence over SKIP_MISSING_XMLS. The manifest notes which experiments ran without MSCCL.
+    let nccl_fallback_for_missing_xmls = match std::env::var("NCCL_FALLBACK_FOR_MISSING_XMLS") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'NCCL_FALLBACK_FOR_MISSING_XMLS=true', will run permutations whose XML file is not found without MSCCL.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Check if GPU power draw/utilization should be sampled during each experiment
+    let sample_power = match std::env::var("SAMPLE_POWER") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'SAMPLE_POWER=true', will sample GPU power draw and utilization with 'nvidia-smi'.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Where to export the parsed results as Parquet (optional)
+    // Note: Results are written incrementally (one row group per experiment) unless 'PARQUET_SINGLE_SHOT' is set. Requires
+    //       the `dataframe` feature (on by default), as do 'RESULT_COLUMNS' and 'BANDWIDTH_UNIT', which only apply to it.
+    #[cfg(feature = "dataframe")]
+    let mut parquet_exporter = match std::env::var("PARQUET_OUTPUT_PATH") {
+        Ok(v) => {
+            debug!("PARQUET_OUTPUT_PATH set to: {}", v);
+            let single_shot = match std::env::var("PARQUET_SINGLE_SHOT") {
+                Ok(v) => v.to_lowercase() == "true" || v.to_lowercase() == "1",
+                Err(_) => false,
+            };
+            Some(ParquetExporter::new(PathBuf::from(v).as_path(), !single_shot))
+        },
+        Err(_) => None
+    };
+
+    #[cfg(not(feature = "dataframe"))]
+    if std::env::var("PARQUET_OUTPUT_PATH").is_ok() {
+        warn!("PARQUET_OUTPUT_PATH is set, but this build has no DataFrame support (build with '--features dataframe'). Will not write a Parquet file.");
     }
 
-    // Number of Nodes
-    let num_nodes = match std::env::var("NUM_NODES") {
+    // Which columns of the parsed rows to export (optional; comma-separated, e.g., "size,oop_bus_bw,ip_bus_bw")
+    // Note: Defaults to all of them. The columns identifying the experiment are always exported.
+    #[cfg(feature = "dataframe")]
+    let result_columns = match std::env::var("RESULT_COLUMNS") {
         Ok(v) => {
-            debug!("NUM_NODES set to: {}", v);
-            v.parse::<u64>().unwrap()
+            debug!("RESULT_COLUMNS set to: {}", v);
+            let columns = v.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect::<Vec<String>>();
+            validate_row_columns(&columns)?;
+            Some(columns)
         },
-        Err(_) => {
-            panic!("[ERROR] Envvar NUM_NODES not set!");
+        Err(_) => None
+    };
+
+    // Check if a CSV of the bus bandwidth of every repetition (per message size) should be written for each config
+    let per_size_csv = match std::env::var("PER_SIZE_CSV") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'PER_SIZE_CSV=true', will write a per-size bus bandwidth CSV for each config.");
+                true
+            } else {
+                false
+            }
         }
+        Err(_) => false
     };
 
-    // GPUs per Node
-    let gpus_per_node = match std::env::var("GPUS_PER_NODE") {
+    // Check if an index of every experiment's output files (`index.csv`) should be written as experiments complete
+    let log_index = match std::env::var("LOG_INDEX") {
         Ok(v) => {
-            debug!("GPUS_PER_NODE set to: {}", v);
-            v.parse::<u64>().unwrap()
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'LOG_INDEX=true', will write an index of every experiment's output files.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Check how the tokens of output file names should be put together (defaults give the usual `a_b_..._i0.log` names)
+    let output_filename_format = FilenameFormat {
+        separator: match std::env::var("OUTPUT_FILENAME_SEPARATOR") {
+            Ok(v) => {
+                debug!("OUTPUT_FILENAME_SEPARATOR set to: {}", v);
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => {
+                        validate_filename_separator(c)?;
+                        c
+                    },
+                    _ => return Err(format!("OUTPUT_FILENAME_SEPARATOR must be a single character, got: '{}'", v).into()),
+                }
+            },
+            Err(_) => FilenameFormat::default().separator,
         },
-        Err(_) => {
-            panic!("[ERROR] Envvar GPUS_PER_NODE not set!");
+        iteration_token: match std::env::var("OUTPUT_FILENAME_ITERATION") {
+            Ok(v) => {
+                if v.to_lowercase() == "false" || v.to_lowercase() == "0" {
+                    info!("Found 'OUTPUT_FILENAME_ITERATION=false', will leave the iteration token out of output file names.");
+                    false
+                } else {
+                    true
+                }
+            }
+            Err(_) => true
+        },
+        ..FilenameFormat::default()
+    };
+
+    // Check if the per-rank topology (rings, trees, connections, and NICs) NCCL reports at `NCCL_DEBUG=INFO` should be
+    // summarized next to each experiment's log
+    let topology_summary = match std::env::var("TOPOLOGY_SUMMARY") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'TOPOLOGY_SUMMARY=true', will write a per-rank topology summary for each experiment.");
+                true
+            } else {
+                false
+            }
         }
+        Err(_) => false
     };
 
-    // Experiments Output Directory
-    let experiments_output_dir = match std::env::var("EXPERIMENTS_OUTPUT_DIR") {
+    // Patterns of NCCL output that mean a run is doomed, so it is killed right away instead of waiting for it to end or
+    // time out (comma-separated; set to 'none' to disable)
+    let fatal_error_patterns = match std::env::var("FATAL_ERROR_PATTERNS") {
         Ok(v) => {
-            debug!("EXPERIMENTS_OUTPUT_DIR set to: {}", v);
-            let path = PathBuf::from(v);
+            debug!("FATAL_ERROR_PATTERNS set to: {}", v);
+            if v.trim().to_lowercase() == "none" {
+                info!("Found 'FATAL_ERROR_PATTERNS=none', will not kill experiments early on fatal errors.");
+                Vec::new()
+            } else {
+                v.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect::<Vec<String>>()
+            }
+        },
+        Err(_) => DEFAULT_FATAL_ERROR_PATTERNS.iter().map(|p| p.to_string()).collect::<Vec<String>>(),
+    };
 
-            // Verify that the directory exists. Otherwise, create it.
-            if !path.exists() {
-                std::fs::create_dir(path.as_path())?;
-                debug!("Created experiment log output directory at: {:?}", path);
+    // How many lines at the end of stderr are kept in memory for the errors of a run (bounds memory on runaway output,
+    // e.g., at `NCCL_DEBUG=TRACE`; the log and stderr files still get every line)
+    let stderr_tail_lines = match std::env::var("STDERR_TAIL_LINES") {
+        Ok(v) => {
+            debug!("STDERR_TAIL_LINES set to: {}", v);
+            v.parse::<usize>().map_err(|e| format!("Invalid STDERR_TAIL_LINES value '{}': {}", v, e))?
+        }
+        Err(_) => DEFAULT_STDERR_TAIL_LINES,
+    };
+
+    // Check if every run should be checked for actually using EFA when EFA_PATH/AWS_OFI_NCCL_PATH are set (a stale path
+    // silently falls back to sockets; needs `NCCL_DEBUG=INFO`)
+    let check_efa_transport = match std::env::var("CHECK_EFA_TRANSPORT") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'CHECK_EFA_TRANSPORT=true', will check that each experiment used EFA.");
+                true
             } else {
-                debug!("Experiment log output directory already exists at: {:?}", path);
+                false
             }
+        }
+        Err(_) => false
+    };
 
-            path
+    // Check if a compact JSON summary of the bus bandwidths (peak, peak size, mean, min, max) should be written for each experiment
+    let summary_json = match std::env::var("SUMMARY_JSON") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'SUMMARY_JSON=true', will write a JSON summary for each experiment.");
+                true
+            } else {
+                false
+            }
         }
-        Err(_) => {
-            panic!("[ERROR] Envvar EXPERIMENTS_OUTPUT_DIR not set!");
+        Err(_) => false
+    };
+
+    // Check if per-iteration timings should be exported as a time series per message size (for studying jitter)
+    // Note: Needs an nccl-tests build that prints them (see `IterationTiming`), enabled with its flag in EXTRA_TEST_ARGS
+    let iteration_timeseries = match std::env::var("ITERATION_TIMESERIES") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'ITERATION_TIMESERIES=true', will export the per-iteration timings of each experiment.");
+                true
+            } else {
+                false
+            }
         }
+        Err(_) => false
     };
 
-    // Check if should skip previously completed experiments (ala makefile)
-    let skip_finished = match std::env::var("SKIP_FINISHED") {
+    // Check if the details of failed experiments should be collected in an errors log ('errors.log' in the output dir)
+    let errors_log_enabled = match std::env::var("ERRORS_LOG") {
         Ok(v) => {
             if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
-                info!("⏭️ Found 'SKIP_FINISHED=true', will skip experiments that already have an output file! ⏭️");
+                info!("Found 'ERRORS_LOG=true', will collect the details of failed experiments in an errors log.");
                 true
             } else {
-                info!("Found 'SKIP_FINISHED=false', will NOT skip experiments that already have an output file.");
                 false
             }
         }
-        Err(_) => {
-            info!("Did not find a setting for 'SKIP_FINISHED', will NOT skip experiments that already have an output file.");
-            false
+        Err(_) => false
+    };
+
+    // Check if the stdout of the NCCL tests should be mirrored to the harness's own stdout as it arrives
+    // Note: Otherwise, it only shows up at the debug log level (it is always written to the log file)
+    let tee_stdout = match std::env::var("TEE_STDOUT") {
+        Ok(v) => {
+            if (v.to_lowercase() == "true" || v.to_lowercase() == "1") && !quiet {
+                info!("Found 'TEE_STDOUT=true', will mirror the output of the NCCL tests to stdout.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Check if the parsed rows of each repetition should be re-emitted as an nccl-tests table (for tools that expect it)
+    let nccl_table_output = match std::env::var("NCCL_TABLE_OUTPUT") {
+        Ok(v) => {
+            if v.to_lowercase() == "true" || v.to_lowercase() == "1" {
+                info!("Found 'NCCL_TABLE_OUTPUT=true', will write the parsed rows of each repetition in the nccl-tests table format.");
+                true
+            } else {
+                false
+            }
         }
+        Err(_) => false
+    };
+
+    // Unit of the exported bandwidth columns with explicit unit suffixes ("GB/s" as reported by NCCL tests, or "Gbit/s")
+    #[cfg(feature = "dataframe")]
+    let bandwidth_unit = match std::env::var("BANDWIDTH_UNIT") {
+        Ok(v) => {
+            debug!("BANDWIDTH_UNIT set to: {}", v);
+            BandwidthUnit::from_name(v.as_str())?
+        },
+        Err(_) => BandwidthUnit::GigabytesPerSec
+    };
+
+    // Where to write a Markdown report of the sweep (optional)
+    let markdown_report_path = match std::env::var("MARKDOWN_REPORT_PATH") {
+        Ok(v) => {
+            debug!("MARKDOWN_REPORT_PATH set to: {}", v);
+            Some(PathBuf::from(v))
+        },
+        Err(_) => None
     };
 
     // Check if doing a dry run
@@ -193,6 +899,107 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(_) => false
     };
 
+    // Link bandwidths of the hardware, used to compare results against the theoretical peak (optional)
+    let hardware_spec = match (std::env::var("NVLINK_BW_GBPS"), std::env::var("NIC_BW_GBPS")) {
+        (Ok(nvlink), Ok(nic)) => {
+            debug!("NVLINK_BW_GBPS set to: {}, NIC_BW_GBPS set to: {}", nvlink, nic);
+            Some(HardwareSpec {
+                nvlink_bw: nvlink.parse::<f64>()?,
+                nic_bw: nic.parse::<f64>()?,
+            })
+        },
+        (Err(_), Err(_)) => {
+            debug!("NVLINK_BW_GBPS and NIC_BW_GBPS not set, will not compare against the theoretical peak.");
+            None
+        },
+        _ => return Err("Both NVLINK_BW_GBPS and NIC_BW_GBPS must be set to compare against the theoretical peak".into()),
+    };
+
+    // Fraction the bus bandwidth may exceed the theoretical peak by before an experiment is marked suspect
+    let peak_margin = match std::env::var("PEAK_BW_MARGIN") {
+        Ok(v) => {
+            let margin = v.parse::<f64>().map_err(|e| format!("Invalid PEAK_BW_MARGIN value '{}': {}", v, e))?;
+            if !margin.is_finite() || margin < 0.0 {
+                return Err(format!("PEAK_BW_MARGIN must be a non-negative fraction (e.g., 0.05), got: {}", v).into());
+            }
+            if hardware_spec.is_none() {
+                warn!("PEAK_BW_MARGIN is set, but is only used with NVLINK_BW_GBPS and NIC_BW_GBPS set.");
+            }
+            margin
+        }
+        Err(_) => DEFAULT_PEAK_MARGIN,
+    };
+
+    // Minimum bus bandwidths experiments must reach to pass, e.g., "all-reduce=150,all-reduce@1G=120,*=40" (optional)
+    let bandwidth_gates = match std::env::var("MIN_BUS_BW_GATES") {
+        Ok(v) => {
+            let gates = parse_bandwidth_gates(v.as_str())?;
+            info!("Found 'MIN_BUS_BW_GATES', will fail experiments that miss any of {} bandwidth gate(s).", gates.len());
+            gates
+        }
+        Err(_) => Vec::new(),
+    };
+
+    // Check if a dry run should also let the launcher validate every launch command (without running anything)
+    let dry_run_validate = match std::env::var("DRY_RUN_VALIDATE") {
+        Ok(v) => {
+            if dry_run && (v.to_lowercase() == "true" || v.to_lowercase() == "1") {
+                info!("Found 'DRY_RUN_VALIDATE=true', will let the launcher validate every launch command.");
+                true
+            } else {
+                false
+            }
+        }
+        Err(_) => false
+    };
+
+    // Metadata that describes the sweep in the manifest, the Markdown report, and the header of each log (optional)
+    // Note: The git commit is detected from the current directory if not set
+    let sweep_metadata = SweepMetadata {
+        run_id: std::env::var("SWEEP_RUN_ID").ok(),
+        git_commit: match std::env::var("SWEEP_GIT_COMMIT") {
+            Ok(v) => Some(v),
+            Err(_) => match Command::new("git").args(["rev-parse", "HEAD"]).output() {
+                Ok(output) if output.status.success() => {
+                    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+                }
+                _ => {
+                    debug!("Could not detect the git commit of the current directory.");
+                    None
+                }
+            },
+        },
+        cluster_name: std::env::var("SWEEP_CLUSTER_NAME").ok(),
+        notes: std::env::var("SWEEP_NOTES").ok(),
+    };
+    debug!("Sweep metadata: {:?}", sweep_metadata);
+
+    // Optionally write the results to a SQLite database as experiments complete (requires the `sqlite` feature)
+    // Note: The database is only opened once the configuration is resolved (see below)
+    let sqlite_output_path = match std::env::var("SQLITE_OUTPUT_PATH") {
+        Ok(v) => {
+            debug!("SQLITE_OUTPUT_PATH set to: {}", v);
+            Some(PathBuf::from(v))
+        },
+        Err(_) => None
+    };
+    #[cfg(not(feature = "sqlite"))]
+    if sqlite_output_path.is_some() {
+        warn!("SQLITE_OUTPUT_PATH is set, but this build has no SQLite support (build with '--features sqlite'). Will not write a SQLite database.");
+    }
+
+    // Prometheus pushgateway (optional)
+    let pushgateway_url = match std::env::var("PUSHGATEWAY_URL") {
+        Ok(v) => {
+            info!("Found 'PUSHGATEWAY_URL', will push bandwidth metrics to: {}", v);
+            Some(v)
+        }
+        Err(_) => {
+            debug!("PUSHGATEWAY_URL not set, will not push bandwidth metrics.");
+            None
+        }
+    };
+
     // Experimental setup
     // Independent Variables:
     // • Collective Algorithm (e.g., all_reduce_perf, all_gather_perf, alltoall_perf, broadcast_perf, gather_perf, hypercube_perf, reduce_perf, reduce_scatter_perf, scatter_perf, sendrecv_perf)
@@ -209,14 +1016,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let num_gpus = num_nodes * gpus_per_node;
 
     // Selected
-    let num_repetitions = 2;
+    // Repetitions of each config (`NUM_REPETITIONS`, defaults to 2)
+    let num_repetitions = match std::env::var("NUM_REPETITIONS") {
+        Ok(v) => {
+            debug!("NUM_REPETITIONS set to: {}", v);
+            let n = v.parse::<usize>().map_err(|e| format!("Invalid NUM_REPETITIONS value '{}': {}", v, e))?;
+            if n < 1 {
+                return Err("NUM_REPETITIONS must be at least 1".into());
+            }
+            if n == 1 && skip_warmup_repetition {
+                warn!("SKIP_WARMUP_REPETITION is set with a single repetition per config, so no results will be kept.");
+            }
+            n
+        },
+        Err(_) => 2,
+    };
     let collectives = [
         "all-reduce",
         // "all-gather",
         // "all-to-all",
         // "broadcast",
         // "gather",
-        // "hypercube",  // BROKEN FOR HYPERCUBE BECAUSE THE OUTPUT TABLE IS BLANK FOR REDOP (breaks parsing)
+        // "hypercube",  // Its output table has a blank redop column (parsed as no op, like `none`)
         // "reduce",
         // "reduce-scatter",
         // "scatter",
@@ -234,7 +1055,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "float",
         // "int32",
         // "int8",
+        // "half",      // Requires a recent nccl-tests build (also accepts "fp16")
+        // "bfloat16",  // Requires a recent nccl-tests build (also accepts "bf16")
+        // "fp8",       // Requires a recent nccl-tests build (maps to "f8e4m3")
     ];
+
+    // Let repeatable command-line flags override the lists above for quick one-off sweeps
+    // (e.g., `--collective all-reduce --collective all-gather --op sum --dtype float`)
+    let collectives = match cli.collectives.iter().map(|v| v.as_str()).collect::<Vec<&str>>() {
+        v if v.is_empty() => collectives.to_vec(),
+        v => {
+            for collective in v.iter() {
+                collective_to_test_exe(collective)?;
+            }
+            info!("Found '--collective', will only sweep the collective(s): {}", v.join(", "));
+            v
+        }
+    };
+    let reduction_ops = match cli.ops.iter().map(|v| v.as_str()).collect::<Vec<&str>>() {
+        v if v.is_empty() => reduction_ops.to_vec(),
+        v => {
+            for op in v.iter() {
+                validate_op(op)?;
+            }
+            info!("Found '--op', will only sweep the reduction op(s): {}", v.join(", "));
+            v
+        }
+    };
+    let data_types = match cli.dtypes.iter().map(|v| v.as_str()).collect::<Vec<&str>>() {
+        v if v.is_empty() => data_types.to_vec(),
+        v => {
+            for dtype in v.iter() {
+                validate_dtype(dtype)?;
+            }
+            info!("Found '--dtype', will only sweep the data type(s): {}", v.join(", "));
+            v
+        }
+    };
+
+    // Distinguish the output files of configs that only differ in their op or data type
+    let output_filename_format = FilenameFormat {
+        op_token: reduction_ops.len() > 1,
+        dtype_token: data_types.len() > 1,
+        ..output_filename_format
+    };
+
     let comm_algorithms = [
         "binary-tree",
         // "binomial-tree",
@@ -243,8 +1108,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "ring",
         // "trinomial-tree"
     ];
+    for comm_algorithm in comm_algorithms {
+        algorithm_chunks_channels(comm_algorithm)?;
+    }
 
-    // Note: These will be determined by the special case generator in the loop (at Ly's request)
+    // The algorithms each collective is swept with (pairs that make no sense are pruned), e.g., "broadcast=binary-tree|ring"
+    // Note: Overrides replace the collective's entry of `COLLECTIVE_ALGORITHMS`
+    let collective_algorithms = match std::env::var("COLLECTIVE_ALGORITHMS") {
+        Ok(v) => {
+            debug!("COLLECTIVE_ALGORITHMS set to: {}", v);
+            let overrides = parse_collective_algorithms(v.as_str())?;
+            for (collective, algorithms) in overrides.iter() {
+                for algorithm in algorithms.iter().filter(|a| *a != "*" && !comm_algorithms.contains(&a.as_str())) {
+                    warn!("COLLECTIVE_ALGORITHMS allows '{}' for {}, but it is not one of the swept algorithms: {}", algorithm, collective, comm_algorithms.join(", "));
+                }
+            }
+            collective_algorithms_table(&overrides)
+        }
+        Err(_) => collective_algorithms_table(&[]),
+    };
+
+    // Note: These are determined per algorithm by `algorithm_chunks_channels` in the loop (at Ly's request)
     // let msccl_potential_channels = [  // NOTE: HANDLED IN THE PERMUTATION GENERATOR BECAUSE THERE ARE SPECIAL CASES!
     //     4,
     //     8,
@@ -266,11 +1150,103 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         4u64,
     ];
     let message_size_range = ("64K", "16G"); // We use a range for all experiments
-    let gpus_as_nodes = [
-        // true, 
-        false
+    // Step through the range by a factor OR by a fixed number of bytes (exactly one must be set)
+    let message_size_step_factor = Some("2");
+    let message_size_step_bytes: Option<&str> = None; // e.g., Some("64M") for fine-grained sweeps
+    validate_step(message_size_step_factor, message_size_step_bytes)?;
+    // Number of timed and warmup iterations per message size (0 timed iterations lets nccl-tests pick the count
+    // per size within the `--timeout` budget, which keeps measurement quality consistent across the size range)
+    let num_iters = 60u64;
+    let num_warmup_iters = 20u64;
+    let test_timeout_secs: Option<u64> = None; // e.g., Some(10) with `num_iters = 0`
+    validate_iters(num_iters, test_timeout_secs)?;
+    // Optionally vary the iterations with the message size as (up to size, warmup iters, timed iters) regimes, which
+    // replace the two above (e.g., more warmup and fewer timed iterations for large messages). Each regime runs as its
+    // own nccl-tests run over its part of the size range.
+    let iteration_regimes = parse_iter_regimes(&[
+        // ("1M", 10, 200),
+        // ("256M", 20, 60),
+        // ("16G", 50, 20),
+    ])?;
+    // Whether each GPU is treated as its own node by the XML (comma-separated in 'GPUS_AS_NODES', e.g., "false,true")
+    // Note: The `gan0`/`gan1` XML files of every variant are checked for when generating the permutations
+    let gpus_as_nodes = match std::env::var("GPUS_AS_NODES") {
+        Ok(v) => {
+            debug!("GPUS_AS_NODES set to: {}", v);
+            v.split(',')
+                .map(|g| match g.trim().to_lowercase().as_str() {
+                    "true" | "1" => Ok(true),
+                    "false" | "0" => Ok(false),
+                    _ => Err(format!("Invalid value in GPUS_AS_NODES: {}", g)),
+                })
+                .collect::<Result<Vec<bool>, String>>()?
+        },
+        Err(_) => vec![
+            // true,
+            false
+        ],
+    };
+    if gpus_as_nodes.contains(&true) && mpi_proc_per_node != gpus_per_node {
+        warn!(
+            "Experiments with GPUs as nodes will use one MPI process per GPU ({} per node) instead of MPI_PROC_PER_NODE ({}).",
+            gpus_per_node, mpi_proc_per_node
+        );
+    }
+    // Number of CPU threads per process (nccl-tests `--nthreads`; must be at least 1)
+    let threads_per_process = [
+        1u64,
+        // 2u64,
+    ];
+    if threads_per_process.iter().any(|&n| n < 1) {
+        return Err("The number of threads per process must be at least 1".into());
+    }
+    // NCCL protocols (`None` leaves NCCL_PROTO unset so NCCL picks; e.g., "Simple", "LL", "LL128")
+    let nccl_protos: [Option<&str>; 1] = [
+        None,
+        // Some("Simple"),
+        // Some("LL"),
+        // Some("LL128"),
+    ];
+    for nccl_proto in nccl_protos.iter().flatten() {
+        validate_nccl_proto(nccl_proto)?;
+    }
+    // NCCL channel bounds as (NCCL_MIN_NCHANNELS, NCCL_MAX_NCHANNELS) (`None` leaves the bound unset so NCCL picks)
+    // Note: Mostly useful for the default NCCL algorithms, since MSCCL sets its channels in the XML
+    let nccl_nchannels: [(Option<u64>, Option<u64>); 1] = [
+        (None, None),
+        // (Some(4), Some(4)),
+        // (Some(8), Some(8)),
+        // (Some(16), Some(16)),
+    ];
+    for (min, max) in nccl_nchannels {
+        validate_nchannels(min, max)?;
+    }
+    // Root ranks to sweep for rooted collectives (broadcast, reduce, gather, scatter; ignored for the others)
+    let roots = [
+        0u64,
+        // 1u64,
+    ];
+    // Number of CUDA graph launches (`None` disables CUDA graphs; requires a recent nccl-tests build)
+    let cudagraph_launches = [
+        None,
+        // Some(10u64),
     ];
 
+    // MPI processes per node to sweep (`None` uses MPI_PROC_PER_NODE; e.g., Some(4) to undersubscribe or Some(16) to
+    // oversubscribe 8-GPU nodes)
+    // Note: Overridden permutations get a `_ppn` token in their file names. GPUs-as-nodes permutations ignore overrides.
+    let procs_per_node_overrides: [Option<u64>; 1] = [
+        None,
+        // Some(4u64),
+        // Some(16u64),
+    ];
+    if procs_per_node_overrides.iter().any(|o| o.is_some()) {
+        let hosts = if mpi_hostfile_path.exists() { parse_hostfile(mpi_hostfile_path.as_path())? } else { Vec::new() };
+        for &ppn in procs_per_node_overrides.iter().flatten() {
+            validate_proc_per_node_override(gpus_per_node, ppn, &hosts)?;
+        }
+    }
+
     // Blacklist certain XML files that hang or otherwise misbehave
     let blacklist: [&str; 0] = [];  // Use this if you want the blacklist to contain nothing
     // let blacklist = [
@@ -281,127 +1257,438 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let nccl_debug_level = "INFO"; // Use `TRACE` for replayable trace information on every call
 
+    // Print the resolved configuration and exit (`--print-config`)
+    // Note: Comes before anything is created on disk (the output directory, the SQLite database, ...)
+    if print_config {
+        let config = ExperimentConfig {
+            env_paths: env_paths.clone(),
+            metadata: sweep_metadata.clone(),
+            num_repetitions,
+            collectives: collectives.iter().map(|v| v.to_string()).collect(),
+            reduction_ops: reduction_ops.iter().map(|v| v.to_string()).collect(),
+            data_types: data_types.iter().map(|v| v.to_string()).collect(),
+            comm_algorithms: comm_algorithms.iter().map(|v| v.to_string()).collect(),
+            collective_algorithms: collective_algorithms.clone(),
+            buffer_sizes: buffer_sizes.to_vec(),
+            message_size_range: (message_size_range.0.to_string(), message_size_range.1.to_string()),
+            message_size_step_factor: message_size_step_factor.map(|v| v.to_string()),
+            message_size_step_bytes: message_size_step_bytes.map(|v| v.to_string()),
+            num_iters,
+            num_warmup_iters,
+            test_timeout_secs,
+            iteration_regimes: iteration_regimes.clone(),
+            gpus_as_nodes: gpus_as_nodes.clone(),
+            threads_per_process: threads_per_process.to_vec(),
+            nccl_protos: nccl_protos.iter().map(|p| p.map(|v| v.to_string())).collect(),
+            nccl_nchannels: nccl_nchannels.to_vec(),
+            roots: roots.to_vec(),
+            cudagraph_launches: cudagraph_launches.to_vec(),
+            procs_per_node_overrides: procs_per_node_overrides.to_vec(),
+            blacklist: blacklist.iter().map(|v| v.to_string()).collect(),
+            nccl_debug_level: nccl_debug_level.to_string(),
+            launcher: std::env::var("LAUNCHER").unwrap_or_else(|_| "mpirun".to_string()),
+            experiment_order,
+            ld_library_path_order: ld_library_path_order.clone(),
+            extra_test_args: extra_test_args.clone(),
+            passthrough_env_vars: passthrough_env_vars.clone(),
+            cuda_visible_devices: cuda_visible_devices.clone(),
+            mpi_binding,
+            ms_gen_xml,
+            timeouts: experiment_timeouts,
+            retry_policy,
+            fatal_error_patterns: fatal_error_patterns.clone(),
+            stderr_tail_lines,
+            parallel_repetitions,
+            cluster_warmup,
+            skip_warmup_repetition,
+            dry_run,
+            dry_run_validate,
+            skip_finished,
+            fail_fast,
+            start_index,
+            profile_index,
+            nsys_args: nsys_args.clone(),
+            skip_missing_xmls,
+            skip_missing_xmls_at_run_time,
+            nccl_fallback_for_missing_xmls,
+            hardware_spec: hardware_spec.clone(),
+            peak_margin,
+            bandwidth_gates: bandwidth_gates.clone(),
+            anomaly_bw_fraction,
+            inplace_divergence_ratio,
+            check_row_count,
+            check_efa_transport,
+            output_filename_format: output_filename_format.clone(),
+            nested_output_dirs,
+            tee_stdout,
+            summary_json,
+            log_index,
+            per_size_csv,
+            errors_log: errors_log_enabled,
+            nccl_table_output,
+            iteration_timeseries,
+            topology_summary,
+            sample_power,
+            markdown_report_path: markdown_report_path.clone(),
+            parquet_output_path: std::env::var("PARQUET_OUTPUT_PATH").ok().map(PathBuf::from),
+            #[cfg(feature = "dataframe")]
+            result_columns: result_columns.clone(),
+            #[cfg(feature = "dataframe")]
+            bandwidth_unit,
+            sqlite_output_path: sqlite_output_path.clone(),
+            pushgateway_url: pushgateway_url.clone(),
+        };
+        println!("{}", serde_json::to_string_pretty(&config)?);
+
+        return Ok(());
+    }
+
+    // Create the output directory (only now, so that printing the configuration leaves no trace)
+    env_paths.create_output_dir()?;
+
+    #[cfg(feature = "sqlite")]
+    let mut sqlite_exporter = match sqlite_output_path.as_ref() {
+        Some(path) => Some(SqliteExporter::new(path.as_path(), &sweep_metadata)?),
+        None => None,
+    };
+
     // Store list of all experiment permutations
     let mut permutations = Vec::new();
     let mut experiment_descriptors = Vec::new();
+    let mut num_dropped_permutations = 0;
+
+    // Create the record-keeping manifest
+    let mut manifest_collection = Vec::new();
+
+    // Replaying a single experiment doesn't generate any permutations
+    let collectives = if replay_params.is_some() { Vec::new() } else { collectives };
 
     // Create permutations
-    for collective in collectives {
+    let mut explained_algorithms = std::collections::HashSet::new();
+    for collective in collectives.iter().copied() {
         // Build executable path
         let collective_exe = collective_to_test_exe(collective)?;
-        let nccl_test_executable = nccl_test_bins.join(collective_exe.clone());
+        for (_, build_dir) in nccl_test_builds.iter() {
+            let nccl_test_executable = build_dir.join(collective_exe.clone());
 
-        #[cfg(not(feature = "no_check_paths"))]
-        assert!(nccl_test_executable.exists());
+            if validate_only {
+                if !nccl_test_executable.exists() {
+                    validation_problems.push(format!("NCCL tests executable not found at: {}", nccl_test_executable.to_str().unwrap()));
+                }
+            } else if !list_xmls {
+                #[cfg(not(feature = "no_check_paths"))]
+                assert!(nccl_test_executable.exists());
+            }
+        }
+
+        // Only rooted collectives get a root (non-rooted collectives never get the `--root` flag)
+        let root_options = if is_rooted_collective(collective) {
+            roots.iter().map(|&r| Some(r)).collect::<Vec<Option<u64>>>()
+        } else {
+            vec![None]
+        };
+
+        if explain {
+            if !is_reducing_collective(collective) && reduction_ops.len() > 1 {
+                info!("[explain] {}: not a reducing collective, so only the first op ({}) is swept instead of: {}", collective, reduction_ops[0], reduction_ops.join(", "));
+            }
+            if is_rooted_collective(collective) {
+                info!("[explain] {}: rooted collective, so each root is swept: {:?}", collective, roots);
+            }
+        }
 
         // Run experiments across all variations
         for buffer_size in buffer_sizes {
-            for data_type in data_types {
-                for reduction_op in reduction_ops {
+            for &data_type in data_types.iter() {
+                // Get the data type name nccl-tests expects
+                let nc_dtype = validate_dtype(data_type)?;
+
+                // Non-reducing collectives ignore the op, so only run them with the first one (instead of duplicates)
+                let collective_ops = if is_reducing_collective(collective) {
+                    &reduction_ops[..]
+                } else {
+                    &reduction_ops[..reduction_ops.len().min(1)]
+                };
+
+                for &reduction_op in collective_ops.iter() {
                     for comm_algorithm in comm_algorithms {
+                        // Skip algorithms that make no sense for the collective
+                        if !collective_algorithm_allowed(&collective_algorithms, collective, comm_algorithm) {
+                            debug!("Skipping the '{}' algorithm for {} (not allowed for it by COLLECTIVE_ALGORITHMS).", comm_algorithm, collective);
+                            if explain && explained_algorithms.insert((collective, comm_algorithm)) {
+                                info!("[explain] {} {}: not swept because COLLECTIVE_ALGORITHMS does not allow the algorithm for the collective", collective, comm_algorithm);
+                            }
+                            continue;
+                        }
+
                         // Handle special cases for different communication algorithms
-                        let (msccl_potential_chunks, msccl_potential_channels) =
-                            match comm_algorithm {
-                                "binary-tree" => (vec![1u64, 2, 4, 8, 16], vec![4u64, 8, 16]),
-                                // "binomial-tree" => (vec![8, 16, 32, 64, 128], vec![1, 2]),
-                                // "recursive-doubling-halving" => (vec![8, 16, 32], vec![1, 2]),
-                                "ring" => (vec![1u64, 2], vec![4u64, 8, 16]),
-                                // "double-binary-tree" => (vec![8, 16, 32, 64, 128, 256], vec![1, 2]),
-                                // "double-binomial-tree" => (vec![8, 16, 32, 64, 128], vec![1, 2]),
-                                // "trinomial-tree" => (vec![8, 16, 32, 64, 128], vec![1, 2]),
-                                // "recursive-doubling" => (vec![8, 16, 32], vec![1, 2]),
-                                _ => panic!("[ERROR] Unknown comm_algorithm: {}", comm_algorithm),
-                            };
+                        let (msccl_potential_chunks, msccl_potential_channels) = algorithm_chunks_channels(comm_algorithm)?;
+                        if explain && explained_algorithms.insert((collective, comm_algorithm)) {
+                            info!(
+                                "[explain] {} {}: sweeping every combination of chunks {:?} and channels {:?} ({} combination(s); rule: the '{}' entry of ALGORITHM_CHUNKS_CHANNELS)",
+                                collective, comm_algorithm, msccl_potential_chunks, msccl_potential_channels,
+                                msccl_potential_chunks.len() * msccl_potential_channels.len(), comm_algorithm
+                            );
+                        }
 
                         // Create permutations
                         for msccl_chunks in msccl_potential_chunks.iter() {
                             for msccl_channels in msccl_potential_channels.iter() {
-                                for gpu_as_node in gpus_as_nodes {
-                                    // Figure out the name of potential the XML file name for this experiment
-                                    let xml_file_name = params_to_xml(
-                                        collective,
-                                        comm_algorithm,
-                                        num_nodes,
-                                        num_gpus.clone(),
-                                        msccl_channels.clone(),
-                                        msccl_chunks.clone(),
-                                        gpu_as_node,
-                                    )?;
-
-                                    let xml_file = msccl_xmls_directory.join(xml_file_name);
-
-                                    // Verify that the XML file exists
-                                    // Note: We want to fail early if the XML file is not found rather than failing mid-way through
-                                    //       running the experiments.
-                                    
-                                    if !xml_file.exists() {
-                                        #[cfg(feature = "no_check_paths")]
-                                        warn!("During permutation generation, XML file not found at: {}. Continuing because 'no_check_paths' cfg is set", xml_file.to_str().unwrap());
-
-                                        #[cfg(not(feature = "no_check_paths"))]
-                                        panic!("During permutation generation, XML file not found at: {}. Quitting.", xml_file.to_str().unwrap());
+                                // Drop combinations that are known to give a broken schedule (they'd only fail or hang)
+                                let infeasible = check_chunks_channels_feasible(comm_algorithm, num_gpus, *msccl_channels, *msccl_chunks).err();
+                                if let Some(reason) = &infeasible {
+                                    if explain {
+                                        info!("[explain] {} {} {}: dropped, {}", collective, reduction_op, nc_dtype, reason);
                                     } else {
-                                        debug!("Found XML file at: {}", xml_file.to_str().unwrap());
+                                        warn!("Dropping permutations of {} {} {}: {}", collective, reduction_op, nc_dtype, reason);
                                     }
+                                }
 
-                                    // Create a full set of experiment parameters for this permutation
-                                    let experiment = MscclExperimentParams {
-                                        // Environment params
-                                        cuda_path: cuda_path.clone(),
-                                        efa_path: efa_path.clone(),
-                                        aws_ofi_nccl_path: aws_ofi_nccl_path.clone(),
-                                        openmpi_path: openmpi_path.clone(),
-                                        msccl_path: msccl_path.clone(),
-
-                                        // Exe params
-                                        executable: nccl_test_executable.clone(),
-
-                                        // MSCCL params
-                                        algorithm: comm_algorithm.to_string(),
-                                        ms_xml_file: xml_file,
-                                        ms_channels: msccl_channels.clone(),
-                                        ms_chunks: msccl_chunks.clone(),
-                                        gpu_as_node,
-                                        num_nodes,
-                                        total_gpus: num_gpus,
-                                        buffer_size,
-
-                                        // MPI Params
-                                        mpi_hostfile_path: mpi_hostfile_path.clone(),
-                                        mpi_proc_per_node: gpus_per_node.clone(),
-
-                                        // NCCL Tests params
-                                        nc_collective: collective.to_string(),
-                                        nc_op: reduction_op.to_string(),
-                                        nc_dtype: data_type.to_string(),
-                                        nc_num_threads: 1,
-                                        nc_num_gpus: 1,
-                                        nc_min_bytes: message_size_range.0.to_string(),
-                                        nc_max_bytes: message_size_range.1.to_string(),
-                                        nc_step_factor: "2".to_string(),
-                                        nc_num_iters: 60,
-                                        nc_num_warmup_iters: 20,
-
-                                        // NCCL Env params
-                                        nccl_debug_level: nccl_debug_level.to_string(),
-                                        nccl_algo:
-                                            "Tree,Ring,CollnetDirect,CollnetChain,NVLS,NVLSTree"
-                                                .to_string(), // Default NCCL
-                                    };
-
-                                    // Add the full experiment to the list
-                                    experiment_descriptors.push(experiment);
-
-                                    // Add the permutation to the list
-                                    permutations.push(Permutation {
-                                        collective_exe: collective_exe.to_string(),
-                                        data_type: data_type.to_string(),
-                                        reduction_op: reduction_op.to_string(),
-                                        comm_algorithm: comm_algorithm.to_string(),
-                                        msccl_channel: Some(msccl_channels.to_string()),
-                                        msccl_chunk: Some(msccl_chunks.to_string()),
-                                        buffer_size: Some(buffer_size.to_string()),
-                                    });
+                                for gpu_as_node in gpus_as_nodes.iter().copied() {
+                                    for cudagraph in cudagraph_launches {
+                                        for num_threads in threads_per_process {
+                                            for nccl_proto in nccl_protos {
+                                                for nc_root in root_options.iter() {
+                                                    for (nccl_min_nchannels, nccl_max_nchannels) in nccl_nchannels {
+                                                        for &ppn_override in procs_per_node_overrides.iter() {
+                                                            // GPUs-as-nodes permutations always use one process per GPU, so overrides don't apply
+                                                            if gpu_as_node && ppn_override.is_some() {
+                                                                continue;
+                                                            }
+
+                                                            for (build_name, build_dir, net_plugin, host_group) in nccl_test_builds.iter().flat_map(|(name, dir)| {
+                                                                let host_group_options = &host_group_options;
+                                                                net_plugin_options.iter().flat_map(move |plugin| {
+                                                                    host_group_options.iter().map(move |group| (name, dir, plugin, group))
+                                                                })
+                                                            }) {
+                                                                if infeasible.is_some() {
+                                                                    num_dropped_permutations += 1;
+                                                                    continue;
+                                                                }
+
+                                                                // Figure out the name of potential the XML file name for this experiment
+                                                                let xml_file_name = params_to_xml(
+                                                                    collective,
+                                                                    comm_algorithm,
+                                                                    num_nodes,
+                                                                    num_gpus.clone(),
+                                                                    msccl_channels.clone(),
+                                                                    msccl_chunks.clone(),
+                                                                    gpu_as_node,
+                                                                )?;
+
+                                                                let xml_file = msccl_xmls_directory.join(xml_file_name);
+
+                                                                // Remember the XML file if only listing the expected XMLs (deduplicated by the set)
+                                                                if list_xmls {
+                                                                    expected_xmls.insert(xml_file.clone());
+                                                                }
+
+                                                                // Verify that the XML file exists
+                                                                // Note: We want to fail early if the XML file is not found rather than failing mid-way through
+                                                                //       running the experiments.
+                                    
+                                                                let mut ms_xml_fallback = false;
+                                                                if !xml_file.exists() {
+                                                                    if explain {
+                                                                        info!(
+                                                                            "[explain] {} {} {} {} chunks {} channels {} (GPU as node: {}): XML file not found at: {}",
+                                                                            collective, reduction_op, nc_dtype, comm_algorithm, msccl_chunks, msccl_channels, gpu_as_node, xml_file.to_str().unwrap()
+                                                                        );
+                                                                    }
+
+                                                                    // Just record the problem when validating
+                                                                    if validate_only {
+                                                                        validation_problems.push(format!("XML file not found at: {}", xml_file.to_str().unwrap()));
+                                                                    }
+
+                                                                    // Don't quit when only validating or listing the expected XMLs
+                                                                    if validate_only || list_xmls {
+                                                                        num_dropped_permutations += 1;
+                                                                        continue;
+                                                                    }
+
+                                                                    // Run the permutation with NCCL's own algorithms instead (rather than quitting) if requested at runtime
+                                                                    if nccl_fallback_for_missing_xmls {
+                                                                        warn!("During permutation generation, XML file not found at: {}. Will run this permutation without MSCCL because 'NCCL_FALLBACK_FOR_MISSING_XMLS' is set", xml_file.to_str().unwrap());
+                                                                        ms_xml_fallback = true;
+                                                                    }
+                                                                    // Drop the permutation (rather than quitting) if requested at runtime
+                                                                    else if skip_missing_xmls {
+                                                                        warn!("During permutation generation, XML file not found at: {}. Dropping this permutation because 'SKIP_MISSING_XMLS' is set", xml_file.to_str().unwrap());
+
+                                                                        // Update manifest
+                                                                        manifest_collection.push(ManifestEntry {
+                                                                            collective: collective.to_string(),
+                                                                            op: reduction_op.to_string(),
+                                                                            dtype: nc_dtype.clone(),
+                                                                            algorithm: comm_algorithm.to_string(),
+                                                                            num_channels: *msccl_channels,
+                                                                            num_chunks: *msccl_chunks,
+                                                                            num_gpus,
+                                                                            buffer_size_factor: buffer_size,
+                                                                            overall_result: ResultDescription::Skipped,
+                                                                            flagged_sizes: Vec::new(),
+                                                                            peak_bus_bw: None,
+                                                                            efficiency: None,
+                                                                            notes: Vec::new(),
+                                                                            config_hash: None,
+                                                                            build: build_name.clone(),
+                                                                            avg_bus_bw: None,
+                                                                            net_plugin: net_plugin.as_ref().map(|p| p.name.clone()),
+                                                                            host_group: host_group.as_ref().map(|(name, _)| name.clone()),
+                                                                        });
+                                                                        num_dropped_permutations += 1;
+
+                                                                        continue;
+                                                                    }
+
+                                                                    // Keep the permutation, but skip it at run time if the XML file is still missing then
+                                                                    else if skip_missing_xmls_at_run_time {
+                                                                        warn!("During permutation generation, XML file not found at: {}. Will skip this permutation at run time if it is still missing because 'SKIP_MISSING_XMLS_AT_RUN_TIME' is set", xml_file.to_str().unwrap());
+                                                                    }
+
+                                                                    #[cfg(feature = "no_check_paths")]
+                                                                    if !ms_xml_fallback && !skip_missing_xmls_at_run_time {
+                                                                        warn!("During permutation generation, XML file not found at: {}. Continuing because 'no_check_paths' cfg is set", xml_file.to_str().unwrap());
+                                                                    }
+
+                                                                    #[cfg(not(feature = "no_check_paths"))]
+                                                                    if !ms_xml_fallback && !skip_missing_xmls_at_run_time {
+                                                                        panic!("During permutation generation, XML file not found at: {}. Quitting.", xml_file.to_str().unwrap());
+                                                                    }
+                                                                } else {
+                                                                    debug!("Found XML file at: {}", xml_file.to_str().unwrap());
+                                                                }
+
+                                                                // Every GPU must be its own rank when the XML treats it as a node
+                                                                // Note: Oversubscribed nodes (more processes than GPUs) give each process a single GPU
+                                                                let (exp_proc_per_node, exp_num_gpus) = if gpu_as_node {
+                                                                    (gpus_per_node, 1)
+                                                                } else if let Some(ppn) = ppn_override {
+                                                                    (ppn, gpus_per_proc(gpus_per_node, ppn).unwrap_or(1))
+                                                                } else {
+                                                                    (mpi_proc_per_node, nc_num_gpus)
+                                                                };
+
+                                                                // Create a full set of experiment parameters for this permutation
+                                                                let experiment = MscclExperimentParams {
+                                                                    // Environment params
+                                                                    cuda_path: cuda_path.clone(),
+                                                                    efa_path: efa_path.clone(),
+                                                                    aws_ofi_nccl_path: aws_ofi_nccl_path.clone(),
+                                                                    openmpi_path: openmpi_path.clone(),
+                                                                    msccl_path: msccl_path.clone(),
+                                                                    ld_library_path_order: ld_library_path_order.clone(),
+                                                                    passthrough_env_vars: passthrough_env_vars.clone(),
+                                                                    cuda_visible_devices: cuda_visible_devices.as_ref().map(|d| d.join(",")),
+
+                                                                    // Exe params
+                                                                    executable: build_dir.join(collective_exe.clone()),
+                                                                    nc_build: build_name.clone(),
+                                                                    nsys_profile: None,
+                                                                    output_filename_format: output_filename_format.clone(),
+
+                                                                    // MSCCL params
+                                                                    algorithm: comm_algorithm.to_string(),
+                                                                    ms_xml_file: xml_file,
+                                                                    ms_channels: msccl_channels.clone(),
+                                                                    ms_chunks: msccl_chunks.clone(),
+                                                                    ms_gen_xml,
+                                                                    ms_xml_fallback,
+                                                                    gpu_as_node,
+                                                                    num_nodes,
+                                                                    total_gpus: num_gpus,
+                                                                    buffer_size,
+
+                                                                    // MPI Params
+                                                                    mpi_hostfile_path: match host_group {
+                                                                        Some((_, hostfile)) => hostfile.clone(),
+                                                                        None => mpi_hostfile_path.clone(),
+                                                                    },
+                                                                    mpi_host_group: host_group.as_ref().map(|(name, _)| name.clone()),
+                                                                    mpi_proc_per_node: exp_proc_per_node,
+                                                                    mpi_ppn_override: ppn_override.is_some(),
+                                                                    mpi_binding,
+
+                                                                    // NCCL Tests params
+                                                                    nc_collective: collective.to_string(),
+                                                                    nc_op: reduction_op.to_string(),
+                                                                    nc_dtype: nc_dtype.clone(),
+                                                                    nc_num_threads: num_threads,
+                                                                    nc_num_gpus: exp_num_gpus,
+                                                                    nc_min_bytes: message_size_range.0.to_string(),
+                                                                    nc_max_bytes: message_size_range.1.to_string(),
+                                                                    nc_step_factor: message_size_step_factor.map(|v| v.to_string()),
+                                                                    nc_step_bytes: message_size_step_bytes.map(|v| v.to_string()),
+                                                                    nc_num_iters: num_iters,
+                                                                    nc_num_warmup_iters: num_warmup_iters,
+                                                                    nc_iter_regimes: iteration_regimes.clone(),
+                                                                    nc_timeout: test_timeout_secs,
+                                                                    nc_cudagraph: cudagraph,
+                                                                    nc_root: *nc_root,
+                                                                    extra_test_args: extra_test_args.clone(),
+
+                                                                    // NCCL Env params
+                                                                    nccl_debug_level: nccl_debug_level.to_string(),
+                                                                    nccl_algo:
+                                                                        "Tree,Ring,CollnetDirect,CollnetChain,NVLS,NVLSTree"
+                                                                            .to_string(), // Default NCCL
+                                                                    nccl_proto: nccl_proto.map(|v| v.to_string()),
+                                                                    nccl_min_nchannels,
+                                                                    nccl_max_nchannels,
+                                                                    nccl_net_plugin: net_plugin.clone(),
+                                                                };
+
+                                                                // Make sure every rank has the visible devices it drives
+                                                                if let Some(devices) = &cuda_visible_devices {
+                                                                    match check_visible_devices(devices, &experiment) {
+                                                                        Ok(0) => {}
+                                                                        Ok(unused) => debug!("{} of the visible device(s) will go unused by this config.", unused),
+                                                                        Err(e) if validate_only => validation_problems.push(e.to_string()),
+                                                                        Err(e) => return Err(e),
+                                                                    }
+                                                                }
+
+                                                                // Make sure the XML file was made for this number of GPUs
+                                                                if let Err(e) = check_xml_gpu_count(experiment.ms_xml_file.as_path(), experiment.total_gpus) {
+                                                                    if validate_only {
+                                                                        validation_problems.push(e.to_string());
+                                                                    } else {
+                                                                        return Err(e);
+                                                                    }
+                                                                }
+
+                                                                // Add the full experiment to the list
+                                                                if explain {
+                                                                    let blacklisted = blacklist.iter().any(|b| experiment.ms_xml_file == msccl_xmls_directory.join(b));
+                                                                    info!(
+                                                                        "[explain] #{}: {} {} {} {} chunks {} channels {} (GPU as node: {}) generated from XML file: {}{}",
+                                                                        experiment_descriptors.len(), collective, reduction_op, nc_dtype, comm_algorithm, msccl_chunks, msccl_channels, gpu_as_node,
+                                                                        experiment.ms_xml_file.file_name().unwrap().to_str().unwrap(),
+                                                                        if blacklisted { " (but will be skipped because the XML file is blacklisted)" } else if ms_xml_fallback { " (missing; will run without MSCCL)" } else { "" }
+                                                                    );
+                                                                }
+                                                                experiment_descriptors.push(experiment);
+
+                                                                // Add the permutation to the list
+                                                                permutations.push(Permutation {
+                                                                    collective_exe: collective_exe.to_string(),
+                                                                    data_type: nc_dtype.clone(),
+                                                                    reduction_op: reduction_op.to_string(),
+                                                                    comm_algorithm: comm_algorithm.to_string(),
+                                                                    msccl_channel: Some(msccl_channels.to_string()),
+                                                                    msccl_chunk: Some(msccl_chunks.to_string()),
+                                                                    buffer_size: Some(buffer_size.to_string()),
+                                                                });
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -412,16 +1699,359 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     debug!("Finished generating all permutations/experiment configs.");
 
+    // Reorder before anything refers to the permutations by index
+    order_experiments(&mut experiment_descriptors, experiment_order);
+
+    // Print the expected XML files and quit if only listing them
+    if list_xmls {
+        for xml_file in expected_xmls.iter() {
+            let file_name = xml_file.file_name().unwrap().to_str().unwrap();
+            if list_xmls_status {
+                println!("{}\t{}", file_name, if xml_file.exists() { "exists" } else { "missing" });
+            } else {
+                println!("{}", file_name);
+            }
+        }
+        return Ok(());
+    }
+
+    // Print the report and quit if only validating
+    if validate_only {
+        print_validation_report(&validation_problems, experiment_descriptors.len() + num_dropped_permutations);
+        std::process::exit(if validation_problems.is_empty() { 0 } else { 1 });
+    }
+    if num_dropped_permutations > 0 {
+        warn!(
+            "Dropped {} permutation(s) because their XML files were not found. Will run the remaining {}.",
+            num_dropped_permutations,
+            experiment_descriptors.len()
+        );
+    }
+
+    // Only keep the configs that failed in the prior sweep if rerunning
+    // Note: The params are reconstructed by the permutation generator (using the current XML directory and env paths)
+    if rerun_failed {
+        let failed_entries = prior_manifest
+            .iter()
+            .filter(|e| matches!(e.overall_result, ResultDescription::Failure | ResultDescription::PartialFailure))
+            .collect::<Vec<&ManifestEntry>>();
+
+        experiment_descriptors.retain(|p| failed_entries.iter().any(|e| manifest_entry_matches(e, p)));
+        manifest_collection.clear(); // Skipped entries of configs that are not rerun
+        info!(
+            "Found {} failed entries in the manifest, will rerun {} config(s).",
+            failed_entries.len(),
+            experiment_descriptors.len()
+        );
+
+        for entry in failed_entries.iter() {
+            if !experiment_descriptors.iter().any(|p| manifest_entry_matches(entry, p)) {
+                warn!(
+                    "Failed config is not part of the current experimental setup and will not be rerun: {} {} {} {} (channels: {}, chunks: {}, GPUs: {}, buffer size: {}, build: {})",
+                    entry.collective, entry.op, entry.dtype, entry.algorithm,
+                    entry.num_channels, entry.num_chunks, entry.num_gpus, entry.buffer_size_factor, entry.build.as_deref().unwrap_or("default")
+                );
+            }
+        }
+    }
+
+    // Skip the permutations that already ran if resuming (the generation order is deterministic, so indices are stable)
+    if start_index > 0 {
+        let total_permutations = experiment_descriptors.len();
+        if start_index >= total_permutations {
+            return Err(format!(
+                "'--start-index {}' skips every permutation (only {} generated)", start_index, total_permutations
+            ).into());
+        }
+
+        experiment_descriptors.drain(..start_index);
+        info!(
+            "⏩ Resuming at permutation {} of {}, will run the remaining {} permutation(s). ⏩",
+            start_index, total_permutations, experiment_descriptors.len()
+        );
+    }
+
+    // Only keep the profiled permutation (and wrap it with nsys if available) when profiling
+    // Note: Profiles and the results of the (perturbed) profiled run go to a `profile` subdirectory so that they never
+    //       mix with or get skipped because of the sweep's own results
+    let (experiments_output_dir, num_repetitions) = match profile_index {
+        Some(index) => {
+            if rerun_failed {
+                return Err("'--profile' runs a single permutation and can't be combined with 'rerun-failed'".into());
+            }
+            if index >= experiment_descriptors.len() {
+                return Err(format!(
+                    "'--profile {}' is out of range (only {} permutations generated)", index, experiment_descriptors.len()
+                ).into());
+            }
+            let mut descriptor = experiment_descriptors.swap_remove(index);
+            experiment_descriptors = Vec::new();
+            manifest_collection.clear();
+
+            let profile_dir = experiments_output_dir.join("profile");
+            if !profile_dir.exists() {
+                std::fs::create_dir_all(profile_dir.as_path())?;
+                debug!("Created profile output directory at: {:?}", profile_dir);
+            }
+
+            if nsys_available() || dry_run {
+                let output = profile_dir.join(exp_params_to_config_filename(&descriptor, "nsys")).with_extension("");
+                info!("Will write Nsight Systems reports to: {:?} (one per rank)", output);
+                descriptor.nsys_profile = Some(NsysProfile { output, args: nsys_args.clone() });
+            } else {
+                warn!("Could not run 'nsys' (is Nsight Systems installed and on your PATH?). Will run the permutation without profiling.");
+            }
+            experiment_descriptors.push(descriptor);
+
+            (profile_dir, 1)
+        }
+        None => (experiments_output_dir, num_repetitions),
+    };
+
+    // Only run the replayed experiment, once
+    // Note: Its results go to a `replay` subdirectory so that they never mix with the sweep's own results
+    let (experiments_output_dir, num_repetitions) = match replay_params {
+        Some(params) => {
+            experiment_descriptors = vec![params];
+
+            let replay_dir = experiments_output_dir.join("replay");
+            if !replay_dir.exists() {
+                std::fs::create_dir_all(replay_dir.as_path())?;
+                debug!("Created replay output directory at: {:?}", replay_dir);
+            }
+
+            (replay_dir, 1)
+        }
+        None => (experiments_output_dir, num_repetitions),
+    };
+
+    // Without the iteration token, the repetitions of a config would write to the same files
+    if !output_filename_format.iteration_token && num_repetitions > 1 {
+        return Err(format!(
+            "OUTPUT_FILENAME_ITERATION=false needs a single repetition per config (NUM_REPETITIONS=1), but {} repetitions are set",
+            num_repetitions
+        ).into());
+    }
+
+    // Collect the params, exit code, timeout status, and stderr tail of every failed experiment in one place
+    let mut errors_log = if errors_log_enabled && !dry_run {
+        let path = experiments_output_dir.join("errors.log");
+        info!("Will write the details of failed experiments to: {:?}", path);
+        Some(create_output_file(path.as_path())?)
+    } else {
+        None
+    };
+
+    // Split the hostfile into one slice per parallel repetition
+    let hostfile_slices = if parallel_repetitions > 1 {
+        let hosts = parse_hostfile(mpi_hostfile_path.as_path())?;
+        let slices = write_hostfile_slices(&hosts, num_nodes, parallel_repetitions, experiments_output_dir.join("hostfiles").as_path())?;
+        if slices.len() < 2 {
+            warn!(
+                "PARALLEL_REPETITIONS is {}, but the hostfile only lists {} host(s) ({} needed per repetition). Will run repetitions one at a time.",
+                parallel_repetitions, hosts.len(), num_nodes
+            );
+            Vec::new()
+        } else {
+            info!("Will run up to {} repetitions at once, each on its own slice of the hostfile: {:?}", slices.len(), slices);
+            slices
+        }
+    } else {
+        Vec::new()
+    };
+
+    // Check the assembled nccl-tests flags for known incompatible combinations (before anything is launched)
+    let mut flag_warnings = std::collections::BTreeSet::new();
+    for experiment_descriptor in experiment_descriptors.iter() {
+        let warnings = check_test_flag_compatibility(&nccl_tests_args(experiment_descriptor))
+            .map_err(|e| format!("{} (XML file {:?})", e, experiment_descriptor.ms_xml_file))?;
+        flag_warnings.extend(warnings);
+    }
+    for warning in flag_warnings.iter() {
+        warn!("Possibly incompatible NCCL tests flags {}", warning);
+    }
+
     // Pretty-print the permutations
     pretty_print_configs(&experiment_descriptors, false);
 
-    // Create the record-keeping manifest
-    let mut manifest_collection = Vec::new();
+    // Write the full plan so that it can be reviewed in a spreadsheet (and diffed against prior sweeps)
+    let planned_path = experiments_output_dir.join("planned_experiments.csv");
+    match write_planned_experiments_csv(&experiment_descriptors, planned_path.as_path()) {
+        Ok(_) => info!("Wrote planned experiments to: {:?}", planned_path),
+        Err(e) => error!("Error writing planned experiments to {:?}: {}", planned_path, e),
+    }
+
+    // Check that every unique test executable is an NCCL tests binary (catches NCCL_TESTS_HOME pointing at the wrong build)
+    // that loads the NCCL of the MSCCL build
+    if !dry_run && !cfg!(feature = "no_check_paths") {
+        let mut checked_executables = std::collections::HashSet::new();
+        for experiment_descriptor in experiment_descriptors.iter() {
+            if checked_executables.insert(experiment_descriptor.executable.clone()) {
+                // Note: Checked first, since an unresolved libnccl.so also makes the executable fail to run
+                if let Err(e) = check_nccl_library(experiment_descriptor) {
+                    warn!("Possible NCCL mismatch: {}", e);
+                }
+                validate_test_executable(experiment_descriptor)?;
+            }
+        }
+        info!("Checked {} NCCL tests executable(s).", checked_executables.len());
+    }
+
+    // Let the launcher check every launch command (catches mistakes that `echo` happily prints)
+    if dry_run_validate {
+        let mut num_rejected = 0;
+        for experiment_descriptor in experiment_descriptors.iter() {
+            if let Err(e) = validate_launch(launcher.as_ref(), experiment_descriptor) {
+                error!("Launch command for XML file {:?} would fail: {}", experiment_descriptor.ms_xml_file, e);
+                num_rejected += 1;
+            }
+        }
+
+        if num_rejected > 0 {
+            return Err(format!("{} of {} launch commands were rejected by the launcher", num_rejected, experiment_descriptors.len()).into());
+        }
+        info!("The launcher accepted all {} launch commands.", experiment_descriptors.len());
+    }
+
+    // Pay the one-time fabric/topology detection costs before the first real experiment
+    if cluster_warmup {
+        run_cluster_warmup(launcher.as_ref(), &experiment_descriptors, &sweep_metadata, dry_run, experiment_timeouts, &fatal_error_patterns, stderr_tail_lines)?;
+    }
+
+    // Start the index of the output files (experiments skipped while generating the permutations never ran)
+    let log_index = if log_index {
+        let index_path = experiments_output_dir.join("index.csv");
+        match LogIndex::create(index_path.as_path()) {
+            Ok(index) => {
+                for entry in manifest_collection.iter() {
+                    if let Err(e) = index.append(entry, None, None, None, None) {
+                        error!("Error writing to the log index at {:?}: {}", index_path, e);
+                    }
+                }
+                info!("Will write the log index to: {:?}", index_path);
+                Some(index)
+            }
+            Err(e) => {
+                error!("Error creating the log index at {:?}: {}. Continuing without it...", index_path, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
     // ACTUALLY run experiments by iterating over the list of permutations
+    let mut num_skipped_missing_xmls = 0;
     let total_experiments = experiment_descriptors.len() * num_repetitions;
+    if let Some(sweep_progress) = sweep_progress.as_mut() {
+        sweep_progress.start(total_experiments as u64);
+    }
     for (progress, experiment_descriptor) in experiment_descriptors.iter().enumerate() {
+        // Buffer the parsed rows of each repetition that didn't fail (with its index) so they can be aggregated
+        let config_manifest_start = manifest_collection.len();
+        let mut repetition_rows = Vec::new();
+        let mut repetition_manifest_starts = Vec::new(); // (Repetition, index of its first manifest entry)
+
+        // Get the output directory for this experiment (creating it on demand if nested)
+        let experiment_output_dir = if nested_output_dirs {
+            let path = experiments_output_dir
+                .join(&experiment_descriptor.nc_collective)
+                .join(&experiment_descriptor.algorithm);
+
+            if !path.exists() {
+                std::fs::create_dir_all(path.as_path())?;
+                debug!("Created experiment log output subdirectory at: {:?}", path);
+            }
+
+            path
+        } else {
+            experiments_output_dir.clone()
+        };
+
+        // Identify the exact params and XML contents this config runs with (to tell whether finished logs are up to date)
+        let experiment_config_hash = config_hash(experiment_descriptor);
+
+        // Run the repetitions in parallel batches on distinct nodes (if enabled); the results are handled in order below
+        // Note: Only repetitions that would actually run are started (blacklisted configs and finished logs are skipped)
+        let mut parallel_results = std::collections::HashMap::new();
+        let blacklisted = blacklist.iter().any(|b| experiment_descriptor.ms_xml_file == msccl_xmls_directory.join(b));
+        let xml_missing = skip_missing_xmls_at_run_time && !experiment_descriptor.ms_xml_fallback && !experiment_descriptor.ms_xml_file.exists();
+        if hostfile_slices.len() > 1 && !blacklisted && !xml_missing {
+            let pending = (0..num_repetitions)
+                .filter(|&i| {
+                    let output_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "log"));
+                    !(skip_finished && check_log_finished(output_path.as_path(), experiment_config_hash.as_str()).is_ok())
+                })
+                .collect::<Vec<usize>>();
+
+            for batch in pending.chunks(hostfile_slices.len()) {
+                info!("Running repetitions {:?} in parallel on distinct nodes...", batch.iter().map(|i| i + 1).collect::<Vec<usize>>());
+                let batch_results = std::thread::scope(|scope| {
+                    let handles = batch
+                        .iter()
+                        .zip(hostfile_slices.iter())
+                        .map(|(&i, hostfile_slice)| {
+                            let mut params = experiment_descriptor.clone();
+                            params.mpi_hostfile_path = hostfile_slice.clone();
+                            let output_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "log"));
+                            let stderr_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "stderr"));
+                            let power_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "power.csv"));
+                            let (launcher, sweep_metadata, fatal_error_patterns) = (launcher.as_ref(), &sweep_metadata, &fatal_error_patterns);
+                            // Logs record the descriptor's hash (not the slice's), so `SKIP_FINISHED` recognizes them
+                            let experiment_config_hash = experiment_config_hash.as_str();
+
+                            scope.spawn(move || {
+                                let power_sampler = if sample_power && !dry_run { PowerSampler::start(power_path.as_path()) } else { None };
+                                let result = run_msccl_tests_with_retries(
+                                    launcher, &params, sweep_metadata, dry_run, experiment_timeouts, retry_policy, tee_stdout, fatal_error_patterns, stderr_tail_lines, output_path, stderr_path,
+                                    experiment_config_hash,
+                                )
+                                // Boxed errors can't leave the thread, so only keep harness errors as they are
+                                .map_err(|e| e.downcast::<HarnessError>().map(|e| *e).map_err(|e| e.to_string()));
+                                if let Some(sampler) = power_sampler {
+                                    sampler.stop();
+                                }
+                                (i, result)
+                            })
+                        })
+                        .collect::<Vec<_>>();
+
+                    handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<(usize, Result<Vec<Row>, Result<HarnessError, String>>)>>()
+                });
+                parallel_results.extend(batch_results);
+            }
+        }
+
         for i in 0..num_repetitions {
+            repetition_manifest_starts.push((i as u64, manifest_collection.len()));
+
+            // Attach the experiment's context to every log line emitted while running it
+            let _experiment_span = info_span!(
+                "experiment",
+                collective = %experiment_descriptor.nc_collective,
+                algorithm = %experiment_descriptor.algorithm,
+                channels = experiment_descriptor.ms_channels,
+                chunks = experiment_descriptor.ms_chunks,
+                gpus = experiment_descriptor.total_gpus,
+                iteration = i,
+            ).entered();
+
+            // Show the config that runs next
+            if let Some(sweep_progress) = sweep_progress.as_ref() {
+                sweep_progress.update(
+                    (progress * num_repetitions + i) as u64,
+                    format!(
+                        "{} {} ch{} ck{} gpu{} (rep {})",
+                        experiment_descriptor.nc_collective,
+                        experiment_descriptor.algorithm,
+                        experiment_descriptor.ms_channels,
+                        experiment_descriptor.ms_chunks,
+                        experiment_descriptor.total_gpus,
+                        i + 1
+                    ).as_str(),
+                );
+            }
+
             // debug!("Experiment descriptor found: {:#?}", experiment_descriptor);
 
             // Print info about this experiment
@@ -449,10 +2079,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
 
             // Get the output file paths
-            let output_path = experiments_output_dir.clone().join(
+            let output_path = experiment_output_dir.join(
                 exp_params_to_output_filename(&experiment_descriptor, i as u64, "log"),
             );
-            let stderr_path = experiments_output_dir.clone().join(
+            let stderr_path = experiment_output_dir.join(
                 exp_params_to_output_filename(&experiment_descriptor, i as u64, "stderr")
             );
 
@@ -469,17 +2099,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     info!("Skipping experiment because XML file is blacklisted: {:?}", experiment_descriptor.ms_xml_file);
 
                     // Update manifest
-                    manifest_collection.push(ManifestEntry {
-                        collective: experiment_descriptor.nc_collective.clone(),
-                        op: experiment_descriptor.nc_op.clone(),
-                        dtype: experiment_descriptor.nc_dtype.clone(),
-                        algorithm: experiment_descriptor.algorithm.clone(),
-                        num_channels: experiment_descriptor.ms_channels,
-                        num_chunks: experiment_descriptor.ms_chunks,
-                        num_gpus: experiment_descriptor.total_gpus,
-                        buffer_size_factor: experiment_descriptor.buffer_size,
-                        overall_result: ResultDescription::Blacklisted,
-                    });
+                    manifest_collection.push(ManifestEntry::for_experiment(experiment_descriptor, ResultDescription::Blacklisted, experiment_config_hash.as_str()));
 
                     info!("---------------------------------------");
 
@@ -487,21 +2107,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
-            // Skip if already completed and skip envvar is set
-            if skip_finished && output_path.exists() {
-                info!("Skipping experiment because output file already exists at: {:?} and 'SKIP_COMPLETED' envvar is set.", output_path);
+            // Skip (rather than launch a command that is bound to fail) if the XML file is still missing
+            if xml_missing {
+                warn!("Skipping experiment because its XML file is missing at: {:?} and 'SKIP_MISSING_XMLS_AT_RUN_TIME' is set", experiment_descriptor.ms_xml_file);
+                num_skipped_missing_xmls += 1;
 
                 // Update manifest
                 manifest_collection.push(ManifestEntry {
-                    collective: experiment_descriptor.nc_collective.clone(),
-                    op: experiment_descriptor.nc_op.clone(),
-                    dtype: experiment_descriptor.nc_dtype.clone(),
-                    algorithm: experiment_descriptor.algorithm.clone(),
-                    num_channels: experiment_descriptor.ms_channels,
-                    num_chunks: experiment_descriptor.ms_chunks,
-                    num_gpus: experiment_descriptor.total_gpus,
-                    buffer_size_factor: experiment_descriptor.buffer_size,
-                    overall_result: ResultDescription::Skipped,
+                    notes: vec!["missing XML".to_string()],
+                    ..ManifestEntry::for_experiment(experiment_descriptor, ResultDescription::Skipped, experiment_config_hash.as_str())
                 });
 
                 info!("---------------------------------------");
@@ -509,14 +2123,102 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 continue;
             }
 
-            let rows = match run_msccl_tests(
-                &experiment_descriptor.executable,
-                &experiment_descriptor,
-                true, // Why? Well, Liuyao's testo sometimes return a nonzero status code
-                dry_run,
-                Some(output_path.clone()),
-                Some(stderr_path.clone()),
-            ) {
+            // Skip if already completed and skip envvar is set
+            // Only skip repetitions whose log was written with the same params and XML contents and recorded a successful
+            // result (so a sweep that crashed mid-config only reruns the interrupted or failed repetitions)
+            let finished = if skip_finished && output_path.exists() {
+                match check_log_finished(output_path.as_path(), experiment_config_hash.as_str()) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        info!("Rerunning repetition {} of {} because {}.", i + 1, num_repetitions, reason);
+                        false
+                    }
+                }
+            } else {
+                false
+            };
+            if finished {
+                info!("Skipping repetition {} of {} because output file already exists at: {:?} with the same config hash and a successful result and 'SKIP_FINISHED' envvar is set.", i + 1, num_repetitions, output_path);
+
+                // Update manifest
+                manifest_collection.push(ManifestEntry::for_experiment(experiment_descriptor, ResultDescription::Skipped, experiment_config_hash.as_str()));
+
+                info!("---------------------------------------");
+
+                continue;
+            }
+
+            // Start sampling power (if requested) for the duration of the run
+            // Note: Repetitions that already ran in parallel were sampled on their own
+            let power_sampler = if sample_power && !dry_run && !parallel_results.contains_key(&i) {
+                PowerSampler::start(
+                    experiment_output_dir
+                        .join(exp_params_to_output_filename(experiment_descriptor, i as u64, "power.csv"))
+                        .as_path(),
+                )
+            } else {
+                None
+            };
+
+            // Run the experiment (unless it already ran in parallel), retrying hangs and crashes according to their own policies
+            let result = match parallel_results.remove(&i) {
+                Some(result) => result.map_err(|e| match e {
+                    Ok(e) => e.into(),
+                    Err(e) => e.into(),
+                }),
+                None => run_msccl_tests_with_retries(
+                    launcher.as_ref(),
+                    experiment_descriptor,
+                    &sweep_metadata,
+                    dry_run,
+                    experiment_timeouts,
+                    retry_policy,
+                    tee_stdout,
+                    &fatal_error_patterns,
+                    stderr_tail_lines,
+                    output_path.clone(),
+                    stderr_path.clone(),
+                    experiment_config_hash.as_str(),
+                ),
+            };
+
+            if let Some(sampler) = power_sampler {
+                sampler.stop();
+            }
+
+            // Summarize the topology NCCL built (also for failed runs, where it is often the most telling part)
+            if topology_summary && !dry_run && output_path.exists() {
+                let topology_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "topology.json"));
+                match parse_topology_log(output_path.as_path()) {
+                    Ok(summary) if summary.is_empty() => {
+                        warn!("No NCCL topology lines found in {:?} (is NCCL_DEBUG set to INFO?).", output_path);
+                    }
+                    Ok(summary) => match write_topology_json(&summary, topology_path.as_path()) {
+                        Ok(_) => debug!("Wrote topology summary of {} rank(s) to: {:?}", summary.ranks.len(), topology_path),
+                        Err(e) => error!("Error writing topology summary to {:?}: {}", topology_path, e),
+                    },
+                    Err(e) => error!("Error reading {:?} for the topology summary: {}", output_path, e),
+                }
+            }
+
+            // Check that the run actually used EFA if it was set up to
+            if check_efa_transport && !dry_run && output_path.exists()
+                && (experiment_descriptor.efa_path.is_some() || experiment_descriptor.aws_ofi_nccl_path.is_some())
+            {
+                match parse_network_log(output_path.as_path()) {
+                    Ok(report) => match (report.efa_fallback(), &report.network) {
+                        (Some(reason), _) => warn!(
+                            "Experiment fell back from EFA even though EFA_PATH/AWS_OFI_NCCL_PATH are set: {} (check the paths and LD_LIBRARY_PATH)!",
+                            reason
+                        ),
+                        (None, None) => debug!("No NCCL network lines found in {:?} (is NCCL_DEBUG set to INFO?).", output_path),
+                        (None, Some(network)) => debug!("Experiment used the '{}' network.", network),
+                    },
+                    Err(e) => error!("Error reading {:?} for the EFA check: {}", output_path, e),
+                }
+            }
+
+            let rows = match result {
                 Ok(v) => v,
                 Err(e) => {
                     error!(
@@ -524,18 +2226,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         e
                     );
 
+                    // Record the failure in the errors log
+                    if let Some(file) = errors_log.as_mut() {
+                        if let Err(e) = file.write_all(format_error_log_entry(experiment_descriptor, i as u64, e.as_ref()).as_bytes()) {
+                            error!("Error writing to the errors log: {}", e);
+                        }
+                    }
+
                     // Update manifest
-                    manifest_collection.push(ManifestEntry {
-                        collective: experiment_descriptor.nc_collective.clone(),
-                        op: experiment_descriptor.nc_op.clone(),
-                        dtype: experiment_descriptor.nc_dtype.clone(),
-                        algorithm: experiment_descriptor.algorithm.clone(),
-                        num_channels: experiment_descriptor.ms_channels,
-                        num_chunks: experiment_descriptor.ms_chunks,
-                        num_gpus: experiment_descriptor.total_gpus,
-                        buffer_size_factor: experiment_descriptor.buffer_size,
-                        overall_result: ResultDescription::Failure,
-                    });
+                    manifest_collection.push(ManifestEntry::for_experiment(experiment_descriptor, ResultDescription::Failure, experiment_config_hash.as_str()));
+
+                    // Stop here, but keep what was recorded so far
+                    if fail_fast {
+                        return Err(stop_at_first_failure(
+                            e.to_string().as_str(),
+                            sweep_progress.as_ref(),
+                            &manifest_collection,
+                            rerun_failed.then_some((prior_manifest.as_slice(), experiment_descriptors.as_slice())),
+                            &sweep_metadata,
+                            experiments_output_dir.as_path(),
+                        ));
+                    }
 
                     info!("---------------------------------------");
 
@@ -544,42 +2255,418 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
+            // Ran without errors, but nothing could be parsed (e.g., an empty results table)
+            if rows.is_empty() && !dry_run {
+                warn!("NCCL tests ran without errors, but no result rows could be parsed. Continuing...");
+
+                // Update manifest
+                manifest_collection.push(ManifestEntry::for_experiment(experiment_descriptor, ResultDescription::NoData, experiment_config_hash.as_str()));
+
+                info!("---------------------------------------");
+
+                continue;
+            }
+
+            // Exclude the warmup repetition from the results (it pays one-time init costs)
+            if skip_warmup_repetition && i == 0 {
+                info!("Excluding repetition 1 of {} from the results because it is a warmup repetition.", num_repetitions);
+
+                // Update manifest
+                manifest_collection.push(ManifestEntry::for_experiment(experiment_descriptor, ResultDescription::Warmup, experiment_config_hash.as_str()));
+                if !dry_run {
+                    if let Err(e) = append_log_result(output_path.as_path(), &ResultDescription::Warmup) {
+                        error!("Error recording the result in {:?}: {}", output_path, e);
+                    }
+                }
+
+                info!("---------------------------------------");
+
+                continue;
+            }
+
+            // Flag message sizes with anomalously low bandwidth (e.g., a transient stall)
+            let flagged_sizes = match anomaly_bw_fraction {
+                Some(fraction) => flag_low_bandwidth_sizes(&rows, fraction),
+                None => Vec::new(),
+            };
+            if !flagged_sizes.is_empty() {
+                warn!(
+                    "Found {} message size(s) with a bus bandwidth below {} of the median: {:?}",
+                    flagged_sizes.len(),
+                    anomaly_bw_fraction.unwrap(),
+                    flagged_sizes
+                );
+            }
+
+            // Check that in-place and out-of-place bandwidths are comparable (a large divergence usually means trouble)
+            let mut notes = Vec::new();
+            if let Some(max_ratio) = inplace_divergence_ratio {
+                let divergent_sizes = flag_inplace_divergence(&rows, max_ratio);
+                if !divergent_sizes.is_empty() {
+                    warn!(
+                        "Found {} message size(s) where in-place and out-of-place bus bandwidth differ by more than {}x: {:?}",
+                        divergent_sizes.len(),
+                        max_ratio,
+                        divergent_sizes
+                    );
+                    notes.push(format!(
+                        "In-place/out-of-place bus bandwidth differ by more than {}x at sizes: {}",
+                        max_ratio,
+                        divergent_sizes.iter().map(|s| s.to_string()).collect::<Vec<String>>().join(", ")
+                    ));
+                }
+            }
+
+            // Check that every message size has a row (nccl-tests steps through a predictable set of sizes)
+            if check_row_count {
+                match message_sizes(experiment_descriptor) {
+                    Ok(sizes) => {
+                        let mismatches = row_count_mismatches(&rows, sizes.len() as u64);
+                        if !mismatches.is_empty() {
+                            warn!("Parsed an unexpected number of rows (the run may have been truncated): {}", mismatches.join("; "));
+                            notes.push(format!("Unexpected row count: {}", mismatches.join("; ")));
+                        }
+                    }
+                    Err(e) => warn!("Could not compute the expected number of rows: {}", e),
+                }
+            }
+
+            // Fail experiments whose bus bandwidth is below the required minimum (regression gating)
+            let gate_failures = bandwidth_gate_failures(&rows, experiment_descriptor.nc_collective.as_str(), &bandwidth_gates);
+            if !gate_failures.is_empty() {
+                error!("Experiment missed {} bandwidth gate(s): {}", gate_failures.len(), gate_failures.join("; "));
+                notes.push(format!("Below the bandwidth gate: {}", gate_failures.join("; ")));
+            }
+
+            // Compare against the theoretical peak of the hardware
+            if let Some(spec) = &hardware_spec {
+                match peak_efficiency(&rows, experiment_descriptor.nc_collective.as_str(), experiment_descriptor.total_gpus, experiment_descriptor.num_nodes, spec) {
+                    Some(v) => info!("Achieved {:.1}% of the theoretical peak bus bandwidth.", v * 100.0),
+                    None => debug!("No theoretical bus bandwidth formula for collective: {}", experiment_descriptor.nc_collective),
+                }
+
+                // Results above the peak can't be real (probably a parsing bug or a unit mistake), so mark them suspect
+                let impossible_sizes = sizes_exceeding_peak(&rows, experiment_descriptor.num_nodes, spec, peak_margin);
+                if !impossible_sizes.is_empty() {
+                    error!(
+                        "🚨 Found {} message size(s) with a bus bandwidth more than {:.0}% above the theoretical peak of {:.2} GB/s (parsing bug, unit mistake, or wrong NVLINK_BW_GBPS/NIC_BW_GBPS?): {:?}",
+                        impossible_sizes.len(),
+                        peak_margin * 100.0,
+                        spec.peak_bus_bw(experiment_descriptor.num_nodes),
+                        impossible_sizes
+                    );
+                    notes.push(format!(
+                        "Suspect: bus bandwidth above the theoretical peak at sizes: {}",
+                        impossible_sizes.iter().map(|s| s.to_string()).collect::<Vec<String>>().join(", ")
+                    ));
+                }
+            }
+
+            // Push metrics (failing to push should never stop the sweep)
+            if let Err(e) = push_bus_bw_metrics(pushgateway_url.as_deref(), experiment_descriptor, &rows) {
+                warn!("Failed to push metrics to the pushgateway: {}. Continuing...", e);
+            }
+
             info!(
                 "Finished running experiment. Completed {} of {} experiments ({:.1}%).",
-                progress * 2 + i + 1,
+                progress * num_repetitions + i + 1,
                 total_experiments,
                 if total_experiments > 0 {
-                    ((progress * 2 + i + 1) as f64 / total_experiments as f64) * 100.0
+                    ((progress * num_repetitions + i + 1) as f64 / total_experiments as f64) * 100.0
                 } else {
                     100.0
                 }
             );
 
+            // Keep the average bus bandwidth nccl-tests reports in its footer (the most cited single number of a run)
+            let avg_bus_bw = if dry_run {
+                None
+            } else {
+                match parse_footer_log(output_path.as_path()) {
+                    Ok(footer) => footer.and_then(|f| f.avg_bus_bw),
+                    Err(e) => {
+                        warn!("Error reading {:?} for the NCCL tests footer: {}", output_path, e);
+                        None
+                    }
+                }
+            };
+            match avg_bus_bw {
+                Some(v) => info!("NCCL tests report an average bus bandwidth of {} GB/s.", v),
+                None => debug!("No average bus bandwidth found in the NCCL tests footer."),
+            }
+
             // Update manifest
+            let overall_result = if !gate_failures.is_empty() {
+                ResultDescription::Failure
+            } else if flagged_sizes.is_empty() && notes.is_empty() {
+                ResultDescription::Success
+            } else {
+                ResultDescription::PartialFailure
+            };
             manifest_collection.push(ManifestEntry {
-                collective: experiment_descriptor.nc_collective.clone(),
-                op: experiment_descriptor.nc_op.clone(),
-                dtype: experiment_descriptor.nc_dtype.clone(),
-                algorithm: experiment_descriptor.algorithm.clone(),
-                num_channels: experiment_descriptor.ms_channels,
-                num_chunks: experiment_descriptor.ms_chunks,
-                num_gpus: experiment_descriptor.total_gpus,
-                buffer_size_factor: experiment_descriptor.buffer_size,
-                overall_result: ResultDescription::Success,
+                flagged_sizes,
+                notes,
+                avg_bus_bw,
+                ..ManifestEntry::for_experiment(experiment_descriptor, overall_result, experiment_config_hash.as_str())
             });
 
+            // Record the result at the end of the log (marks the repetition as finished for 'SKIP_FINISHED')
+            if !dry_run {
+                if let Some(entry) = manifest_collection.last() {
+                    if let Err(e) = append_log_result(output_path.as_path(), &entry.overall_result) {
+                        error!("Error recording the result in {:?}: {}", output_path, e);
+                    }
+                }
+            }
+
+            // Export the rows
+            #[cfg(feature = "dataframe")]
+            if let Some(exporter) = parquet_exporter.as_mut() {
+                if let Err(e) = experiment_df(experiment_descriptor, i as u64, rows.clone(), bandwidth_unit, result_columns.as_deref()).and_then(|df| exporter.write(df)) {
+                    error!("Error exporting results to Parquet: {}. Continuing...", e);
+                }
+            }
+
+            // Re-emit the parsed rows in the nccl-tests table format
+            if nccl_table_output {
+                let table_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "table.txt"));
+                match std::fs::write(table_path.as_path(), format_nccl_table(&rows)) {
+                    Ok(_) => debug!("Wrote nccl-tests table to: {:?}", table_path),
+                    Err(e) => error!("Error writing nccl-tests table to {:?}: {}", table_path, e),
+                }
+            }
+
+            // Write the compact summary of the rows
+            if summary_json {
+                if let Some(summary) = summarize_rows(&rows) {
+                    let summary_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "summary.json"));
+                    match serde_json::to_string_pretty(&summary).map_err(|e| e.into()).and_then(|v| std::fs::write(summary_path.as_path(), v)) {
+                        Ok(_) => debug!("Wrote summary to: {:?}", summary_path),
+                        Err(e) => error!("Error writing summary to {:?}: {}", summary_path, e),
+                    }
+                }
+            }
+
+            // Export the per-iteration timings (parsed from the log, since only the table rows are kept while running)
+            if iteration_timeseries && !dry_run {
+                match parse_iteration_log(output_path.as_path()) {
+                    Ok(timings) if timings.is_empty() => {
+                        warn!("No per-iteration timings found in {:?} (does the NCCL tests build print them?).", output_path);
+                    }
+                    Ok(timings) => {
+                        let csv_path = experiment_output_dir.join(exp_params_to_output_filename(experiment_descriptor, i as u64, "iterations.csv"));
+                        match write_iteration_timeseries_csv(&timings, csv_path.as_path()) {
+                            Ok(_) => debug!("Wrote {} per-iteration timing(s) to: {:?}", timings.len(), csv_path),
+                            Err(e) => error!("Error writing per-iteration timings to {:?}: {}", csv_path, e),
+                        }
+                    }
+                    Err(e) => error!("Error reading {:?} for the per-iteration timings: {}", output_path, e),
+                }
+            }
+
+            #[cfg(feature = "sqlite")]
+            if let Some(exporter) = sqlite_exporter.as_mut() {
+                if let Err(e) = exporter.write_rows(experiment_descriptor, i as u64, &rows) {
+                    error!("Error writing results to SQLite: {}. Continuing...", e);
+                }
+            }
+
+            // Keep the rows for aggregation across repetitions
+            // Note: Missing a bandwidth gate fails the repetition, so its rows are left out. Partial failures only flag
+            //       some sizes (or add notes), so their rows still count.
+            if gate_failures.is_empty() {
+                repetition_rows.push((i as u64, rows));
+            }
+
+            // Missing a bandwidth gate fails the experiment, so stop here too (keeping what was recorded so far)
+            if fail_fast && !gate_failures.is_empty() {
+                return Err(stop_at_first_failure(
+                    format!("Missed {} bandwidth gate(s): {}", gate_failures.len(), gate_failures.join("; ")).as_str(),
+                    sweep_progress.as_ref(),
+                    &manifest_collection,
+                    rerun_failed.then_some((prior_manifest.as_slice(), experiment_descriptors.as_slice())),
+                    &sweep_metadata,
+                    experiments_output_dir.as_path(),
+                ));
+            }
+
             // Print line separator
             info!("---------------------------------------");
         }
+
+        // Aggregate the peak bus bandwidth across the repetitions of this config that didn't fail
+        let peaks = repetition_rows.iter().filter_map(|(_, rows)| peak_bus_bw(rows)).collect::<Vec<f64>>();
+        if let Some(summary) = summarize(&peaks) {
+            info!(
+                "Peak bus bandwidth across {} repetition(s) that didn't fail: min {:.2}, median {:.2}, max {:.2}",
+                peaks.len(), summary.min, summary.median, summary.max
+            );
+            for entry in manifest_collection[config_manifest_start..].iter_mut() {
+                entry.peak_bus_bw = Some(summary.clone());
+            }
+        }
+
+        // Keep the exact params of failed configs so they can be replayed (`replay <params.json>`) or attached to bug reports
+        let config_failed = manifest_collection[config_manifest_start..]
+            .iter()
+            .any(|e| matches!(e.overall_result, ResultDescription::Failure | ResultDescription::PartialFailure));
+        if config_failed && !dry_run {
+            let params_path = experiment_output_dir.join(exp_params_to_config_filename(experiment_descriptor, "params.json"));
+            match write_params_json(experiment_descriptor, params_path.as_path()) {
+                Ok(_) => info!("Wrote the params of the failed config to: {:?}", params_path),
+                Err(e) => error!("Error writing the params of the failed config to {:?}: {}", params_path, e),
+            }
+        }
+
+        // Tag the experiments that ran without MSCCL because their XML file is missing
+        if experiment_descriptor.ms_xml_fallback {
+            for entry in manifest_collection[config_manifest_start..].iter_mut() {
+                entry.notes.push("XML file missing; ran without MSCCL (NCCL's own algorithms)".to_string());
+            }
+        }
+
+        // Keep the best fraction of the theoretical peak across the repetitions of this config that didn't fail
+        if let Some(spec) = &hardware_spec {
+            let efficiency = repetition_rows.iter()
+                .filter_map(|(_, rows)| peak_efficiency(rows, experiment_descriptor.nc_collective.as_str(), experiment_descriptor.total_gpus, experiment_descriptor.num_nodes, spec))
+                .reduce(f64::max);
+            for entry in manifest_collection[config_manifest_start..].iter_mut() {
+                entry.efficiency = efficiency;
+            }
+        }
+
+        // Write the bus bandwidth of every repetition side by side (one row per message size)
+        if per_size_csv {
+            let csv_path = experiment_output_dir.join(exp_params_to_config_filename(experiment_descriptor, "bus_bw.csv"));
+            match write_bus_bw_csv(&repetition_rows, num_repetitions as u64, csv_path.as_path()) {
+                Ok(_) => debug!("Wrote per-size bus bandwidth CSV to: {:?}", csv_path),
+                Err(e) => error!("Error writing per-size bus bandwidth CSV to {:?}: {}", csv_path, e),
+            }
+        }
+
+        // Add the finished config's experiments to the index (paths relative to the experiments output directory)
+        if let Some(index) = log_index.as_ref() {
+            let relative_dir = experiment_output_dir.strip_prefix(&experiments_output_dir).unwrap_or(experiment_output_dir.as_path());
+            let bus_bw_csv = per_size_csv.then(|| relative_dir.join(exp_params_to_config_filename(experiment_descriptor, "bus_bw.csv")));
+            for (k, entry) in manifest_collection.iter().enumerate().skip(config_manifest_start) {
+                let repetition = repetition_manifest_starts.iter().rev().find(|(_, start)| *start <= k).map(|(i, _)| *i);
+                let (log, stderr) = match repetition {
+                    Some(i) => (
+                        Some(relative_dir.join(exp_params_to_output_filename(experiment_descriptor, i, "log"))),
+                        Some(relative_dir.join(exp_params_to_output_filename(experiment_descriptor, i, "stderr"))),
+                    ),
+                    None => (None, None),
+                };
+                if let Err(e) = index.append(entry, repetition, log.as_deref(), stderr.as_deref(), bus_bw_csv.as_deref()) {
+                    error!("Error writing to the log index: {}", e);
+                }
+            }
+        }
+    }
+
+    if let Some(sweep_progress) = sweep_progress.as_ref() {
+        sweep_progress.finish();
+    }
+
+    // Report a degraded sweep if experiments were skipped because their XML files were missing
+    if num_skipped_missing_xmls > 0 {
+        warn!(
+            "⚠️ The sweep is degraded: {} experiment(s) were skipped because their XML file was missing (see the 'missing XML' notes in the manifest).",
+            num_skipped_missing_xmls
+        );
     }
 
     // Pretty Print the Manifest
     println!("\n\n\n--- 📋📋📋 EXPERIMENT RESULTS 📋📋📋 ---\n");
     pretty_print_result_manifest(&manifest_collection);
 
+    // Finish the Parquet export
+    #[cfg(feature = "dataframe")]
+    if let Some(exporter) = parquet_exporter {
+        if let Err(e) = exporter.finish() {
+            error!("Error finishing Parquet export: {}", e);
+        }
+    }
+
+    // Write the Markdown report
+    if let Some(path) = markdown_report_path {
+        match write_markdown_report(&sweep_metadata, &experiment_descriptors, &manifest_collection, path.as_path()) {
+            Ok(_) => info!("Wrote Markdown report to: {:?}", path),
+            Err(e) => error!("Error writing Markdown report to {:?}: {}", path, e),
+        }
+    }
+
+    // Write the manifest as JSON (when rerunning, the prior entries of the rerun configs are replaced)
+    let manifest_entries = if rerun_failed {
+        merge_rerun_manifest(&prior_manifest, &experiment_descriptors, &manifest_collection)
+    } else {
+        manifest_collection.clone()
+    };
+    let manifest_json = Manifest { metadata: sweep_metadata.clone(), entries: manifest_entries };
+    #[cfg(feature = "sqlite")]
+    if let Some(exporter) = sqlite_exporter.as_mut() {
+        if let Err(e) = exporter.write_manifest(&manifest_collection) {
+            error!("Error writing manifest to SQLite: {}", e);
+        }
+    }
+    let manifest_path = experiments_output_dir.join("manifest.json");
+    match write_manifest_json(&manifest_json, manifest_path.as_path()) {
+        Ok(_) => info!("Wrote manifest to: {:?}", manifest_path),
+        Err(e) => error!("Error writing manifest to {:?}: {}", manifest_path, e),
+    }
+
+    // Check the self-test's results (its directory is kept for inspection if anything failed)
+    if let Some(dir) = self_test_dir {
+        check_self_test(&manifest_collection).map_err(|e| format!("{} (see the output in {:?})", e, dir))?;
+        println!("✅ Self-test passed: {} experiment(s) ran and were parsed.", manifest_collection.len());
+        if let Err(e) = std::fs::remove_dir_all(dir.as_path()) {
+            warn!("Could not remove the self-test directory at {:?}: {}", dir, e);
+        }
+    }
+
+    // Exit with an error if any experiment missed a bandwidth gate (so that CI catches the regression)
+    let num_gate_failures = manifest_collection
+        .iter()
+        .filter(|e| e.notes.iter().any(|n| n.starts_with("Below the bandwidth gate")))
+        .count();
+    if num_gate_failures > 0 {
+        return Err(format!("{} experiment(s) missed a bandwidth gate of MIN_BUS_BW_GATES (see the manifest notes)", num_gate_failures).into());
+    }
+
     Ok(())
 }
 
+/// Stop the sweep at the first failed experiment (`--fail-fast`), printing and writing the manifest recorded so far
+///
+/// Note: `rerun` holds the prior manifest and the rerun configs when rerunning the failed configs of a prior sweep, so
+///       that the partial manifest replaces their prior entries (like the manifest of a finished sweep).
+fn stop_at_first_failure(
+    reason: &str,
+    sweep_progress: Option<&SweepProgress>,
+    manifest_collection: &[ManifestEntry],
+    rerun: Option<(&[ManifestEntry], &[MscclExperimentParams])>,
+    sweep_metadata: &SweepMetadata,
+    experiments_output_dir: &Path,
+) -> Box<dyn std::error::Error> {
+    if let Some(sweep_progress) = sweep_progress {
+        sweep_progress.finish();
+    }
+    println!("\n\n\n--- 📋📋📋 EXPERIMENT RESULTS (STOPPED AT FIRST FAILURE) 📋📋📋 ---\n");
+    pretty_print_result_manifest(manifest_collection);
+
+    let manifest_entries = match rerun {
+        Some((prior_manifest, rerun_configs)) => merge_rerun_manifest(prior_manifest, rerun_configs, manifest_collection),
+        None => manifest_collection.to_vec(),
+    };
+    let manifest_path = experiments_output_dir.join("manifest.json");
+    if let Err(e) = write_manifest_json(&Manifest { metadata: sweep_metadata.clone(), entries: manifest_entries }, manifest_path.as_path()) {
+        return e;
+    }
+    info!("Wrote partial manifest to: {:?}", manifest_path);
+
+    format!("Stopping at the first failed experiment because of '--fail-fast': {}", reason).into()
+}
+
 // /// Run NCCL tests with MPI using a set of parameters
 // fn run_nccl_test(hostfile_path: &Path, executable: &Path, msccl_xml_file: Option<&Path>,
 //     proc_per_node: &str, num_threads: &str, num_gpus: &str, min_bytes: &str, max_bytes: &str, step_factor: &str, 
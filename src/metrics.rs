@@ -0,0 +1,59 @@
+use std::fmt::Write;
+use tracing::{debug, info};
+
+use crate::{Row, MscclExperimentParams};
+
+/// Push the bus bandwidth of each parsed row to a Prometheus pushgateway
+///
+/// Note: Does nothing if no pushgateway URL is given. The experiment parameters are encoded in the grouping key so
+///       that every config gets its own set of series in Grafana.
+///
+/// # Arguments
+/// * `pushgateway_url` - Base URL of the pushgateway (e.g., `http://localhost:9091`)
+/// * `params` - The experiment parameters the rows were collected with
+/// * `rows` - The parsed rows of the NCCL output table
+pub fn push_bus_bw_metrics(
+    pushgateway_url: Option<&str>,
+    params: &MscclExperimentParams,
+    rows: &[Row],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let base_url = match pushgateway_url {
+        Some(v) => v.trim_end_matches('/'),
+        None => return Ok(()),
+    };
+
+    if rows.is_empty() {
+        debug!("No rows were parsed, so there is nothing to push to the pushgateway.");
+        return Ok(());
+    }
+
+    // Grouping key: /metrics/job/<job>/<label>/<value>/...
+    let url = format!(
+        "{}/metrics/job/nccl_harness/collective/{}/algorithm/{}/channels/{}/chunks/{}/gpus/{}",
+        base_url,
+        params.nc_collective,
+        params.algorithm,
+        params.ms_channels,
+        params.ms_chunks,
+        params.total_gpus,
+    );
+
+    // Build the body using the Prometheus text exposition format
+    let mut body = String::new();
+    writeln!(body, "# TYPE nccl_harness_oop_bus_bw gauge")?;
+    for row in rows {
+        writeln!(body, "nccl_harness_oop_bus_bw{{size=\"{}\"}} {}", row.size, row.oop_bus_bw)?;
+    }
+    writeln!(body, "# TYPE nccl_harness_ip_bus_bw gauge")?;
+    for row in rows {
+        writeln!(body, "nccl_harness_ip_bus_bw{{size=\"{}\"}} {}", row.size, row.ip_bus_bw)?;
+    }
+
+    debug!("Pushing {} rows of bus bandwidth metrics to: {}", rows.len(), url);
+    ureq::post(url.as_str())
+        .set("Content-Type", "text/plain; version=0.0.4")
+        .send_string(body.as_str())?;
+    info!("Pushed bus bandwidth metrics to the pushgateway.");
+
+    Ok(())
+}
@@ -1,38 +1,154 @@
+use std::path::Path;
 use regex::Regex;
+#[cfg(feature = "dataframe")]
 use polars::prelude::*;
+#[cfg(feature = "dataframe")]
+use serde::Serialize;
+use tracing::warn;
 
 // mod util;
 use crate::{Row, Permutation, MscclExperimentParams};
+use crate::util::{WrongColumn, WrongValues};
 
-/// Convert rows to a Polars DataFrame
+/// Convert rows to a Polars DataFrame (requires the `dataframe` feature)
 /// 
 /// Note: The implementaiton is very manual and not efficient.
+#[cfg(feature = "dataframe")]
 pub fn rows_to_df(rows: Vec<Row>) -> Result<DataFrame, Box<dyn std::error::Error>> {
     // Create the dataframe
     let df = DataFrame::new(vec![
         Series::new("size", rows.iter().map(|r| r.size).collect::<Vec<u64>>()),
         Series::new("count", rows.iter().map(|r| r.count).collect::<Vec<u64>>()),
         Series::new("dtype", rows.iter().map(|r| r.dtype.clone()).collect::<Vec<String>>()),
-        Series::new("redop", rows.iter().map(|r| r.redop.clone()).collect::<Vec<String>>()),
+        Series::new("redop", rows.iter().map(|r| r.redop.clone()).collect::<Vec<Option<String>>>()),
         Series::new("root", rows.iter().map(|r| r.root).collect::<Vec<i64>>()),
         Series::new("oop_time", rows.iter().map(|r| r.oop_time).collect::<Vec<f64>>()),
         Series::new("oop_alg_bw", rows.iter().map(|r| r.oop_alg_bw).collect::<Vec<f64>>()),
         Series::new("oop_bus_bw", rows.iter().map(|r| r.oop_bus_bw).collect::<Vec<f64>>()),
         Series::new("oop_num_wrong", rows.iter().map(|r| r.oop_num_wrong.clone()).collect::<Vec<String>>()),
+        Series::new("oop_wrong_count", rows.iter().map(|r| r.oop_wrong.count()).collect::<Vec<Option<u64>>>()),
+        Series::new("oop_error", rows.iter().map(|r| r.oop_wrong.magnitude()).collect::<Vec<Option<f64>>>()),
         Series::new("ip_time", rows.iter().map(|r| r.ip_time).collect::<Vec<f64>>()),
         Series::new("ip_alg_bw", rows.iter().map(|r| r.ip_alg_bw).collect::<Vec<f64>>()),
         Series::new("ip_bus_bw", rows.iter().map(|r| r.ip_bus_bw).collect::<Vec<f64>>()),
-        Series::new("ip_num_wrong", rows.iter().map(|r| r.ip_num_wrong.clone()).collect::<Vec<String>>())
+        Series::new("ip_num_wrong", rows.iter().map(|r| r.ip_num_wrong.clone()).collect::<Vec<String>>()),
+        Series::new("ip_wrong_count", rows.iter().map(|r| r.ip_wrong.count()).collect::<Vec<Option<u64>>>()),
+        Series::new("ip_error", rows.iter().map(|r| r.ip_wrong.magnitude()).collect::<Vec<Option<f64>>>()),
+        Series::new("table", rows.iter().map(|r| r.table).collect::<Vec<u64>>())
     ])?;
 
     Ok(df)
 }
 
+/// Names of the columns of the DataFrame created by `rows_to_df` (one per `Row` field, with the interpreted correctness
+/// column split into a count and a magnitude)
+#[cfg(feature = "dataframe")]
+pub const ROW_COLUMNS: [&str; 18] = [
+    "size", "count", "dtype", "redop", "root",
+    "oop_time", "oop_alg_bw", "oop_bus_bw", "oop_num_wrong", "oop_wrong_count", "oop_error",
+    "ip_time", "ip_alg_bw", "ip_bus_bw", "ip_num_wrong", "ip_wrong_count", "ip_error",
+    "table",
+];
+
+/// Check that every requested column is one of the `Row` columns (see `ROW_COLUMNS`)
+#[cfg(feature = "dataframe")]
+pub fn validate_row_columns(columns: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    for column in columns {
+        if !ROW_COLUMNS.contains(&column.as_str()) {
+            return Err(format!("Unknown result column: {} (expected one of: {})", column, ROW_COLUMNS.join(", ")).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep only the given `Row` columns of a DataFrame created by `rows_to_df` (in the given order)
+#[cfg(feature = "dataframe")]
+pub fn select_row_columns(df: DataFrame, columns: &[String]) -> Result<DataFrame, Box<dyn std::error::Error>> {
+    Ok(df.select(columns)?)
+}
+
+/// Unit of the bandwidth columns added by `add_bandwidth_unit_columns`
+#[cfg(feature = "dataframe")]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum BandwidthUnit {
+    GigabytesPerSec, // GB/s (as reported by NCCL tests)
+    GigabitsPerSec,  // Gbit/s (as used by NIC specs)
+}
+
+#[cfg(feature = "dataframe")]
+impl BandwidthUnit {
+    /// Get a unit from its name (`GB/s` or `Gbit/s`, case-insensitive)
+    pub fn from_name(name: &str) -> Result<BandwidthUnit, Box<dyn std::error::Error>> {
+        match name.to_lowercase().as_str() {
+            "gb/s" | "gbps" => Ok(BandwidthUnit::GigabytesPerSec),
+            "gbit/s" | "gbitps" => Ok(BandwidthUnit::GigabitsPerSec),
+            _ => Err(format!("Unknown bandwidth unit: {} (expected GB/s or Gbit/s)", name).into()),
+        }
+    }
+
+    /// Get the suffix of the column names in this unit
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            BandwidthUnit::GigabytesPerSec => "GBps",
+            BandwidthUnit::GigabitsPerSec => "Gbitps",
+        }
+    }
+
+    /// Get the factor that converts from GB/s to this unit
+    pub fn factor(&self) -> f64 {
+        match self {
+            BandwidthUnit::GigabytesPerSec => 1.0,
+            BandwidthUnit::GigabitsPerSec => 8.0,
+        }
+    }
+}
+
+/// Add a copy of every bandwidth column with an explicit unit suffix (e.g., `oop_bus_bw_Gbitps`)
+///
+/// Note: NCCL tests report algorithm and bus bandwidth in GB/s, with 1 GB = 10^9 bytes (SI, not GiB). The original
+///       columns are kept as they are. Bandwidth columns that aren't in the DataFrame (e.g., because they weren't
+///       selected) are skipped.
+#[cfg(feature = "dataframe")]
+pub fn add_bandwidth_unit_columns(mut df: DataFrame, unit: BandwidthUnit) -> Result<DataFrame, Box<dyn std::error::Error>> {
+    for name in ["oop_alg_bw", "oop_bus_bw", "ip_alg_bw", "ip_bus_bw"] {
+        if df.get_column_index(name).is_none() {
+            continue;
+        }
+        let mut converted = df.column(name)? * unit.factor();
+        converted.rename(format!("{}_{}", name, unit.suffix()).as_str());
+        df.with_column(converted)?;
+    }
+
+    Ok(df)
+}
+
+/// Normalize the redop column of a table row
+///
+/// Note: Non-reducing collectives print `none` (e.g., broadcast) or leave the column blank (e.g., hypercube). Both (and
+///       `N/A`) become `None`, so filtering by op downstream doesn't depend on how the collective spells "no op".
+pub fn normalize_redop(redop: &str) -> Option<String> {
+    match redop.to_lowercase().as_str() {
+        "" | "none" | "n/a" => None,
+        _ => Some(redop.to_string()),
+    }
+}
+
 /// Parse a line from the NCCL output
 /// 
 /// Note: Only returns something if the line is a table data row
 pub fn parse_line(line: &str) -> Result<Option<Row>, Box<dyn std::error::Error>> {
-    let line_slice = line.split_whitespace().collect::<Vec<&str>>();
+    // `#`-prefixed lines are nccl-tests' header and footer comments, never data (even if they have 13 tokens)
+    if line.trim_start().starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut line_slice = line.split_whitespace().collect::<Vec<&str>>();
+
+    // Some collectives (e.g., hypercube) leave the redop column blank, which drops a column when splitting
+    if line_slice.len() == 12 && line_slice[3].parse::<i64>().is_ok() {
+        line_slice.insert(3, "");
+    }
 
     // Describes the prelude to a logfile
     let re = Regex::new(r"[A-z0-9]+:[0-9]+:[0-9]+").unwrap();
@@ -66,11 +182,7 @@ pub fn parse_line(line: &str) -> Result<Option<Row>, Box<dyn std::error::Error>>
                 }
             },
             dtype: line_slice[2].to_string(),
-            redop: match line_slice[3].to_string().is_empty() {
-                true => "N/A".to_string(),
-                false => line_slice[3].to_string()
-            
-            },
+            redop: normalize_redop(line_slice[3]),
             root: match line_slice[4].parse::<i64>() {
                 Ok(v) => v,
                 Err(e) => {
@@ -100,6 +212,7 @@ pub fn parse_line(line: &str) -> Result<Option<Row>, Box<dyn std::error::Error>>
                 }
             },
             oop_num_wrong: line_slice[8].to_string(),
+            oop_wrong: WrongValues::parse(line_slice[8], None), // Reinterpreted by `TableParser` once the header is known
             ip_time: match line_slice[9].parse::<f64>() {
                 Ok(v) => v,
                 Err(e) => {
@@ -121,13 +234,505 @@ pub fn parse_line(line: &str) -> Result<Option<Row>, Box<dyn std::error::Error>>
                     return Ok(None);
                 }
             },
-            ip_num_wrong: line_slice[12].to_string()
+            ip_num_wrong: line_slice[12].to_string(),
+            ip_wrong: WrongValues::parse(line_slice[12], None),
+            table: 0, // Only known to the stateful `TableParser`
         };
         // println!("Row: {:?}", row);
 
+        // Reject non-finite values (e.g., from a `nan` or `inf` token) so that they never reach the summary statistics
+        let values = [row.oop_time, row.oop_alg_bw, row.oop_bus_bw, row.ip_time, row.ip_alg_bw, row.ip_bus_bw];
+        if values.iter().any(|v| !v.is_finite()) {
+            warn!("Ignoring table row with non-finite time or bandwidth values: {}", line.trim());
+            return Ok(None);
+        }
+
         // Return that a line was successfully parsed
         return Ok(Some(row));
     }
 
     Ok(None)
-}
\ No newline at end of file
+}
+/// Header of an nccl-tests results table (the same layout nccl-tests prints)
+const NCCL_TABLE_HEADER: [&str; 3] = [
+    "#                                                              out-of-place                       in-place          ",
+    "#       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong",
+    "#        (B)    (elements)                               (us)  (GB/s)  (GB/s)            (us)  (GB/s)  (GB/s)       ",
+];
+
+/// Format a time the way nccl-tests does (fewer decimals for longer times so the column stays 7 wide)
+fn format_nccl_time(time: f64) -> String {
+    if time >= 10000.0 {
+        format!("{:7.0}", time)
+    } else if time >= 100.0 {
+        format!("{:7.1}", time)
+    } else {
+        format!("{:7.2}", time)
+    }
+}
+
+/// Format a single row as an nccl-tests table data row
+///
+/// Note: This is the inverse of `parse_line`. Collectives without a redop get `none` in the redop column, just like
+///       nccl-tests prints them for non-reducing collectives.
+pub fn format_line(row: &Row) -> String {
+    let redop = row.redop.as_deref().unwrap_or("none");
+
+    format!(
+        "{:>12}  {:>12}  {:>8}  {:>6}  {:>6}  {}  {:6.2}  {:6.2}  {:>5}  {}  {:6.2}  {:6.2}  {:>5}",
+        row.size, row.count, row.dtype, redop, row.root,
+        format_nccl_time(row.oop_time), row.oop_alg_bw, row.oop_bus_bw, row.oop_num_wrong,
+        format_nccl_time(row.ip_time), row.ip_alg_bw, row.ip_bus_bw, row.ip_num_wrong,
+    )
+}
+
+/// Format rows as nccl-tests results tables (aligned columns, same header) for tools that expect the raw format
+///
+/// Note: A header is printed at the start of every table (e.g., one per datatype), so parsing the output again with
+///       `TableParser` yields equivalent rows.
+pub fn format_nccl_table(rows: &[Row]) -> String {
+    let mut table = String::new();
+    let mut current_table = None;
+    for row in rows {
+        if current_table != Some(row.table) {
+            for line in NCCL_TABLE_HEADER {
+                table.push_str(line);
+                table.push('\n');
+            }
+            current_table = Some(row.table);
+        }
+
+        table.push_str(format_line(row).as_str());
+        table.push('\n');
+    }
+
+    table
+}
+
+/// Context that nccl-tests prints in the `#` header lines before its tables
+///
+/// Note: The fields are only set if the corresponding header line was seen (e.g.,
+///       `# nThread 1 nGpus 1 minBytes 8 maxBytes 134217728 step: 2(factor) warmup iters: 5 iters: 20 ...`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NcclTestHeader {
+    pub num_threads: Option<u64>, // `nThread`: Threads per process
+    pub num_gpus: Option<u64>, // `nGpus`: GPUs per thread
+    pub min_bytes: Option<u64>, // `minBytes`
+    pub max_bytes: Option<u64>, // `maxBytes`
+    pub wrong_column: Option<WrongColumn>, // From the column header (`#wrong` or `error`)
+}
+
+impl NcclTestHeader {
+    /// Pick up the context in a `#` header line (lines without any are ignored)
+    pub fn update(&mut self, line: &str) {
+        let tokens = line.trim_start().trim_start_matches('#').split_whitespace().collect::<Vec<&str>>();
+        for pair in tokens.windows(2) {
+            let value = match pair[1].parse::<u64>() {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            match pair[0] {
+                "nThread" => self.num_threads = Some(value),
+                "nGpus" => self.num_gpus = Some(value),
+                "minBytes" => self.min_bytes = Some(value),
+                "maxBytes" => self.max_bytes = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    /// Pick up the kind of correctness column from a table's column header (`#  size  count  type ... #wrong`)
+    pub fn update_columns(&mut self, line: &str) {
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        if tokens.contains(&"#wrong") {
+            self.wrong_column = Some(WrongColumn::Count);
+        } else if tokens.contains(&"error") {
+            self.wrong_column = Some(WrongColumn::Magnitude);
+        }
+    }
+}
+
+/// The summary nccl-tests prints at the end of a run (`# Out of bounds values : N OK` and `# Avg bus bandwidth : X`)
+///
+/// Note: The fields are only set if the corresponding footer line was seen. The out-of-bounds count is nccl-tests' own
+///       verdict on the correctness of the whole run, so it is more robust than summing the `#wrong` columns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Footer {
+    pub out_of_bounds: Option<u64>, // Number of wrong values across all sizes (0 means the results are correct)
+    pub avg_bus_bw: Option<f64>, // Mean bus bandwidth across all sizes in GB/s
+    pub num_rows: u64, // Table rows the footer summarizes (to weigh the averages of several runs)
+}
+
+impl Footer {
+    /// Check whether nccl-tests found wrong values
+    pub fn has_wrong_values(&self) -> bool {
+        self.out_of_bounds.is_some_and(|n| n > 0)
+    }
+}
+
+/// Combine the footers of several runs appended to one log (e.g., one per iteration regime) into one
+///
+/// Note: The out-of-bounds counts are summed and the average bus bandwidths are weighted by the number of rows each
+///       footer summarizes. Returns `None` if there are no footers.
+pub fn combine_footers(footers: &[Footer]) -> Option<Footer> {
+    if footers.is_empty() {
+        return None;
+    }
+
+    let counts = footers.iter().filter_map(|f| f.out_of_bounds).collect::<Vec<u64>>();
+    let averages = footers.iter().filter_map(|f| f.avg_bus_bw.map(|bw| (bw, f.num_rows.max(1)))).collect::<Vec<(f64, u64)>>();
+    let weights = averages.iter().map(|(_, n)| *n).sum::<u64>();
+
+    Some(Footer {
+        out_of_bounds: if counts.is_empty() { None } else { Some(counts.iter().sum()) },
+        avg_bus_bw: if averages.is_empty() {
+            None
+        } else {
+            Some(averages.iter().map(|(bw, n)| bw * *n as f64).sum::<f64>() / weights as f64)
+        },
+        num_rows: footers.iter().map(|f| f.num_rows).sum(),
+    })
+}
+
+/// The timing of one iteration of one message size, from a per-iteration line of nccl-tests
+///
+/// Note: Upstream nccl-tests only prints the averages of the table. Per-iteration lines come from builds patched to
+///       report every timed iteration (enabled with their own flag, e.g., through `EXTRA_TEST_ARGS`), as
+///       `# iter 3 size 1048576 inplace 0 time 45.21 algbw 23.19 busbw 43.48` (the bandwidths are optional).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationTiming {
+    pub size: u64,
+    pub iteration: u64,
+    pub in_place: bool,
+    pub time: f64, // In microseconds
+    pub alg_bw: Option<f64>,
+    pub bus_bw: Option<f64>,
+}
+
+/// Parse a per-iteration line of the NCCL output (see `IterationTiming`)
+///
+/// Note: Only returns something if the line is a complete per-iteration line (`iter`, `size`, and `time` are required)
+pub fn parse_iteration_line(line: &str) -> Option<IterationTiming> {
+    let tokens = line.trim_start().strip_prefix('#')?.split_whitespace().collect::<Vec<&str>>();
+    if tokens.first() != Some(&"iter") {
+        return None;
+    }
+
+    let (mut iteration, mut size, mut time) = (None, None, None);
+    let mut timing = IterationTiming { size: 0, iteration: 0, in_place: false, time: 0.0, alg_bw: None, bus_bw: None };
+    for pair in tokens.windows(2) {
+        match pair[0] {
+            "iter" => iteration = pair[1].parse::<u64>().ok(),
+            "size" => size = pair[1].parse::<u64>().ok(),
+            "time" => time = pair[1].parse::<f64>().ok(),
+            "inplace" => timing.in_place = pair[1] == "1",
+            "algbw" => timing.alg_bw = pair[1].parse::<f64>().ok(),
+            "busbw" => timing.bus_bw = pair[1].parse::<f64>().ok(),
+            _ => {}
+        }
+    }
+
+    timing.iteration = iteration?;
+    timing.size = size?;
+    timing.time = time?;
+
+    Some(timing)
+}
+
+/// Stateful parser for NCCL output that keeps track of table boundaries
+///
+/// Note: nccl-tests prints one table (with its own header) per datatype when given multiple datatypes. A new table
+///       starts at each header line, or when the datatype changes without a header in between.
+#[derive(Debug, Default)]
+pub struct TableParser {
+    table: u64,
+    table_rows: u64,
+    current_dtype: Option<String>,
+    header: NcclTestHeader,
+    iterations: Vec<IterationTiming>,
+    footers: Vec<Footer>,
+    rows_since_footer: u64,
+}
+
+impl TableParser {
+    pub fn new() -> TableParser {
+        TableParser::default()
+    }
+
+    /// Get the context picked up from the `#` header lines so far
+    pub fn header(&self) -> &NcclTestHeader {
+        &self.header
+    }
+
+    /// Get the footers seen so far (one per run; several runs can be appended to one log)
+    pub fn footers(&self) -> &[Footer] {
+        &self.footers
+    }
+
+    /// Pick up a footer line (`# Out of bounds values : N OK` or `# Avg bus bandwidth : X`)
+    ///
+    /// Note: A footer line after new table rows starts a new footer, so the footers of appended runs stay separate.
+    ///
+    /// # Returns
+    /// Whether the line was a footer line
+    fn update_footer(&mut self, line: &str) -> bool {
+        let (key, value) = match line.trim_start_matches('#').split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return false,
+        };
+        if key != "Out of bounds values" && key != "Avg bus bandwidth" {
+            return false;
+        }
+
+        if self.footers.is_empty() || self.rows_since_footer > 0 {
+            self.footers.push(Footer { num_rows: self.rows_since_footer, ..Default::default() });
+            self.rows_since_footer = 0;
+        }
+        let footer = self.footers.last_mut().unwrap();
+        let first = value.split_whitespace().next().unwrap_or_default();
+        if key == "Out of bounds values" {
+            footer.out_of_bounds = first.parse::<u64>().ok();
+        } else {
+            footer.avg_bus_bw = first.parse::<f64>().ok();
+        }
+
+        true
+    }
+
+    /// Start a new table (if the current one already has rows)
+    fn next_table(&mut self) {
+        if self.table_rows > 0 {
+            self.table += 1;
+            self.table_rows = 0;
+        }
+        self.current_dtype = None;
+    }
+
+    /// Parse a line from the NCCL output, tagging table data rows with the table they belong to
+    ///
+    /// Note: Only returns something if the line is a table data row
+    pub fn parse_line(&mut self, line: &str) -> Result<Option<Row>, Box<dyn std::error::Error>> {
+        // Handle table header rows (e.g., `#  size  count  type  redop ...`)
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            if let Some(timing) = parse_iteration_line(trimmed) {
+                self.iterations.push(timing);
+            } else if trimmed.contains("size") && trimmed.contains("count") {
+                self.next_table();
+                self.header.update_columns(trimmed);
+            } else if !self.update_footer(trimmed) {
+                self.header.update(trimmed);
+            }
+            return Ok(None);
+        }
+
+        // Handle table data rows
+        let mut row = match parse_line(line)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        // A change of datatype without a header also means a new table
+        if let Some(dtype) = &self.current_dtype {
+            if *dtype != row.dtype {
+                self.next_table();
+            }
+        }
+        self.current_dtype = Some(row.dtype.clone());
+
+        // Interpret the correctness columns by the table's header (a count and a magnitude can both look like `0`)
+        if let Some(column) = self.header.wrong_column {
+            row.oop_wrong = WrongValues::parse(row.oop_num_wrong.as_str(), Some(column));
+            row.ip_wrong = WrongValues::parse(row.ip_num_wrong.as_str(), Some(column));
+        }
+
+        row.table = self.table;
+        self.table_rows += 1;
+        self.rows_since_footer += 1;
+
+        Ok(Some(row))
+    }
+}
+
+/// Read the per-iteration timings of an experiment from its log (empty if the build doesn't print them)
+pub fn parse_iteration_log(path: &Path) -> Result<Vec<IterationTiming>, Box<dyn std::error::Error>> {
+    let mut parser = TableParser::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        parser.parse_line(line)?;
+    }
+
+    Ok(parser.iterations)
+}
+
+/// Read the footer of an experiment from its log (the footers of appended runs are combined, see `combine_footers`)
+pub fn parse_footer_log(path: &Path) -> Result<Option<Footer>, Box<dyn std::error::Error>> {
+    let mut parser = TableParser::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        parser.parse_line(line)?;
+    }
+
+    Ok(combine_footers(&parser.footers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_tagged_with_their_table() {
+        let output = "\
+#       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong
+#        (B)    (elements)                               (us)  (GB/s)  (GB/s)            (us)  (GB/s)  (GB/s)       
+       65536         16384     float     sum      -1    16.00    4.10    7.17      0    16.10    4.07    7.12      0
+      131072         32768     float     sum      -1    18.00    7.28   12.74      0    18.20    7.20   12.60      0
+#       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong
+#        (B)    (elements)                               (us)  (GB/s)  (GB/s)            (us)  (GB/s)  (GB/s)       
+       65536         32768      half     sum      -1    15.00    4.37    7.65      0    15.10    4.34    7.60      0
+";
+        let mut parser = TableParser::new();
+        let rows = output.lines().filter_map(|l| parser.parse_line(l).unwrap()).collect::<Vec<Row>>();
+        assert_eq!(rows.iter().map(|r| (r.dtype.as_str(), r.table)).collect::<Vec<(&str, u64)>>(), [("float", 0), ("float", 0), ("half", 1)]);
+    }
+
+    /// Output of `hypercube_perf`, which leaves the redop column blank (so its rows split into 12 tokens)
+    const HYPERCUBE_OUTPUT: &str = "\
+# nThread 1 nGpus 1 minBytes 65536 maxBytes 131072 step: 2(factor) warmup iters: 5 iters: 20 agg iters: 1 validation: 1 graph: 0
+#
+#                                                              out-of-place                       in-place          
+#       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong
+#        (B)    (elements)                               (us)  (GB/s)  (GB/s)            (us)  (GB/s)  (GB/s)       
+       65536         16384     float              -1    35.12    1.87    1.63      0    34.98    1.87    1.64      0
+      131072         32768     float              -1    41.60    3.15    2.76      0    41.22    3.18    2.78      0
+# Out of bounds values : 0 OK
+# Avg bus bandwidth    : 2.2025 
+#
+";
+
+    #[test]
+    fn hypercube_rows_with_blank_redop_are_parsed() {
+        let mut parser = TableParser::new();
+        let rows = HYPERCUBE_OUTPUT.lines().filter_map(|l| parser.parse_line(l).unwrap()).collect::<Vec<Row>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].size, 65536);
+        assert_eq!(rows[0].redop, None);
+        assert_eq!(rows[0].root, -1);
+        assert_eq!(rows[1].oop_bus_bw, 2.76);
+        assert_eq!(rows[1].ip_num_wrong, "0");
+    }
+
+    #[test]
+    fn output_with_only_headers_has_no_rows() {
+        // E.g., a run that printed its headers but no results (classified as `NoData`)
+        let mut parser = TableParser::new();
+        let rows = HYPERCUBE_OUTPUT
+            .lines()
+            .filter(|l| l.trim_start().starts_with('#'))
+            .filter_map(|l| parser.parse_line(l).unwrap())
+            .collect::<Vec<Row>>();
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn formatted_rows_parse_back_to_themselves() {
+        let lines = [
+            "     1048576        262144     float     sum      -1    20.31   51.63   96.81      0    20.12   52.12   97.72      0",
+            "   134217728      33554432     float    none       0   1090.1  123.13  215.47    N/A    10880   12.34   21.59    N/A",
+        ];
+        for line in lines {
+            let row = parse_line(line).unwrap().unwrap();
+            assert_eq!(parse_line(format_line(&row).as_str()).unwrap(), Some(row));
+        }
+    }
+
+    #[test]
+    fn nan_bandwidth_row_is_rejected() {
+        let line = "     1048576        262144     float     sum      -1    20.31     nan   96.81      0    20.12   52.12   97.72      0";
+        assert!(parse_line(line).unwrap().is_none());
+    }
+
+    #[test]
+    fn inf_bandwidth_row_is_rejected() {
+        let line = "     1048576        262144     float     sum      -1    20.31   51.63   96.81      0    20.12   52.12     inf      0";
+        assert!(parse_line(line).unwrap().is_none());
+    }
+
+    #[test]
+    fn broadcast_none_redop_parses_to_none() {
+        let line = "     1048576        262144     float    none       0    20.31   51.63   51.63      0    20.12   52.12   52.12      0";
+        let row = parse_line(line).unwrap().unwrap();
+        assert_eq!(row.redop, None);
+        assert_eq!(row.root, 0);
+    }
+
+    #[test]
+    fn blank_redop_parses_to_none() {
+        let line = "     1048576        262144     float              -1    20.31   51.63   51.63      0    20.12   52.12   52.12      0";
+        let row = parse_line(line).unwrap().unwrap();
+        assert_eq!(row.redop, None);
+        assert_eq!(row.root, -1);
+    }
+
+    #[test]
+    fn sum_redop_is_kept() {
+        let line = "     1048576        262144     float     sum      -1    20.31   51.63   96.81      0    20.12   52.12   97.72      0";
+        let row = parse_line(line).unwrap().unwrap();
+        assert_eq!(row.redop.as_deref(), Some("sum"));
+    }
+
+    #[test]
+    fn preamble_is_skipped_and_picked_up() {
+        let output = "\
+# nThread 2 nGpus 4 minBytes 8 maxBytes 134217728 step: 2(factor) warmup iters: 5 iters: 20 agg iters: 1 validation: 1 graph: 0
+#
+# Using devices
+#  Rank  0 Group  0 Pid  12345 on ip-10-0-0-1 device  0 [0x10] NVIDIA A100-SXM4-40GB
+#  Rank  1 Group  0 Pid  12345 on ip-10-0-0-1 device  1 [0x20] NVIDIA A100-SXM4-40GB
+#
+#                                                              out-of-place                       in-place          
+#       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong
+#        (B)    (elements)                               (us)  (GB/s)  (GB/s)            (us)  (GB/s)  (GB/s)       
+";
+        let mut parser = TableParser::new();
+        for line in output.lines() {
+            assert!(parser.parse_line(line).unwrap().is_none());
+        }
+        let header = parser.header();
+        assert_eq!(header.num_threads, Some(2));
+        assert_eq!(header.num_gpus, Some(4));
+        assert_eq!(header.min_bytes, Some(8));
+        assert_eq!(header.max_bytes, Some(134217728));
+        assert_eq!(header.wrong_column, Some(WrongColumn::Count));
+    }
+
+    /// Parse every line of some NCCL output and get the parser's combined footer
+    fn parse_footer(output: &str) -> Option<Footer> {
+        let mut parser = TableParser::new();
+        for line in output.lines() {
+            parser.parse_line(line).unwrap();
+        }
+        combine_footers(parser.footers())
+    }
+
+    #[test]
+    fn ok_footer_is_parsed() {
+        let footer = parse_footer(HYPERCUBE_OUTPUT).unwrap();
+        assert_eq!(footer.out_of_bounds, Some(0));
+        assert_eq!(footer.avg_bus_bw, Some(2.2025));
+        assert_eq!(footer.num_rows, 2);
+        assert!(!footer.has_wrong_values());
+    }
+
+    #[test]
+    fn out_of_bounds_footer_has_wrong_values() {
+        let output = HYPERCUBE_OUTPUT.replace("# Out of bounds values : 0 OK", "# Out of bounds values : 12 FAILED");
+        let footer = parse_footer(output.as_str()).unwrap();
+        assert_eq!(footer.out_of_bounds, Some(12));
+        assert!(footer.has_wrong_values());
+    }
+
+    #[test]
+    fn missing_footer_is_none() {
+        let output = HYPERCUBE_OUTPUT.lines().filter(|l| !l.contains(" : ")).collect::<Vec<&str>>().join("\n");
+        assert_eq!(parse_footer(output.as_str()), None);
+    }
+}
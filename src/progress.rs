@@ -0,0 +1,84 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Live progress bar of a sweep (experiments completed/total, current config, and ETA)
+///
+/// Note: The bar is hidden until the sweep starts. Log lines must be written through `SweepProgress::writer` so that
+///       they are printed above the bar instead of tearing it.
+#[derive(Clone)]
+pub struct SweepProgress {
+    bar: ProgressBar,
+    start: Instant,
+}
+
+impl SweepProgress {
+    pub fn new() -> SweepProgress {
+        let bar = ProgressBar::with_draw_target(Some(0), ProgressDrawTarget::hidden());
+        bar.set_style(
+            ProgressStyle::with_template("{elapsed_precise} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+
+        SweepProgress { bar, start: Instant::now() }
+    }
+
+    /// Get a writer for log lines that suspends the bar while writing
+    pub fn writer(&self) -> ProgressWriter {
+        ProgressWriter(self.bar.clone())
+    }
+
+    /// Show the bar for a sweep of `total` experiments
+    pub fn start(&mut self, total: u64) {
+        self.start = Instant::now();
+        self.bar.set_length(total);
+        self.bar.set_draw_target(ProgressDrawTarget::stderr());
+    }
+
+    /// Update the bar with the number of completed experiments and the config that runs next
+    pub fn update(&self, completed: u64, config: &str) {
+        self.bar.set_position(completed);
+
+        // Estimate the remaining time from the average duration of the completed experiments
+        let eta = if completed > 0 {
+            let average = self.start.elapsed().as_secs_f64() / completed as f64;
+            let remaining = self.bar.length().unwrap_or(completed).saturating_sub(completed);
+            format_duration(Duration::from_secs_f64(average * remaining as f64))
+        } else {
+            "?".to_string()
+        };
+
+        self.bar.set_message(format!("ETA {} | {}", eta, config));
+    }
+
+    /// Mark the sweep as finished
+    pub fn finish(&self) {
+        self.bar.finish_with_message(format!("Done in {}", format_duration(self.start.elapsed())));
+    }
+}
+
+impl Default for SweepProgress {
+    fn default() -> Self {
+        SweepProgress::new()
+    }
+}
+
+/// Format a duration as, e.g., `1h02m03s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}h{:02}m{:02}s", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// Writes to stderr while the progress bar is suspended
+pub struct ProgressWriter(ProgressBar);
+
+impl Write for ProgressWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.suspend(|| std::io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use tracing::{debug, warn};
+
+use crate::util::create_output_file;
+
+/// Background `nvidia-smi` sampler that records GPU power draw and utilization during an experiment
+pub struct PowerSampler {
+    child: Child,
+}
+
+impl PowerSampler {
+    /// Start sampling GPU power draw and utilization every 100 ms, writing the samples as CSV to `output_path`
+    ///
+    /// Note: Returns `None` (after logging a warning) if `nvidia-smi` can't be started, so that a missing
+    ///       `nvidia-smi` never stops the sweep.
+    pub fn start(output_path: &Path) -> Option<PowerSampler> {
+        let output_file = match create_output_file(output_path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Error creating power sample file {:?}. WILL NOT SAMPLE POWER!: {}", output_path, e);
+                return None;
+            }
+        };
+
+        match Command::new("nvidia-smi")
+            .args(["--query-gpu=timestamp,index,power.draw,utilization.gpu", "--format=csv", "-lms", "100"])
+            .stdout(output_file)
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => {
+                debug!("Started power sampler writing to: {}", output_path.to_str().unwrap());
+                Some(PowerSampler { child })
+            }
+            Err(e) => {
+                warn!("Could not start 'nvidia-smi' to sample power: {}. Continuing without power samples.", e);
+                None
+            }
+        }
+    }
+
+    /// Stop sampling and wait for `nvidia-smi` to exit
+    pub fn stop(mut self) {
+        if let Err(e) = self.child.kill() {
+            warn!("Error stopping power sampler: {}", e);
+        }
+        if let Err(e) = self.child.wait() {
+            warn!("Error waiting for power sampler to exit: {}", e);
+        }
+        debug!("Stopped power sampler.");
+    }
+}
@@ -0,0 +1,86 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::util::{collective_to_test_exe, ManifestEntry, ResultDescription};
+
+/// Stub nccl-tests executable that prints canned output (see `scripts/stub-nccl-tests.sh`)
+const STUB_NCCL_TESTS: &str = include_str!("../scripts/stub-nccl-tests.sh");
+
+/// Collectives the stub is installed for (one copy per nccl-tests executable)
+const SELF_TEST_COLLECTIVES: [&str; 10] = [
+    "all-reduce", "all-gather", "all-to-all", "broadcast", "gather", "hypercube", "reduce", "reduce-scatter", "scatter", "sendrecv",
+];
+
+/// Set up a throwaway environment that runs the full pipeline against the stub executable (`self-test`)
+///
+/// Note: Installs the stub under every nccl-tests executable name, creates an empty XML directory and a single-host
+///       hostfile, and points the harness's envvars at them. Experiments run locally without MSCCL (`LAUNCHER=local`,
+///       `NCCL_FALLBACK_FOR_MISSING_XMLS=true`), so no GPUs, MPI, or NCCL are needed. Must be called before any of the
+///       envvars are read.
+///
+/// # Returns
+/// The directory the experiments' output is written to
+pub fn setup_self_test(dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let bin_dir = dir.join("bin");
+    let xml_dir = dir.join("xmls");
+    let output_dir = dir.join("output");
+    for d in [&bin_dir, &xml_dir, &output_dir] {
+        std::fs::create_dir_all(d)?;
+    }
+
+    for collective in SELF_TEST_COLLECTIVES {
+        let exe_path = bin_dir.join(collective_to_test_exe(collective)?);
+        std::fs::write(&exe_path, STUB_NCCL_TESTS)?;
+        std::fs::set_permissions(&exe_path, std::fs::Permissions::from_mode(0o755))?;
+        debug!("Installed stub NCCL tests executable at: {:?}", exe_path);
+    }
+
+    let hostfile_path = dir.join("hostfile");
+    std::fs::write(&hostfile_path, "localhost slots=1\n")?;
+
+    let dir_str = dir.to_str().ok_or("Self-test directory is not valid UTF-8")?;
+    let envvars = [
+        ("NCCL_TESTS_HOME", bin_dir.to_str().unwrap()),
+        ("MSCCL_XMLS", xml_dir.to_str().unwrap()),
+        ("EXPERIMENTS_OUTPUT_DIR", output_dir.to_str().unwrap()),
+        ("MPI_HOSTFILE", hostfile_path.to_str().unwrap()),
+        ("CUDA_HOME", dir_str),
+        ("OPENMPI_PATH", dir_str),
+        ("MSCCL_PATH", dir_str),
+        ("NUM_NODES", "1"),
+        ("GPUS_PER_NODE", "2"), // Sweeps over fewer than 2 GPUs are dropped as infeasible
+        ("MPI_PROC_PER_NODE", "1"),
+        ("LAUNCHER", "local"),
+        ("NCCL_FALLBACK_FOR_MISSING_XMLS", "true"),
+    ];
+    for (key, value) in envvars {
+        std::env::set_var(key, value);
+    }
+    info!("Set up the self-test environment at: {:?}", dir);
+
+    Ok(output_dir)
+}
+
+/// Check that every experiment of a self-test ran and produced parseable results
+pub fn check_self_test(entries: &[ManifestEntry]) -> Result<(), Box<dyn std::error::Error>> {
+    if entries.is_empty() {
+        return Err("Self-test failed: no experiments were run".into());
+    }
+
+    let failed = entries
+        .iter()
+        .filter(|e| !matches!(e.overall_result, ResultDescription::Success | ResultDescription::Warmup))
+        .map(|e| format!("{} {} ch{} ck{}: {}", e.collective, e.algorithm, e.num_channels, e.num_chunks, e.overall_result))
+        .collect::<Vec<String>>();
+    if !failed.is_empty() {
+        return Err(format!(
+            "Self-test failed: {} of {} experiment(s) did not succeed: {}",
+            failed.len(),
+            entries.len(),
+            failed.join(", ")
+        ).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::path::Path;
+use rusqlite::{params, Connection};
+use tracing::{debug, info};
+
+use crate::{Row, MscclExperimentParams, ManifestEntry};
+use crate::util::SweepMetadata;
+
+/// Schema of the results database (created if absent, so several sweeps can be collected in one file)
+///
+/// Note: Every row is tagged with the sweep's run ID (if set) so that sweeps can be told apart in queries
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS results (
+    run_id TEXT,
+    collective TEXT NOT NULL,
+    op TEXT NOT NULL,
+    dtype TEXT NOT NULL,
+    algorithm TEXT NOT NULL,
+    channels INTEGER NOT NULL,
+    chunks INTEGER NOT NULL,
+    nodes INTEGER NOT NULL,
+    gpus INTEGER NOT NULL,
+    buffer_size INTEGER NOT NULL,
+    gpu_as_node INTEGER NOT NULL,
+    iteration INTEGER NOT NULL,
+    table_index INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    redop TEXT,
+    root INTEGER NOT NULL,
+    oop_time REAL NOT NULL,
+    oop_alg_bw REAL NOT NULL,
+    oop_bus_bw REAL NOT NULL,
+    oop_num_wrong TEXT NOT NULL,
+    ip_time REAL NOT NULL,
+    ip_alg_bw REAL NOT NULL,
+    ip_bus_bw REAL NOT NULL,
+    ip_num_wrong TEXT NOT NULL,
+    build TEXT,
+    net_plugin TEXT,
+    host_group TEXT
+);
+CREATE TABLE IF NOT EXISTS manifest (
+    run_id TEXT,
+    collective TEXT NOT NULL,
+    op TEXT NOT NULL,
+    dtype TEXT NOT NULL,
+    algorithm TEXT NOT NULL,
+    num_channels INTEGER NOT NULL,
+    num_chunks INTEGER NOT NULL,
+    num_gpus INTEGER NOT NULL,
+    buffer_size_factor INTEGER NOT NULL,
+    overall_result TEXT NOT NULL,
+    flagged_sizes TEXT NOT NULL,
+    peak_bus_bw_median REAL,
+    efficiency REAL,
+    notes TEXT NOT NULL,
+    config_hash TEXT,
+    build TEXT,
+    net_plugin TEXT,
+    host_group TEXT
+);
+";
+
+/// Writes the results of a sweep to a SQLite database as the experiments complete
+///
+/// Note: The `results` table gets one row per parsed table row (plus the columns that identify the experiment) and the
+///       `manifest` table gets the manifest entries once the sweep finishes.
+pub struct SqliteExporter {
+    connection: Connection,
+    run_id: Option<String>,
+}
+
+impl SqliteExporter {
+    /// Open (or create) the database at `path`, creating the tables if they are absent
+    pub fn new(path: &Path, metadata: &SweepMetadata) -> Result<SqliteExporter, Box<dyn std::error::Error>> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        debug!("Opened SQLite database at: {:?}", path);
+
+        Ok(SqliteExporter { connection, run_id: metadata.run_id.clone() })
+    }
+
+    /// Insert the rows of a single experiment (in one transaction)
+    pub fn write_rows(
+        &mut self,
+        params: &MscclExperimentParams,
+        iteration: u64,
+        rows: &[Row],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO results VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)",
+            )?;
+            for row in rows {
+                statement.execute(params![
+                    self.run_id,
+                    params.nc_collective,
+                    params.nc_op,
+                    params.nc_dtype,
+                    params.algorithm,
+                    params.ms_channels,
+                    params.ms_chunks,
+                    params.num_nodes,
+                    params.total_gpus,
+                    params.buffer_size,
+                    params.gpu_as_node,
+                    iteration,
+                    row.table,
+                    row.size,
+                    row.count,
+                    row.redop,
+                    row.root,
+                    row.oop_time,
+                    row.oop_alg_bw,
+                    row.oop_bus_bw,
+                    row.oop_num_wrong,
+                    row.ip_time,
+                    row.ip_alg_bw,
+                    row.ip_bus_bw,
+                    row.ip_num_wrong,
+                    params.nc_build,
+                    params.nccl_net_plugin.as_ref().map(|p| p.name.clone()),
+                    params.mpi_host_group,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Insert the manifest entries of the sweep (in one transaction)
+    pub fn write_manifest(&mut self, entries: &[ManifestEntry]) -> Result<(), Box<dyn std::error::Error>> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO manifest VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            )?;
+            for entry in entries {
+                statement.execute(params![
+                    self.run_id,
+                    entry.collective,
+                    entry.op,
+                    entry.dtype,
+                    entry.algorithm,
+                    entry.num_channels,
+                    entry.num_chunks,
+                    entry.num_gpus,
+                    entry.buffer_size_factor,
+                    entry.overall_result.to_string(),
+                    serde_json::to_string(&entry.flagged_sizes)?,
+                    entry.peak_bus_bw.as_ref().map(|s| s.median),
+                    entry.efficiency,
+                    entry.notes.join("; "),
+                    entry.config_hash,
+                    entry.build,
+                    entry.net_plugin,
+                    entry.host_group,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        info!("Wrote {} manifest entries to the SQLite database.", entries.len());
+
+        Ok(())
+    }
+}
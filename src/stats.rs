@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Row;
+use crate::util::parse_size_bytes;
+
+/// Get the median of the given values
+///
+/// Note: Returns `None` if there are no values. NaNs are sorted to the end.
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// Get the message sizes of rows whose out-of-place bus bandwidth is anomalously low
+///
+/// # Arguments
+/// * `rows` - The parsed rows of a single experiment
+/// * `fraction` - Rows with an `oop_bus_bw` below `fraction * median(oop_bus_bw)` are flagged
+///
+/// # Returns
+/// The sizes (in bytes) of the flagged rows
+pub fn flag_low_bandwidth_sizes(rows: &[Row], fraction: f64) -> Vec<u64> {
+    let bus_bws = rows.iter().map(|r| r.oop_bus_bw).collect::<Vec<f64>>();
+    let threshold = match median(&bus_bws) {
+        Some(v) => v * fraction,
+        None => return Vec::new(),
+    };
+
+    rows.iter()
+        .filter(|r| r.oop_bus_bw < threshold)
+        .map(|r| r.size)
+        .collect()
+}
+
+/// A minimum out-of-place bus bandwidth (in GB/s) an experiment must reach to pass (for regression gating)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BandwidthGate {
+    pub collective: String, // `*` gates every collective
+    pub size: Option<u64>, // Gate the rows of this message size (otherwise the peak across all sizes)
+    pub min_bus_bw: f64,
+}
+
+/// Parse bandwidth gates given as comma-separated `collective[@size]=min_bus_bw` (e.g., `all-reduce=150,*@1G=40`)
+pub fn parse_bandwidth_gates(gates: &str) -> Result<Vec<BandwidthGate>, Box<dyn std::error::Error>> {
+    let mut parsed = Vec::new();
+    for entry in gates.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let (key, min_bus_bw) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid bandwidth gate '{}', expected 'collective[@size]=min_bus_bw'", entry))?;
+        let (collective, size) = match key.split_once('@') {
+            Some((collective, size)) => (collective.trim(), Some(parse_size_bytes(size)?)),
+            None => (key.trim(), None),
+        };
+        if collective.is_empty() {
+            return Err(format!("Bandwidth gate '{}' has no collective (use '*' for every collective)", entry).into());
+        }
+        let min_bus_bw = min_bus_bw
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("Invalid minimum bus bandwidth in gate '{}': {}", entry, e))?;
+        parsed.push(BandwidthGate { collective: collective.to_string(), size, min_bus_bw });
+    }
+
+    Ok(parsed)
+}
+
+/// Check a single experiment's rows against the bandwidth gates of its collective
+///
+/// Note: A gate on a message size the experiment has no rows for is skipped (e.g., outside of its size range).
+///
+/// # Returns
+/// A description of every gate that was missed, with the actual and required bus bandwidth
+pub fn bandwidth_gate_failures(rows: &[Row], collective: &str, gates: &[BandwidthGate]) -> Vec<String> {
+    gates
+        .iter()
+        .filter(|g| g.collective == "*" || g.collective == collective)
+        .filter_map(|g| {
+            let (actual, at) = match g.size {
+                Some(size) => (peak_bus_bw(&rows.iter().filter(|r| r.size == size).cloned().collect::<Vec<Row>>())?, format!("at size {}", size)),
+                None => (peak_bus_bw(rows)?, "peak".to_string()),
+            };
+            (actual < g.min_bus_bw).then(|| format!("{} bus bandwidth {:.2} GB/s is below the required {:.2} GB/s", at, actual, g.min_bus_bw))
+        })
+        .collect()
+}
+
+/// Find the tables of an experiment whose row count differs from the expected one (e.g., a truncated run)
+///
+/// Note: Rows are counted per table and reduction op, since `--op all` prints the rows of every op in one table.
+///
+/// # Arguments
+/// * `rows` - The parsed rows of a single experiment
+/// * `expected` - The number of rows every table should have (see `message_sizes`)
+///
+/// # Returns
+/// A description of every mismatching table (e.g., `5 of 8 rows (3 missing) in table 0`)
+pub fn row_count_mismatches(rows: &[Row], expected: u64) -> Vec<String> {
+    let mut counts: Vec<((u64, Option<String>), u64)> = Vec::new();
+    for row in rows {
+        let key = (row.table, row.redop.clone());
+        match counts.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((key, 1)),
+        }
+    }
+
+    counts.into_iter()
+        .filter(|(_, n)| *n != expected)
+        .map(|((table, redop), n)| {
+            let difference = if n < expected { format!("{} missing", expected - n) } else { format!("{} extra", n - expected) };
+            match redop {
+                Some(op) => format!("{} of {} rows ({}) in table {} ({})", n, expected, difference, table, op),
+                None => format!("{} of {} rows ({}) in table {}", n, expected, difference, table),
+            }
+        })
+        .collect()
+}
+
+/// Get the message sizes of rows whose in-place and out-of-place bus bandwidths diverge
+///
+/// Note: Rows where either bandwidth is zero (or negative) are only flagged if the other one isn't.
+///
+/// # Arguments
+/// * `rows` - The parsed rows of a single experiment
+/// * `max_ratio` - Rows where the larger bandwidth is more than `max_ratio` times the smaller one are flagged
+///
+/// # Returns
+/// The sizes (in bytes) of the flagged rows
+pub fn flag_inplace_divergence(rows: &[Row], max_ratio: f64) -> Vec<u64> {
+    rows.iter()
+        .filter(|r| {
+            let (low, high) = (r.ip_bus_bw.min(r.oop_bus_bw), r.ip_bus_bw.max(r.oop_bus_bw));
+            if low <= 0.0 {
+                high > 0.0
+            } else {
+                high / low > max_ratio
+            }
+        })
+        .map(|r| r.size)
+        .collect()
+}
+
+/// Summary statistics of a set of values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+}
+
+/// Get the min, max, and median of the given values
+///
+/// Note: Returns `None` if there are no values
+pub fn summarize(values: &[f64]) -> Option<Summary> {
+    Some(Summary {
+        min: values.iter().cloned().reduce(f64::min)?,
+        max: values.iter().cloned().reduce(f64::max)?,
+        median: median(values)?,
+    })
+}
+
+/// Get the peak out-of-place bus bandwidth of a single experiment
+///
+/// Note: Returns `None` if no rows were parsed
+pub fn peak_bus_bw(rows: &[Row]) -> Option<f64> {
+    rows.iter().map(|r| r.oop_bus_bw).reduce(f64::max)
+}
+
+/// Summary of the bus bandwidth of one side (out-of-place or in-place) of a single experiment's rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthSummary {
+    pub peak_bus_bw: f64,
+    pub peak_size: u64, // Message size (in bytes) the peak was reached at
+    pub mean_bus_bw: f64,
+    pub min_bus_bw: f64,
+    pub max_bus_bw: f64,
+}
+
+/// Compact summary of the rows of a single experiment (e.g., for dashboards)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowSummary {
+    pub num_rows: usize,
+    pub oop: BandwidthSummary,
+    pub ip: BandwidthSummary,
+}
+
+/// Summarize the given (size, bus bandwidth) pairs
+fn summarize_bus_bw(values: &[(u64, f64)]) -> Option<BandwidthSummary> {
+    let &(peak_size, peak_bus_bw) = values.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let bus_bws = values.iter().map(|(_, v)| *v).collect::<Vec<f64>>();
+
+    Some(BandwidthSummary {
+        peak_bus_bw,
+        peak_size,
+        mean_bus_bw: bus_bws.iter().sum::<f64>() / bus_bws.len() as f64,
+        min_bus_bw: bus_bws.iter().cloned().reduce(f64::min)?,
+        max_bus_bw: bus_bws.iter().cloned().reduce(f64::max)?,
+    })
+}
+
+/// Get the summary of the out-of-place and in-place bus bandwidths of a single experiment
+///
+/// Note: Returns `None` if no rows were parsed
+pub fn summarize_rows(rows: &[Row]) -> Option<RowSummary> {
+    Some(RowSummary {
+        num_rows: rows.len(),
+        oop: summarize_bus_bw(&rows.iter().map(|r| (r.size, r.oop_bus_bw)).collect::<Vec<(u64, f64)>>())?,
+        ip: summarize_bus_bw(&rows.iter().map(|r| (r.size, r.ip_bus_bw)).collect::<Vec<(u64, f64)>>())?,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::util::WrongValues;
+
+    /// A row of a float sum table with the given size and bus bandwidths (the algorithm bandwidths match them)
+    pub(crate) fn row(size: u64, oop_bus_bw: f64, ip_bus_bw: f64) -> Row {
+        Row {
+            size,
+            count: size / 4,
+            dtype: "float".to_string(),
+            redop: Some("sum".to_string()),
+            root: -1,
+            oop_time: 1.0,
+            oop_alg_bw: oop_bus_bw,
+            oop_bus_bw,
+            oop_num_wrong: "0".to_string(),
+            oop_wrong: WrongValues::Count(0),
+            ip_time: 1.0,
+            ip_alg_bw: ip_bus_bw,
+            ip_bus_bw,
+            ip_num_wrong: "0".to_string(),
+            ip_wrong: WrongValues::Count(0),
+            table: 0,
+        }
+    }
+
+    #[test]
+    fn median_of_odd_count_is_the_middle_value() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+    }
+
+    #[test]
+    fn median_of_even_count_averages_the_middle_values() {
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn median_of_nothing_is_none() {
+        assert_eq!(median(&[]), None);
+        assert!(summarize(&[]).is_none());
+    }
+
+    #[test]
+    fn summary_has_min_median_and_max() {
+        let summary = summarize(&[5.0, 1.0, 3.0]).unwrap();
+        assert_eq!((summary.min, summary.median, summary.max), (1.0, 3.0, 5.0));
+    }
+
+    #[test]
+    fn sizes_below_the_fraction_of_the_median_are_flagged() {
+        let rows = [row(1024, 10.0, 10.0), row(2048, 4.0, 4.0), row(4096, 12.0, 12.0), row(8192, 5.0, 5.0)];
+        // Median of 7.5, so the threshold is 3.75 at 0.5 and 7.5 at 1.0 (below, not at or below)
+        assert!(flag_low_bandwidth_sizes(&rows, 0.5).is_empty());
+        assert_eq!(flag_low_bandwidth_sizes(&rows, 0.6), vec![2048]);
+        assert_eq!(flag_low_bandwidth_sizes(&rows, 1.0), vec![2048, 8192]);
+        assert!(flag_low_bandwidth_sizes(&[], 0.5).is_empty());
+    }
+
+    #[test]
+    fn bandwidth_gates_are_parsed() {
+        let gates = parse_bandwidth_gates(" all-reduce=150, *@1K=40.5 ,").unwrap();
+        assert_eq!(
+            gates,
+            vec![
+                BandwidthGate { collective: "all-reduce".to_string(), size: None, min_bus_bw: 150.0 },
+                BandwidthGate { collective: "*".to_string(), size: Some(1024), min_bus_bw: 40.5 },
+            ]
+        );
+        assert!(parse_bandwidth_gates("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_bandwidth_gates_are_rejected() {
+        for gates in ["all-reduce", "=150", "@1G=150", "all-reduce=fast", "all-reduce@huge=150"] {
+            assert!(parse_bandwidth_gates(gates).is_err(), "{:?}", gates);
+        }
+    }
+
+    #[test]
+    fn gates_check_the_peak_or_a_single_size() {
+        let rows = [row(1024, 10.0, 10.0), row(2048, 20.0, 20.0)];
+        let gates = parse_bandwidth_gates("all-reduce=15,*@1K=15,*@4K=100,all-gather=100").unwrap();
+        // The peak (20) passes, 1K (10) misses, 4K has no rows, and the all-gather gate doesn't apply
+        let failures = bandwidth_gate_failures(&rows, "all-reduce", &gates);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("at size 1024"));
+    }
+
+    #[test]
+    fn row_counts_are_checked_per_table_and_op() {
+        let mut rows = vec![row(1024, 1.0, 1.0), row(2048, 1.0, 1.0), row(1024, 1.0, 1.0)];
+        rows[2].redop = Some("max".to_string());
+        rows.push(Row { table: 1, redop: None, ..row(1024, 1.0, 1.0) });
+        rows.push(Row { table: 1, redop: None, ..row(2048, 1.0, 1.0) });
+        rows.push(Row { table: 1, redop: None, ..row(4096, 1.0, 1.0) });
+        assert_eq!(
+            row_count_mismatches(&rows, 2),
+            vec!["1 of 2 rows (1 missing) in table 0 (max)".to_string(), "3 of 2 rows (1 extra) in table 1".to_string()]
+        );
+    }
+
+    #[test]
+    fn diverging_inplace_bandwidths_are_flagged() {
+        let rows = [row(1024, 10.0, 10.0), row(2048, 10.0, 25.0), row(4096, 0.0, 5.0), row(8192, 0.0, 0.0)];
+        assert_eq!(flag_inplace_divergence(&rows, 2.0), vec![2048, 4096]);
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+
+use crate::Row;
+
+/// Link bandwidths of the hardware the sweep runs on (in GB/s, like the bandwidths reported by NCCL tests)
+#[derive(Debug, Clone, Serialize)]
+pub struct HardwareSpec {
+    pub nvlink_bw: f64, // Per-GPU NVLink bandwidth (intra-node)
+    pub nic_bw: f64,    // Total network bandwidth of a node (inter-node)
+}
+
+impl HardwareSpec {
+    /// Get the theoretical peak bus bandwidth of a single rank
+    ///
+    /// Note: The bus bandwidth is normalized so that it can be compared directly against the slowest link a rank
+    ///       uses, which is the NIC as soon as more than one node is involved.
+    pub fn peak_bus_bw(&self, num_nodes: u64) -> f64 {
+        if num_nodes > 1 {
+            self.nic_bw.min(self.nvlink_bw)
+        } else {
+            self.nvlink_bw
+        }
+    }
+}
+
+/// Get the factor that converts the algorithm bandwidth of a collective to its bus bandwidth
+///
+/// Note: Uses the same formulas as NCCL tests (see `PERFORMANCE.md` in the nccl-tests repo). Returns `None` for
+///       collectives without a known formula.
+///
+/// # Arguments
+/// * `collective` - The name of the collective (e.g., `all-reduce`)
+/// * `num_ranks` - The total number of ranks taking part in the collective
+pub fn bus_bw_factor(collective: &str, num_ranks: u64) -> Option<f64> {
+    if num_ranks == 0 {
+        return None;
+    }
+    let n = num_ranks as f64;
+
+    match collective {
+        "all-reduce" => Some(2.0 * (n - 1.0) / n),
+        "all-gather" | "reduce-scatter" | "all-to-all" | "gather" | "scatter" | "hypercube" => Some((n - 1.0) / n),
+        "broadcast" | "reduce" | "sendrecv" => Some(1.0),
+        _ => None,
+    }
+}
+
+/// Get the bus bandwidth of a collective from its algorithm bandwidth
+pub fn bus_bw_from_alg_bw(collective: &str, num_ranks: u64, alg_bw: f64) -> Option<f64> {
+    Some(alg_bw * bus_bw_factor(collective, num_ranks)?)
+}
+
+/// Get the fraction of the theoretical peak bus bandwidth achieved by a row of NCCL tests output
+///
+/// Note: The bus bandwidth is recomputed from the out-of-place algorithm bandwidth so that the result does not depend
+///       on the formula of the NCCL tests build. Returns `None` if the collective has no known formula.
+///
+/// # Arguments
+/// * `row` - A parsed row of the NCCL output table
+/// * `collective` - The name of the collective the row was collected with
+/// * `num_ranks` - The total number of ranks taking part in the collective
+/// * `num_nodes` - The number of nodes the ranks are spread over
+/// * `spec` - The hardware the row was collected on
+pub fn efficiency(row: &Row, collective: &str, num_ranks: u64, num_nodes: u64, spec: &HardwareSpec) -> Option<f64> {
+    let peak = spec.peak_bus_bw(num_nodes);
+    if peak <= 0.0 {
+        return None;
+    }
+
+    Some(bus_bw_from_alg_bw(collective, num_ranks, row.oop_alg_bw)? / peak)
+}
+
+/// Get the best fraction of the theoretical peak bus bandwidth achieved across the rows of a single experiment
+///
+/// Note: Returns `None` if no rows were parsed or the collective has no known formula
+pub fn peak_efficiency(rows: &[Row], collective: &str, num_ranks: u64, num_nodes: u64, spec: &HardwareSpec) -> Option<f64> {
+    rows.iter()
+        .filter_map(|r| efficiency(r, collective, num_ranks, num_nodes, spec))
+        .reduce(f64::max)
+}
+
+/// Default fraction a bus bandwidth may exceed the theoretical peak by before it is flagged (measurement noise)
+pub const DEFAULT_PEAK_MARGIN: f64 = 0.05;
+
+/// Get the message sizes of rows whose reported bus bandwidth exceeds the theoretical peak of the hardware
+///
+/// Note: No real run can beat the hardware, so such rows almost always mean a parsing bug, a unit mistake, or wrong
+///       link bandwidths in the spec. Both the out-of-place and in-place bus bandwidths are checked, as reported.
+///
+/// # Arguments
+/// * `rows` - The parsed rows of a single experiment
+/// * `num_nodes` - The number of nodes the ranks are spread over
+/// * `spec` - The hardware the rows were collected on
+/// * `margin` - Fraction above the peak that is still tolerated (e.g., `0.05` for 5%)
+pub fn sizes_exceeding_peak(rows: &[Row], num_nodes: u64, spec: &HardwareSpec, margin: f64) -> Vec<u64> {
+    let limit = spec.peak_bus_bw(num_nodes) * (1.0 + margin);
+    if limit <= 0.0 {
+        return Vec::new();
+    }
+
+    rows.iter()
+        .filter(|r| r.oop_bus_bw > limit || r.ip_bus_bw > limit)
+        .map(|r| r.size)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::tests::row;
+
+    const SPEC: HardwareSpec = HardwareSpec { nvlink_bw: 100.0, nic_bw: 50.0 };
+
+    #[test]
+    fn bus_bw_factors_match_nccl_tests() {
+        assert_eq!(bus_bw_factor("all-reduce", 8), Some(1.75));
+        assert_eq!(bus_bw_factor("all-gather", 4), Some(0.75));
+        assert_eq!(bus_bw_factor("broadcast", 8), Some(1.0));
+        assert_eq!(bus_bw_factor("all-reduce", 1), Some(0.0));
+        assert_eq!(bus_bw_factor("all-reduce", 0), None);
+        assert_eq!(bus_bw_factor("unknown", 8), None);
+    }
+
+    #[test]
+    fn peak_is_the_slowest_link() {
+        assert_eq!(SPEC.peak_bus_bw(1), 100.0);
+        assert_eq!(SPEC.peak_bus_bw(2), 50.0);
+    }
+
+    #[test]
+    fn peak_margin_is_tolerated_up_to_and_including_its_bound() {
+        // A limit of 105 on a single node with a 5% margin: at the limit is fine, above it is flagged
+        let rows = [row(1024, 105.0, 100.0), row(2048, 100.0, 105.5), row(4096, 106.0, 90.0)];
+        assert_eq!(sizes_exceeding_peak(&rows, 1, &SPEC, 0.05), vec![2048, 4096]);
+        assert_eq!(sizes_exceeding_peak(&rows, 1, &SPEC, 0.0), vec![1024, 2048, 4096]);
+        // Across nodes, the NIC (50) is the limit
+        assert_eq!(sizes_exceeding_peak(&[row(1024, 53.0, 53.0)], 2, &SPEC, 0.05), vec![1024]);
+    }
+
+    #[test]
+    fn spec_without_bandwidth_flags_nothing() {
+        let spec = HardwareSpec { nvlink_bw: 0.0, nic_bw: 0.0 };
+        assert!(sizes_exceeding_peak(&[row(1024, 1.0, 1.0)], 1, &spec, 0.05).is_empty());
+        assert_eq!(peak_efficiency(&[row(1024, 1.0, 1.0)], "all-reduce", 8, 1, &spec), None);
+    }
+
+    #[test]
+    fn efficiency_uses_the_recomputed_bus_bandwidth() {
+        // 8 ranks of an all-reduce turn 40 GB/s of algorithm bandwidth into 70 GB/s of bus bandwidth
+        let rows = [row(1024, 40.0, 40.0), row(2048, 20.0, 20.0)];
+        assert_eq!(peak_efficiency(&rows, "all-reduce", 8, 1, &SPEC), Some(0.7));
+        assert_eq!(peak_efficiency(&rows, "unknown", 8, 1, &SPEC), None);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use regex::Regex;
+use serde::Serialize;
+
+/// A connection a rank set up with a peer on one channel (from the `NCCL INFO Channel xx/yy : a[d] -> b[d] via ...` lines)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelConnection {
+    pub channel: u64,
+    pub peer: u64,
+    pub direction: Option<String>, // `send` or `receive` (only printed for some transports)
+    pub transport: String, // E.g., `P2P/IPC` or `NET/AWS Libfabric/0/GDRDMA`
+    pub nic: Option<String>, // The NET device used (only for network connections)
+}
+
+/// Where a rank ran and how it is connected to the other ranks
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RankTopology {
+    pub rank: u64,
+    pub host: Option<String>,
+    pub cuda_dev: Option<u64>,
+    pub bus_id: Option<String>,
+    pub trees: Option<String>, // The `Trees` line as printed (e.g., `[0] 1/-1/-1->0->-1 [1] ...`)
+    pub connections: Vec<ChannelConnection>,
+    pub nics: Vec<String>, // The distinct NET devices of `connections`
+}
+
+/// The topology NCCL built for one experiment, as reported by the `NCCL_DEBUG=INFO` init lines of every rank
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TopologySummary {
+    pub num_ranks: Option<u64>,
+    pub rings: BTreeMap<u64, Vec<u64>>, // Channel -> rank order of its ring
+    pub ranks: Vec<RankTopology>,
+}
+
+impl TopologySummary {
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty() && self.ranks.is_empty()
+    }
+}
+
+/// Collects the per-rank topology from NCCL's `INFO` log lines
+///
+/// Note: Lines are tagged with a `host:pid:tid [dev]` prelude. The connection lines name their rank, but the `Trees`
+///       lines don't, so they are tied to a rank through the prelude of the same process's `comm ... rank` line.
+pub struct TopologyParser {
+    prelude_re: Regex,
+    comm_re: Regex,
+    ring_re: Regex,
+    connection_re: Regex,
+    trees_re: Regex,
+    ranks: BTreeMap<u64, RankTopology>,
+    rings: BTreeMap<u64, Vec<u64>>,
+    num_ranks: Option<u64>,
+    process_ranks: BTreeMap<String, u64>, // `host:pid` -> rank
+    process_trees: BTreeMap<String, String>, // `host:pid` -> `Trees` line
+}
+
+impl TopologyParser {
+    pub fn new() -> TopologyParser {
+        TopologyParser {
+            prelude_re: Regex::new(r"^\s*([^\s:]+):(\d+):\d+ \[\d+\] NCCL INFO (.*)$").unwrap(),
+            comm_re: Regex::new(r"comm \S+ rank (\d+) n[Rr]anks (\d+)(?:.*?cudaDev (\d+))?(?:.*?busId (\S+))?").unwrap(),
+            ring_re: Regex::new(r"^Channel (\d+)/\d+ :((?:\s+\d+)+)\s*$").unwrap(),
+            connection_re: Regex::new(r"^Channel (\d+)/\d+ : (\d+)\[\w+\] -> (\d+)\[\w+\](?: \[(send|receive)\])? via (.+?)\s*$").unwrap(),
+            trees_re: Regex::new(r"^Trees (.*?)\s*$").unwrap(),
+            ranks: BTreeMap::new(),
+            rings: BTreeMap::new(),
+            num_ranks: None,
+            process_ranks: BTreeMap::new(),
+            process_trees: BTreeMap::new(),
+        }
+    }
+
+    fn rank(&mut self, rank: u64) -> &mut RankTopology {
+        self.ranks.entry(rank).or_insert_with(|| RankTopology { rank, ..Default::default() })
+    }
+
+    /// Pick up the topology in a line of NCCL output (other lines are ignored)
+    pub fn parse_line(&mut self, line: &str) {
+        let (host, process, message) = match self.prelude_re.captures(line) {
+            Some(c) => (c[1].to_string(), format!("{}:{}", &c[1], &c[2]), c[3].to_string()),
+            None => return,
+        };
+
+        if let Some(c) = self.comm_re.captures(message.as_str()) {
+            let rank = c[1].parse::<u64>().unwrap_or_default();
+            self.num_ranks = c[2].parse::<u64>().ok();
+            self.process_ranks.insert(process, rank);
+            let cuda_dev = c.get(3).and_then(|m| m.as_str().parse::<u64>().ok());
+            let bus_id = c.get(4).map(|m| m.as_str().to_string());
+            let entry = self.rank(rank);
+            entry.host = Some(host);
+            entry.cuda_dev = cuda_dev.or(entry.cuda_dev);
+            entry.bus_id = bus_id.or(entry.bus_id.take());
+        } else if let Some(c) = self.connection_re.captures(message.as_str()) {
+            // Receiving ranks print their connections as `peer -> rank [receive]`, all others as `rank -> peer`
+            let direction = c.get(4).map(|m| m.as_str().to_string());
+            let (rank, peer) = match direction.as_deref() {
+                Some("receive") => (&c[3], &c[2]),
+                _ => (&c[2], &c[3]),
+            };
+            let (rank, peer) = (rank.parse::<u64>().unwrap_or_default(), peer.parse::<u64>().unwrap_or_default());
+            let transport = c[5].to_string();
+            let connection = ChannelConnection {
+                channel: c[1].parse::<u64>().unwrap_or_default(),
+                peer,
+                direction,
+                nic: net_device(transport.as_str()),
+                transport,
+            };
+            let entry = self.rank(rank);
+            entry.host.get_or_insert(host);
+            if let Some(nic) = &connection.nic {
+                if !entry.nics.contains(nic) {
+                    entry.nics.push(nic.clone());
+                }
+            }
+            entry.connections.push(connection);
+        } else if let Some(c) = self.ring_re.captures(message.as_str()) {
+            // Every rank prints the same ring order, so keep the first one
+            let order = c[2].split_whitespace().filter_map(|r| r.parse::<u64>().ok()).collect::<Vec<u64>>();
+            self.rings.entry(c[1].parse::<u64>().unwrap_or_default()).or_insert(order);
+        } else if let Some(c) = self.trees_re.captures(message.as_str()) {
+            self.process_trees.insert(process, c[1].to_string());
+        }
+    }
+
+    /// Get the summary of everything parsed so far
+    pub fn finish(mut self) -> TopologySummary {
+        for (process, trees) in std::mem::take(&mut self.process_trees) {
+            if let Some(&rank) = self.process_ranks.get(&process) {
+                self.rank(rank).trees = Some(trees);
+            }
+        }
+
+        TopologySummary {
+            num_ranks: self.num_ranks,
+            rings: self.rings,
+            ranks: self.ranks.into_values().collect(),
+        }
+    }
+}
+
+/// Get the NET device of a connection's transport (e.g., `0` of `NET/AWS Libfabric/0/GDRDMA`)
+fn net_device(transport: &str) -> Option<String> {
+    let rest = transport.strip_prefix("NET/")?;
+    rest.split('/').nth(1).map(|d| d.to_string())
+}
+
+/// Read the topology NCCL reported in an experiment's log
+pub fn parse_topology_log(path: &Path) -> Result<TopologySummary, Box<dyn std::error::Error>> {
+    let log = std::fs::read_to_string(path)?;
+    let mut parser = TopologyParser::new();
+    for line in log.lines() {
+        parser.parse_line(line);
+    }
+
+    Ok(parser.finish())
+}
+
+/// The network NCCL reported using for an experiment (from its `NCCL_DEBUG=INFO` lines)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkReport {
+    pub network: Option<String>, // From `NCCL INFO Using network ...` (e.g., `AWS Libfabric` or `Socket`)
+    pub ofi_provider: Option<String>, // From `NCCL INFO NET/OFI Selected Provider is ...` (e.g., `efa` or `tcp`)
+}
+
+impl NetworkReport {
+    /// Get why the run did not use EFA, if the report shows it fell back to another transport
+    ///
+    /// Note: Returns `None` if NCCL did not report a network (e.g., `NCCL_DEBUG` is not `INFO`, or a single-node run)
+    pub fn efa_fallback(&self) -> Option<String> {
+        match (&self.network, &self.ofi_provider) {
+            (_, Some(provider)) if provider != "efa" => Some(format!("the OFI plugin selected the '{}' provider instead of 'efa'", provider)),
+            (Some(network), None) if !network.contains("Libfabric") && !network.contains("OFI") => {
+                Some(format!("NCCL used the '{}' network instead of the OFI plugin", network))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Read the network NCCL reported in an experiment's log
+pub fn parse_network_log(path: &Path) -> Result<NetworkReport, Box<dyn std::error::Error>> {
+    let network_re = Regex::new(r"NCCL INFO Using network (.+?)\s*$").unwrap();
+    let provider_re = Regex::new(r"NCCL INFO NET/OFI Selected [Pp]rovider is (\S+)").unwrap();
+
+    let mut report = NetworkReport::default();
+    for line in std::fs::read_to_string(path)?.lines() {
+        if let Some(c) = network_re.captures(line) {
+            report.network.get_or_insert_with(|| c[1].to_string());
+        } else if let Some(c) = provider_re.captures(line) {
+            report.ofi_provider.get_or_insert_with(|| c[1].trim_end_matches(',').to_string());
+        }
+    }
+
+    Ok(report)
+}
+
+/// Write a topology summary as JSON
+pub fn write_topology_json(summary: &TopologySummary, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(path, serde_json::to_string_pretty(summary)?)?;
+
+    Ok(())
+}
@@ -1,22 +1,81 @@
 use std::{fmt, path::{Path, PathBuf}};
 use termion::color;
+use serde::{Deserialize, Serialize};
+
+use crate::launcher::{NetPlugin, NsysProfile, ProcessBinding};
+use crate::stats::Summary;
 
 /// Struct to describe a table row from the NCCL output
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Row {
     pub size: u64,
     pub count: u64,
     pub dtype: String,
-    pub redop: String,
+    pub redop: Option<String>, // `None` for non-reducing collectives (printed as `none` or left blank)
     pub root: i64,
     pub oop_time: f64,
     pub oop_alg_bw: f64,
     pub oop_bus_bw: f64,
     pub oop_num_wrong: String, // Sometimes is N/A, so can't use u64
+    pub oop_wrong: WrongValues, // `oop_num_wrong` interpreted by the column's header
     pub ip_time: f64,
     pub ip_alg_bw: f64,
     pub ip_bus_bw: f64,
     pub ip_num_wrong: String, // Sometimes is N/A, so can't use u64
+    pub ip_wrong: WrongValues, // `ip_num_wrong` interpreted by the column's header
+    pub table: u64, // Index of the table this row came from (nccl-tests prints one table per datatype)
+}
+
+/// What the correctness column of a table row holds, which depends on the nccl-tests build and flags
+///
+/// Note: Most builds print a `#wrong` count, but some print an `error` magnitude (e.g., `1e-07`) instead. Only a count
+///       says whether the results are wrong; a small non-zero magnitude is normal for floating point reductions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrongValues {
+    Count(u64), // Number of wrong elements (`#wrong` column)
+    Magnitude(f64), // Largest deviation from the expected values (`error` column)
+    Unchecked, // `N/A` (e.g., with `--check 0`) or unparseable
+}
+
+/// The kind of correctness column a table's header announces (see `WrongValues`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrongColumn {
+    Count,
+    Magnitude,
+}
+
+impl WrongValues {
+    /// Interpret a correctness cell
+    ///
+    /// Note: Without a header (`column` is `None`), integers are taken as counts and anything else that parses as a
+    ///       number (e.g., `1.5e-07`) as a magnitude.
+    pub fn parse(cell: &str, column: Option<WrongColumn>) -> WrongValues {
+        match column {
+            Some(WrongColumn::Count) => cell.parse::<u64>().map(WrongValues::Count).unwrap_or(WrongValues::Unchecked),
+            Some(WrongColumn::Magnitude) => cell.parse::<f64>().map(WrongValues::Magnitude).unwrap_or(WrongValues::Unchecked),
+            None => match (cell.parse::<u64>(), cell.parse::<f64>()) {
+                (Ok(count), _) => WrongValues::Count(count),
+                (_, Ok(magnitude)) => WrongValues::Magnitude(magnitude),
+                _ => WrongValues::Unchecked,
+            },
+        }
+    }
+
+    /// Get the number of wrong elements (`None` unless the column is a count)
+    pub fn count(&self) -> Option<u64> {
+        match self {
+            WrongValues::Count(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the error magnitude (`None` unless the column is a magnitude)
+    pub fn magnitude(&self) -> Option<f64> {
+        match self {
+            WrongValues::Magnitude(v) => Some(*v),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,7 +90,7 @@ pub struct Permutation {
 }
 
 /// Struct that describes a set of parameters to run MSCCL with
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MscclExperimentParams {
     // Environment Params
     pub cuda_path: String,
@@ -39,15 +98,26 @@ pub struct MscclExperimentParams {
     pub aws_ofi_nccl_path: Option<String>,
     pub openmpi_path: String,
     pub msccl_path: String,
+    pub ld_library_path_order: Vec<String>, // Priority of the above paths in the LD_LIBRARY_PATH
+    pub passthrough_env_vars: Vec<String>, // Names of variables forwarded from the harness's own environment
+    #[serde(default)]
+    pub cuda_visible_devices: Option<String>, // Set as `CUDA_VISIBLE_DEVICES` for every rank (leave unset to see all GPUs)
 
     // Exe params
     pub executable: PathBuf,
+    #[serde(default)]
+    pub nc_build: Option<String>, // Name of the NCCL tests build `executable` comes from (adds a `_b` token to file names)
+    pub nsys_profile: Option<NsysProfile>, // Only set in profile mode
+    #[serde(default, skip_serializing_if = "FilenameFormat::is_default")]
+    pub output_filename_format: FilenameFormat, // How the tokens of this config's output file names are joined
 
     // MSCCL Params
     pub algorithm: String,
     pub ms_xml_file: PathBuf,
     pub ms_channels: u64,
     pub ms_chunks: u64,
+    pub ms_gen_xml: bool, // Set `GENMSCCLXML=1` even though `ms_xml_file` is given (see `nccl_env_vars`)
+    pub ms_xml_fallback: bool, // The XML file is missing, so run without MSCCL (`MSCCL_XML_FILES` unset)
     pub gpu_as_node: bool,
     pub num_nodes: u64,
     pub total_gpus: u64,
@@ -55,7 +125,12 @@ pub struct MscclExperimentParams {
 
     // MPI Params
     pub mpi_hostfile_path: PathBuf,
+    #[serde(default)]
+    pub mpi_host_group: Option<String>, // Name of the node group the hostfile lists (see `MPI_HOSTFILES`)
     pub mpi_proc_per_node: u64,
+    #[serde(default)]
+    pub mpi_ppn_override: bool, // Set by a per-permutation override of `mpi_proc_per_node` (adds a `_ppn` token to file names)
+    pub mpi_binding: ProcessBinding, // How ranks are bound to CPUs (`none` leaves placement to the OS)
 
     // NCCL Tests Params
     pub nc_collective: String,
@@ -65,23 +140,37 @@ pub struct MscclExperimentParams {
     pub nc_num_gpus: u64,
     pub nc_min_bytes: String,
     pub nc_max_bytes: String,
-    pub nc_step_factor: String,
+    pub nc_step_factor: Option<String>, // Mutually exclusive with `nc_step_bytes`
+    pub nc_step_bytes: Option<String>,  // Mutually exclusive with `nc_step_factor`
     pub nc_num_iters: u64,
     pub nc_num_warmup_iters: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nc_iter_regimes: Vec<IterRegime>, // Iterations by message size (replace `nc_num_iters`/`nc_num_warmup_iters`)
+    pub nc_timeout: Option<u64>, // Time budget in seconds (`--timeout`; required when `nc_num_iters` is 0)
+    pub nc_root: Option<u64>, // Only set for rooted collectives
+    pub nc_cudagraph: Option<u64>, // Number of CUDA graph launches (requires a recent nccl-tests build)
+    pub extra_test_args: Vec<String>, // Appended verbatim after the structured flags (e.g., for newer nccl-tests options)
 
     // NCCL Env Params
     pub nccl_debug_level: String,
     pub nccl_algo: String,
+    pub nccl_proto: Option<String>, // Leave unset to let NCCL pick
+    pub nccl_min_nchannels: Option<u64>, // Leave unset to let NCCL pick (must be <= `nccl_max_nchannels`)
+    pub nccl_max_nchannels: Option<u64>, // Leave unset to let NCCL pick
+    #[serde(default)]
+    pub nccl_net_plugin: Option<NetPlugin>, // Leave unset to use the plugin in `aws_ofi_nccl_path` (or NCCL's default)
 }
 
 /// Describes the result of an experiment
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ResultDescription {
     Success,
     PartialFailure,
     Failure,
     Skipped,
     Blacklisted,
+    Warmup,
+    NoData, // Ran without errors, but produced no parseable rows
 }
 
 impl fmt::Display for ResultDescription {
@@ -92,12 +181,14 @@ impl fmt::Display for ResultDescription {
             ResultDescription::Failure => write!(f, "Failure"),
             ResultDescription::Skipped => write!(f, "Skipped"),
             ResultDescription::Blacklisted => write!(f, "Blacklisted"),
+            ResultDescription::Warmup => write!(f, "Warmup"),
+            ResultDescription::NoData => write!(f, "No Data"),
         }
     }
 }
 
 /// Struct the basic params and results of an experiment
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestEntry {
     pub collective: String,
     pub op: String,
@@ -109,25 +200,187 @@ pub struct ManifestEntry {
     pub buffer_size_factor: u64,
 
     pub overall_result: ResultDescription,
+    pub flagged_sizes: Vec<u64>, // Message sizes with anomalously low bus bandwidth
+    pub peak_bus_bw: Option<Summary>, // Peak `oop_bus_bw` across the successful repetitions of this config
+    pub efficiency: Option<f64>, // Best fraction of the theoretical peak bus bandwidth (if the hardware is known)
+    #[serde(default)]
+    pub notes: Vec<String>, // Why the result isn't a plain success (e.g., which sanity check it failed)
+    #[serde(default)]
+    pub config_hash: Option<String>, // Hash of the params and XML contents the config ran with (see `config_hash`)
+    #[serde(default)]
+    pub build: Option<String>, // Name of the NCCL tests build the config ran with (see `NCCL_TESTS_BUILDS`)
+    #[serde(default)]
+    pub avg_bus_bw: Option<f64>, // From the `# Avg bus bandwidth` footer of the run (GB/s)
+    #[serde(default)]
+    pub net_plugin: Option<String>, // Name of the NCCL net plugin the config ran with (see `NCCL_NET_PLUGINS`)
+    #[serde(default)]
+    pub host_group: Option<String>, // Name of the node group the config ran on (see `MPI_HOSTFILES`)
+}
+
+impl ManifestEntry {
+    /// Start the manifest entry of an experiment with the given result (the results of the run itself are left empty)
+    pub fn for_experiment(params: &MscclExperimentParams, overall_result: ResultDescription, config_hash: &str) -> ManifestEntry {
+        ManifestEntry {
+            collective: params.nc_collective.clone(),
+            op: params.nc_op.clone(),
+            dtype: params.nc_dtype.clone(),
+            algorithm: params.algorithm.clone(),
+            num_channels: params.ms_channels,
+            num_chunks: params.ms_chunks,
+            num_gpus: params.total_gpus,
+            buffer_size_factor: params.buffer_size,
+            overall_result,
+            flagged_sizes: Vec::new(),
+            peak_bus_bw: None,
+            efficiency: None,
+            notes: Vec::new(),
+            config_hash: Some(config_hash.to_string()),
+            build: params.nc_build.clone(),
+            avg_bus_bw: None,
+            net_plugin: params.nccl_net_plugin.as_ref().map(|p| p.name.clone()),
+            host_group: params.mpi_host_group.clone(),
+        }
+    }
+}
+
+/// Metadata that describes where and from what a sweep came (so that archived results describe themselves)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SweepMetadata {
+    pub run_id: Option<String>,
+    pub git_commit: Option<String>,
+    pub cluster_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl SweepMetadata {
+    /// Get the set fields as `(name, value)` pairs
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("Run ID", &self.run_id),
+            ("Git Commit", &self.git_commit),
+            ("Cluster", &self.cluster_name),
+            ("Notes", &self.notes),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_deref().map(|v| (name, v)))
+        .collect()
+    }
+}
+
+/// The result manifest of a sweep as written to JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub metadata: SweepMetadata,
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// How the tokens of output file names are put together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilenameFormat {
+    pub separator: char, // Joins the tokens (the tokens themselves are unchanged)
+    pub iteration_token: bool, // Whether per-repetition file names end with an `i(iter id)` token
+    #[serde(default)]
+    pub op_token: bool, // Whether file names include an `op(reduction op)` token (set when several ops are swept)
+    #[serde(default)]
+    pub dtype_token: bool, // Whether file names include a `dt(data type)` token (set when several types are swept)
+}
+
+impl Default for FilenameFormat {
+    fn default() -> FilenameFormat {
+        FilenameFormat { separator: '_', iteration_token: true, op_token: false, dtype_token: false }
+    }
+}
+
+impl FilenameFormat {
+    pub fn is_default(&self) -> bool {
+        *self == FilenameFormat::default()
+    }
+}
+
+/// Check that a character can separate the tokens of output file names
+pub fn validate_filename_separator(separator: char) -> Result<(), Box<dyn std::error::Error>> {
+    if separator == '.' || std::path::is_separator(separator) || separator.is_whitespace() || separator.is_control() {
+        return Err(format!("'{}' can't separate the tokens of file names (it has a meaning in paths or is whitespace)", separator.escape_default()).into());
+    }
+
+    Ok(())
 }
 
 /// Get the name of the output file for a set of given MSCCL experiment parameters
 #[inline(always)]
 pub fn exp_params_to_output_filename(params: &MscclExperimentParams, iteration: u64, extension: &str) -> PathBuf {
-    // (collective)_(algorithm)_node(# nodes)_gpu(# gpus)_mcl(# channels)_mck(# chunks)_buf(scl. fac.)_gan(1|0)_i(iter id).(extension)
-    PathBuf::from(format!(
-        "{}_{}_node{}_gpu{}_mcl{}_mck{}_buf{}_gan{}_i{}.{}",
-        params.nc_collective,
-        params.algorithm,
-        params.num_nodes,
-        params.total_gpus,
-        params.ms_channels,
-        params.ms_chunks,
-        params.buffer_size,
-        if params.gpu_as_node { 1 } else { 0 },
-        iteration,
-        extension,
-    ))
+    // (config name)_i(iter id).(extension)
+    let format = &params.output_filename_format;
+    if format.iteration_token {
+        PathBuf::from(format!("{}{}i{}.{}", exp_params_to_config_name(params), format.separator, iteration, extension))
+    } else {
+        PathBuf::from(format!("{}.{}", exp_params_to_config_name(params), extension))
+    }
+}
+
+/// Get the name of a per-config output file (shared by all repetitions) for a set of given MSCCL experiment parameters
+pub fn exp_params_to_config_filename(params: &MscclExperimentParams, extension: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}", exp_params_to_config_name(params), extension))
+}
+
+/// Get the name that identifies a config in output file names
+fn exp_params_to_config_name(params: &MscclExperimentParams) -> String {
+    // (collective)_(algorithm)_node(# nodes)_gpu(# gpus)_mcl(# channels)_mck(# chunks)_buf(scl. fac.)_gan(1|0)[_op(op)][_dt(dtype)][_nt(# threads)][_cg(# graph launches)][_pr(protocol)][_rt(root)][_nch(min)-(max)][_ppn(# procs per node)][_b(build name)]
+    // Note: The op and data type tokens are only added when several ops/data types are swept (see `FilenameFormat`).
+    //       The thread, CUDA graph, protocol, root, NCCL channel, process, and build tokens are only added when set (and not
+    //       the default) so that existing output files keep their names. The tokens are joined by the separator of
+    //       the config's `FilenameFormat` (`_` by default).
+    let mut tokens = vec![
+        params.nc_collective.clone(),
+        params.algorithm.clone(),
+        format!("node{}", params.num_nodes),
+        format!("gpu{}", params.total_gpus),
+        format!("mcl{}", params.ms_channels),
+        format!("mck{}", params.ms_chunks),
+        format!("buf{}", params.buffer_size),
+        format!("gan{}", if params.gpu_as_node { 1 } else { 0 }),
+    ];
+    if params.output_filename_format.op_token {
+        tokens.push(format!("op{}", params.nc_op));
+    }
+    if params.output_filename_format.dtype_token {
+        tokens.push(format!("dt{}", params.nc_dtype));
+    }
+    if params.nc_num_threads != 1 {
+        tokens.push(format!("nt{}", params.nc_num_threads));
+    }
+    if let Some(n) = params.nc_cudagraph {
+        tokens.push(format!("cg{}", n));
+    }
+    if let Some(p) = &params.nccl_proto {
+        tokens.push(format!("pr{}", p.replace(',', "-").replace('^', "no")));
+    }
+    if let Some(r) = params.nc_root {
+        tokens.push(format!("rt{}", r));
+    }
+    match (params.nccl_min_nchannels, params.nccl_max_nchannels) {
+        (None, None) => {}
+        (min, max) => tokens.push(format!(
+            "nch{}-{}",
+            min.map(|v| v.to_string()).unwrap_or_default(),
+            max.map(|v| v.to_string()).unwrap_or_default()
+        )),
+    }
+    if params.mpi_ppn_override {
+        tokens.push(format!("ppn{}", params.mpi_proc_per_node));
+    }
+    if let Some(b) = &params.nc_build {
+        tokens.push(format!("b{}", b));
+    }
+    if let Some(plugin) = &params.nccl_net_plugin {
+        tokens.push(format!("np{}", plugin.name));
+    }
+    if let Some(group) = &params.mpi_host_group {
+        tokens.push(format!("hg{}", group));
+    }
+
+    tokens.join(params.output_filename_format.separator.to_string().as_str())
 }
 
 /// Get the name of the NCCL-tests executable that corresponds to the given collective name.
@@ -156,6 +409,358 @@ pub fn collective_to_test_exe(collective: &str) -> Result<String, Box<dyn std::e
     }
 }
 
+/// Validate a data type name and get the corresponding nccl-tests `--datatype` value.
+///
+/// Note: Names nccl-tests already accepts are passed through unchanged. Common aliases (e.g., `fp16`, `bf16`) are
+///       mapped to their nccl-tests names. `fp8` maps to `f8e4m3`. The half/bfloat16/fp8 types require a recent
+///       nccl-tests build.
+///
+/// # Arguments
+/// * `dtype` - The name of the data type
+///
+/// # Returns
+/// The name of the data type as nccl-tests expects it
+pub fn validate_dtype(dtype: &str) -> Result<String, Box<dyn std::error::Error>> {
+    match dtype {
+        "int8" | "uint8" | "int32" | "uint32" | "int64" | "uint64" | "half" | "float" | "double" | "bfloat16"
+        | "f8e4m3" | "f8e5m2" => Ok(dtype.to_string()),
+        "fp16" | "float16" => Ok("half".to_string()),
+        "bf16" => Ok("bfloat16".to_string()),
+        "fp8" => Ok("f8e4m3".to_string()),
+        "int" => Ok("int32".to_string()),
+        _ => {
+            Err(format!("Unknown data type (not supported by nccl-tests): {}", dtype).into())
+        }
+    }
+}
+
+/// Validate that exactly one of the nccl-tests step factor (`--stepfactor`) or step size (`--stepbytes`) is set.
+pub fn validate_step(step_factor: Option<&str>, step_bytes: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    match (step_factor, step_bytes) {
+        (Some(_), None) | (None, Some(_)) => Ok(()),
+        (Some(f), Some(b)) => Err(format!("Only one of step factor ({}) or step bytes ({}) may be set", f, b).into()),
+        (None, None) => Err("One of step factor or step bytes must be set".into()),
+    }
+}
+
+/// Validate that a time budget (`--timeout`) is set when nccl-tests picks the iteration count itself (`--iters 0`)
+pub fn validate_iters(num_iters: u64, timeout_secs: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+    match (num_iters, timeout_secs) {
+        (0, None) => Err("A timeout must be set when the number of iterations is 0 (auto-tuned by nccl-tests)".into()),
+        (_, Some(0)) => Err("The timeout must be at least 1 second".into()),
+        _ => Ok(()),
+    }
+}
+
+/// Warmup and timed iterations for the message sizes up to a bound (see `split_iter_regimes`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IterRegime {
+    pub max_bytes: u64, // Inclusive upper bound of the message sizes of this regime
+    pub warmup_iters: u64,
+    pub iters: u64,
+}
+
+/// Parse a message size the way nccl-tests does (e.g., `64K`, `16G`; the suffixes are powers of 1024)
+pub fn parse_size_bytes(size: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let size = size.trim();
+    let (number, factor) = match size.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&size[..size.len() - 1], 1u64 << 10),
+        Some('M') => (&size[..size.len() - 1], 1u64 << 20),
+        Some('G') => (&size[..size.len() - 1], 1u64 << 30),
+        _ => (size, 1),
+    };
+    let number = number.parse::<u64>().map_err(|e| format!("Invalid message size '{}': {}", size, e))?;
+
+    number.checked_mul(factor).ok_or_else(|| format!("Message size '{}' is too large", size).into())
+}
+
+/// Parse iteration regimes given as (up to size, warmup iters, timed iters), checking that every regime times at least
+/// one iteration and that the bounds ascend
+pub fn parse_iter_regimes(regimes: &[(&str, u64, u64)]) -> Result<Vec<IterRegime>, Box<dyn std::error::Error>> {
+    let mut parsed: Vec<IterRegime> = Vec::new();
+    for &(max_bytes, warmup_iters, iters) in regimes {
+        let max_bytes = parse_size_bytes(max_bytes)?;
+        if iters == 0 {
+            return Err(format!("The iteration regime up to {} bytes must time at least 1 iteration", max_bytes).into());
+        }
+        if let Some(previous) = parsed.last() {
+            if max_bytes <= previous.max_bytes {
+                return Err(format!(
+                    "The iteration regimes must have ascending bounds, but {} bytes follows {} bytes",
+                    max_bytes, previous.max_bytes
+                ).into());
+            }
+        }
+        parsed.push(IterRegime { max_bytes, warmup_iters, iters });
+    }
+
+    Ok(parsed)
+}
+
+/// nccl-tests' default `--stepbytes` (used if neither a step factor nor a step size is given)
+const NCCL_TESTS_DEFAULT_STEP_BYTES: u64 = 1 << 20;
+
+/// Get the message sizes nccl-tests steps through for an experiment (one table row each)
+///
+/// Note: nccl-tests runs `size = min; size <= max; size = size * factor` (or `size + step_bytes`), so there are
+///       `floor(log_factor(max / min)) + 1` sizes with a step factor and `floor((max - min) / step_bytes) + 1` sizes with
+///       a fixed step. A factor takes precedence over a fixed step, as in nccl-tests. Stepping stops if it can't make
+///       progress (e.g., a factor with a minimum of 0).
+pub fn message_sizes(params: &MscclExperimentParams) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    let (min_bytes, max_bytes) = (parse_size_bytes(&params.nc_min_bytes)?, parse_size_bytes(&params.nc_max_bytes)?);
+    let step_bytes = match &params.nc_step_bytes {
+        Some(step) => parse_size_bytes(step)?,
+        None => NCCL_TESTS_DEFAULT_STEP_BYTES,
+    };
+
+    let mut sizes = Vec::new();
+    let mut size = min_bytes;
+    while size <= max_bytes {
+        sizes.push(size);
+        size = match &params.nc_step_factor {
+            Some(factor) => match factor.parse::<u64>() {
+                Ok(factor) if factor > 1 && size > 0 => size.saturating_mul(factor),
+                _ => break,
+            },
+            None if step_bytes > 0 => size.saturating_add(step_bytes),
+            None => break,
+        };
+        if size == u64::MAX {
+            break;
+        }
+    }
+
+    Ok(sizes)
+}
+
+/// Split the message size range of an experiment into one run per iteration regime
+///
+/// Note: The message sizes are the ones nccl-tests would step through for the whole range, so the runs together cover
+///       exactly the same sizes. Sizes above the last regime's bound use the last regime. Regimes without any sizes are
+///       left out.
+///
+/// # Returns
+/// The params of each run (with the regime's size range and iterations, and no regimes of their own)
+pub fn split_iter_regimes(params: &MscclExperimentParams) -> Result<Vec<MscclExperimentParams>, Box<dyn std::error::Error>> {
+    let sizes = message_sizes(params)?;
+
+    let mut runs = Vec::new();
+    let mut lower = 0;
+    for (i, regime) in params.nc_iter_regimes.iter().enumerate() {
+        let upper = if i == params.nc_iter_regimes.len() - 1 { u64::MAX } else { regime.max_bytes };
+        let regime_sizes = sizes.iter().filter(|&&s| s >= lower && s <= upper).collect::<Vec<&u64>>();
+        lower = upper.saturating_add(1);
+        let (first, last) = match (regime_sizes.first(), regime_sizes.last()) {
+            (Some(first), Some(last)) => (**first, **last),
+            _ => continue,
+        };
+
+        let mut run = params.clone();
+        run.nc_min_bytes = first.to_string();
+        run.nc_max_bytes = last.to_string();
+        run.nc_num_warmup_iters = regime.warmup_iters;
+        run.nc_num_iters = regime.iters;
+        run.nc_iter_regimes = Vec::new();
+        runs.push(run);
+    }
+
+    Ok(runs)
+}
+
+/// Validate a reduction op name against the ops nccl-tests accepts (`sum`, `prod`, `min`, `max`, `avg`)
+pub fn validate_op(op: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match op {
+        "sum" | "prod" | "min" | "max" | "avg" => Ok(()),
+        _ => Err(format!("Unknown reduction op '{}' (expected one of: sum, prod, min, max, avg)", op).into()),
+    }
+}
+
+/// Validate an `NCCL_PROTO` value against the known protocols (`Simple`, `LL`, `LL128`).
+///
+/// Note: Like NCCL, accepts comma-separated lists, a leading `^` to exclude protocols, and any capitalization.
+pub fn validate_nccl_proto(proto: &str) -> Result<(), Box<dyn std::error::Error>> {
+    for name in proto.trim_start_matches('^').split(',') {
+        match name.to_lowercase().as_str() {
+            "simple" | "ll" | "ll128" => {}
+            _ => {
+                return Err(format!("Unknown NCCL protocol '{}' in NCCL_PROTO: {}", name, proto).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a pair of `NCCL_MIN_NCHANNELS`/`NCCL_MAX_NCHANNELS` values (either may be unset)
+pub fn validate_nchannels(min: Option<u64>, max: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+    if min == Some(0) || max == Some(0) {
+        return Err("NCCL_MIN_NCHANNELS and NCCL_MAX_NCHANNELS must be at least 1".into());
+    }
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(format!("NCCL_MIN_NCHANNELS ({}) must not be greater than NCCL_MAX_NCHANNELS ({})", min, max).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a `CUDA_VISIBLE_DEVICES` list (comma-separated indices or UUIDs, e.g., `0,1` or `GPU-8a1f...,GPU-77c2...`)
+pub fn parse_visible_devices(devices: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let list = devices.split(',').map(|d| d.trim().to_string()).collect::<Vec<String>>();
+    for (i, device) in list.iter().enumerate() {
+        if device.is_empty() {
+            return Err(format!("Empty entry in CUDA_VISIBLE_DEVICES list '{}'", devices).into());
+        }
+        if device.parse::<u64>().is_err() && !device.starts_with("GPU-") && !device.starts_with("MIG-") {
+            return Err(format!("Invalid device '{}' in CUDA_VISIBLE_DEVICES list '{}' (expected an index or a GPU-/MIG- UUID)", device, devices).into());
+        }
+        if list[..i].contains(device) {
+            return Err(format!("Device '{}' is listed more than once in CUDA_VISIBLE_DEVICES list '{}'", device, devices).into());
+        }
+    }
+
+    Ok(list)
+}
+
+/// Check that a `CUDA_VISIBLE_DEVICES` list has a device for every GPU the ranks on a node drive
+///
+/// Note: nccl-tests picks the devices of each process by its local rank (`localRank * nThreads * nGpus + i`), so every
+///       node needs `mpi_proc_per_node * nc_num_threads * nc_num_gpus` visible devices. Extra devices are allowed (they
+///       just go unused).
+///
+/// # Returns
+/// The number of devices that go unused
+pub fn check_visible_devices(devices: &[String], params: &MscclExperimentParams) -> Result<u64, Box<dyn std::error::Error>> {
+    let needed = params.mpi_proc_per_node * params.nc_num_threads * params.nc_num_gpus;
+    let visible = devices.len() as u64;
+    if visible < needed {
+        return Err(format!(
+            "CUDA_VISIBLE_DEVICES lists {} device(s) ({}), but {} process(es) per node with {} thread(s) of {} GPU(s) each need {}",
+            visible,
+            devices.join(","),
+            params.mpi_proc_per_node,
+            params.nc_num_threads,
+            params.nc_num_gpus,
+            needed
+        ).into());
+    }
+
+    Ok(visible - needed)
+}
+
+/// Get the number of GPUs each MPI process should drive (i.e., the nccl-tests `--ngpus` value).
+///
+/// # Arguments
+/// * `gpus_per_node` - The number of GPUs on each node
+/// * `mpi_proc_per_node` - The number of MPI processes to launch on each node
+///
+/// # Returns
+/// The number of GPUs per process, such that `mpi_proc_per_node * gpus_per_proc == gpus_per_node`
+pub fn gpus_per_proc(gpus_per_node: u64, mpi_proc_per_node: u64) -> Result<u64, Box<dyn std::error::Error>> {
+    if mpi_proc_per_node == 0 || !gpus_per_node.is_multiple_of(mpi_proc_per_node) {
+        return Err(format!(
+            "Cannot evenly split {} GPUs per node across {} MPI processes per node",
+            gpus_per_node, mpi_proc_per_node
+        ).into());
+    }
+
+    Ok(gpus_per_node / mpi_proc_per_node)
+}
+
+/// Check that a hostfile has enough hosts and slots for the topology
+///
+/// Note: Hosts that don't list their slots are assumed to have enough.
+pub fn check_hostfile_topology(hosts: &[HostfileEntry], num_nodes: u64, proc_per_node: u64) -> Result<(), Box<dyn std::error::Error>> {
+    if (hosts.len() as u64) < num_nodes {
+        return Err(format!("The hostfile only lists {} host(s), but NUM_NODES is {}", hosts.len(), num_nodes).into());
+    }
+    for entry in hosts {
+        if let Some(slots) = entry.slots {
+            if slots < proc_per_node {
+                return Err(format!(
+                    "Host '{}' only has {} slot(s), but {} MPI processes per node are needed",
+                    entry.host, slots, proc_per_node
+                ).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a per-permutation override of the number of MPI processes per node
+///
+/// Note: Oversubscribing (more processes than GPUs) is allowed, but undersubscribing must split the GPUs evenly. Every
+///       host in the hostfile that lists its slots must have one for every process.
+pub fn validate_proc_per_node_override(
+    gpus_per_node: u64,
+    proc_per_node: u64,
+    hosts: &[HostfileEntry],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if proc_per_node <= gpus_per_node {
+        gpus_per_proc(gpus_per_node, proc_per_node)?;
+    }
+
+    for entry in hosts {
+        if let Some(slots) = entry.slots {
+            if slots < proc_per_node {
+                return Err(format!(
+                    "Host '{}' only has {} slot(s), but {} MPI processes per node are requested by an override",
+                    entry.host, slots, proc_per_node
+                ).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether the given collective reduces its data (i.e., is affected by the nccl-tests `--op` flag)
+pub fn is_reducing_collective(collective: &str) -> bool {
+    matches!(collective, "all-reduce" | "reduce" | "reduce-scatter")
+}
+
+/// Check whether the given collective has a root rank (i.e., accepts the nccl-tests `--root` flag)
+pub fn is_rooted_collective(collective: &str) -> bool {
+    matches!(collective, "broadcast" | "reduce" | "gather" | "scatter")
+}
+
+/// The order the experiments of a sweep are run in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ExperimentOrder {
+    Generated, // The order of the permutation generation (grouped by collective, then op, dtype, algorithm, ...)
+    ByXml, // Experiments sharing an MSCCL XML file run back-to-back
+}
+
+impl ExperimentOrder {
+    /// Get an order from its name (`generated` or `xml`)
+    pub fn from_name(name: &str) -> Result<ExperimentOrder, Box<dyn std::error::Error>> {
+        match name.to_lowercase().as_str() {
+            "generated" => Ok(ExperimentOrder::Generated),
+            "xml" => Ok(ExperimentOrder::ByXml),
+            _ => Err(format!("Unknown experiment order: {} (expected one of: generated, xml)", name).into()),
+        }
+    }
+}
+
+/// Reorder the experiment configs of a sweep
+///
+/// Note: Deterministic, so that permutation indices (`--start-index`, `--profile`) stay stable across runs. With
+///       `ByXml`, the XML files are ordered by their first use and the configs of each file keep their generated order.
+pub fn order_experiments(configs: &mut [MscclExperimentParams], order: ExperimentOrder) {
+    match order {
+        ExperimentOrder::Generated => {}
+        ExperimentOrder::ByXml => {
+            let mut first_use: std::collections::HashMap<PathBuf, usize> = std::collections::HashMap::new();
+            for (i, config) in configs.iter().enumerate() {
+                first_use.entry(config.ms_xml_file.clone()).or_insert(i);
+            }
+            configs.sort_by_key(|c| first_use[&c.ms_xml_file]); // Stable, so the generated order is kept within a file
+        }
+    }
+}
+
 /// Pretty print the given vector of MSCCL experiment parameters as a table.
 ///
 /// # Arguments
@@ -217,43 +822,352 @@ pub fn pretty_print_configs(configs: &Vec<MscclExperimentParams>, color: bool) {
     }
 }
 
+/// Column names of the result manifest table
+pub const MANIFEST_COLUMNS: [&str; 16] = [
+    "Collective", "Op", "DType", "Algorithm", "Num Channels", "Num Chunks", "Num GPUs", "Buffer Size Factor", "Build", "Net Plugin", "Host Group",
+    "Overall Result", "Flagged Sizes", "Peak Bus BW (min/median/max)", "% of Theoretical Peak", "Notes",
+];
+
+/// Column names of the experiment config table
+pub const CONFIG_COLUMNS: [&str; 6] = ["Collective", "Op", "DType", "Algorithm", "Channels", "Chunks"];
+
+/// Get the cells of a result manifest table row (matches `MANIFEST_COLUMNS`)
+pub fn manifest_entry_cells(entry: &ManifestEntry) -> Vec<String> {
+    let result_pretty = match entry.overall_result {
+        ResultDescription::Success => format!("✅ {}", entry.overall_result),
+        ResultDescription::PartialFailure => format!("⛓️‍💥 {}", entry.overall_result),
+        ResultDescription::Failure => format!("❌ {}", entry.overall_result),
+        ResultDescription::Skipped => format!("⏭️ {}", entry.overall_result),
+        ResultDescription::Blacklisted => format!("💔 {}", entry.overall_result),
+        ResultDescription::Warmup => format!("🔥 {}", entry.overall_result),
+        ResultDescription::NoData => format!("📭 {}", entry.overall_result),
+    };
+
+    vec![
+        entry.collective.clone(),
+        entry.op.clone(),
+        entry.dtype.clone(),
+        entry.algorithm.clone(),
+        entry.num_channels.to_string(),
+        entry.num_chunks.to_string(),
+        entry.num_gpus.to_string(),
+        entry.buffer_size_factor.to_string(),
+        entry.build.clone().unwrap_or_default(),
+        entry.net_plugin.clone().unwrap_or_default(),
+        entry.host_group.clone().unwrap_or_default(),
+        result_pretty,
+        entry.flagged_sizes.iter().map(|s| s.to_string()).collect::<Vec<String>>().join(", "),
+        match &entry.peak_bus_bw {
+            Some(v) => format!("{:.2}/{:.2}/{:.2}", v.min, v.median, v.max),
+            None => "N/A".to_string(),
+        },
+        match entry.efficiency {
+            Some(v) => format!("{:.1}%", v * 100.0),
+            None => "N/A".to_string(),
+        },
+        entry.notes.join("; "),
+    ]
+}
+
+/// Get the cells of an experiment config table row (matches `CONFIG_COLUMNS`)
+pub fn config_cells(config: &MscclExperimentParams) -> Vec<String> {
+    vec![
+        config.nc_collective.clone(),
+        config.nc_op.clone(),
+        config.nc_dtype.clone(),
+        config.algorithm.clone(),
+        config.ms_channels.to_string(),
+        config.ms_chunks.to_string(),
+    ]
+}
+
 /// Pretty print the given vector of MSCCL experiment results as a table
 /// 
 /// # Arguments
 /// * `entries` - A vector of MSCCL experiment results to pretty print
-pub fn pretty_print_result_manifest(entries: &Vec<ManifestEntry>) {
+pub fn pretty_print_result_manifest(entries: &[ManifestEntry]) {
     let mut table = prettytable::Table::new();
 
     // Add a title row
-    table.add_row(row!["Collective", "Op", "DType", "Algorithm", "Num Channels", "Num Chunks", "Num GPUs", "Buffer Size Factor", "Overall Result"]);
+    table.add_row(prettytable::Row::new(MANIFEST_COLUMNS.iter().map(|c| prettytable::Cell::new(c)).collect()));
 
     // Iterate over entries and add each as a row
     for entry in entries {
-        let result_pretty = match entry.overall_result {
-            ResultDescription::Success => format!("✅ {}", entry.overall_result),
-            ResultDescription::PartialFailure => format!("⛓️‍💥 {}", entry.overall_result),
-            ResultDescription::Failure => format!("❌ {}", entry.overall_result),
-            ResultDescription::Skipped => format!("⏭️ {}", entry.overall_result),
-            ResultDescription::Blacklisted => format!("💔 {}", entry.overall_result),
-        };
-
-        table.add_row(prettytable::Row::new(vec![
-            prettytable::Cell::new(&entry.collective),
-            prettytable::Cell::new(&entry.op),
-            prettytable::Cell::new(&entry.dtype),
-            prettytable::Cell::new(&entry.algorithm),
-            prettytable::Cell::new(&entry.num_channels.to_string()),
-            prettytable::Cell::new(&entry.num_chunks.to_string()),
-            prettytable::Cell::new(&entry.num_gpus.to_string()),
-            prettytable::Cell::new(&entry.buffer_size_factor.to_string()),
-            prettytable::Cell::new(result_pretty.as_str()),
-        ]));
+        table.add_row(prettytable::Row::new(
+            manifest_entry_cells(entry).iter().map(|c| prettytable::Cell::new(c)).collect()
+        ));
     }
 
     // Print the table
     table.printstd();
 }
 
+/// Render a GitHub-flavored Markdown table
+fn markdown_table(columns: &[&str], rows: &[Vec<String>]) -> String {
+    let escape = |cell: &str| cell.replace('|', "\\|");
+
+    let mut table = format!("| {} |\n", columns.iter().map(|c| escape(c)).collect::<Vec<String>>().join(" | "));
+    table.push_str(format!("|{}\n", " --- |".repeat(columns.len())).as_str());
+    for row in rows {
+        table.push_str(format!("| {} |\n", row.iter().map(|c| escape(c)).collect::<Vec<String>>().join(" | ")).as_str());
+    }
+
+    table
+}
+
+/// Write a self-contained Markdown report of the sweep (metadata, configs, result manifest, and pass/fail counts)
+///
+/// # Arguments
+/// * `metadata` - The metadata of the sweep
+/// * `configs` - The MSCCL experiment parameters of the sweep
+/// * `entries` - The MSCCL experiment results of the sweep
+/// * `path` - Where to write the report
+pub fn write_markdown_report(
+    metadata: &SweepMetadata,
+    configs: &[MscclExperimentParams],
+    entries: &[ManifestEntry],
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut report = String::from("# NCCL Harness Sweep Report\n\n");
+
+    // Where the sweep came from
+    for (name, value) in metadata.fields() {
+        report.push_str(format!("- **{}:** {}\n", name, value).as_str());
+    }
+    if !metadata.fields().is_empty() {
+        report.push('\n');
+    }
+
+    // Summary of pass/fail counts
+    report.push_str("## Summary\n\n");
+    let results = [
+        ResultDescription::Success,
+        ResultDescription::PartialFailure,
+        ResultDescription::Failure,
+        ResultDescription::Skipped,
+        ResultDescription::Blacklisted,
+        ResultDescription::Warmup,
+        ResultDescription::NoData,
+    ];
+    let counts = results
+        .iter()
+        .map(|result| {
+            let count = entries.iter().filter(|e| e.overall_result == *result).count();
+            vec![result.to_string(), count.to_string()]
+        })
+        .collect::<Vec<Vec<String>>>();
+    report.push_str(markdown_table(&["Result", "Count"], &counts).as_str());
+
+    // Configs
+    report.push_str(format!("\n## Configs ({})\n\n", configs.len()).as_str());
+    report.push_str(markdown_table(&CONFIG_COLUMNS, &configs.iter().map(config_cells).collect::<Vec<Vec<String>>>()).as_str());
+
+    // Result manifest
+    report.push_str(format!("\n## Results ({})\n\n", entries.len()).as_str());
+    report.push_str(markdown_table(&MANIFEST_COLUMNS, &entries.iter().map(manifest_entry_cells).collect::<Vec<Vec<String>>>()).as_str());
+
+    std::fs::write(path, report)?;
+
+    Ok(())
+}
+
+/// Check if a result manifest entry was recorded for the given experiment parameters
+///
+/// Note: Only the params stored in the manifest are compared, so permutations that differ in anything else (e.g., the
+///       number of threads or the NCCL protocol) all match the same entry.
+pub fn manifest_entry_matches(entry: &ManifestEntry, params: &MscclExperimentParams) -> bool {
+    entry.collective == params.nc_collective
+        && entry.op == params.nc_op
+        && entry.dtype == params.nc_dtype
+        && entry.algorithm == params.algorithm
+        && entry.num_channels == params.ms_channels
+        && entry.num_chunks == params.ms_chunks
+        && entry.num_gpus == params.total_gpus
+        && entry.buffer_size_factor == params.buffer_size
+        && entry.build == params.nc_build
+        && entry.net_plugin == params.nccl_net_plugin.as_ref().map(|p| p.name.clone())
+        && entry.host_group == params.mpi_host_group
+}
+
+/// Merge the entries of a rerun into the manifest of the prior sweep (the prior entries of the rerun configs are replaced)
+pub fn merge_rerun_manifest(
+    prior: &[ManifestEntry],
+    rerun_configs: &[MscclExperimentParams],
+    rerun_entries: &[ManifestEntry],
+) -> Vec<ManifestEntry> {
+    prior
+        .iter()
+        .filter(|e| !rerun_configs.iter().any(|p| manifest_entry_matches(e, p)))
+        .chain(rerun_entries.iter())
+        .cloned()
+        .collect()
+}
+
+/// Write the result manifest of a sweep as JSON (so that it can be loaded again, e.g., by `rerun-failed`)
+pub fn write_manifest_json(manifest: &Manifest, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(path, serde_json::to_string_pretty(manifest)?)?;
+
+    Ok(())
+}
+
+/// Write the params of a single experiment as JSON (e.g., to attach a failing config to a bug report)
+pub fn write_params_json(params: &MscclExperimentParams, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(path, serde_json::to_string_pretty(params)?)?;
+
+    Ok(())
+}
+
+/// Read the params of a single experiment written by `write_params_json` (for `replay`)
+pub fn read_params_json(path: &Path) -> Result<MscclExperimentParams, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read experiment params at {}: {}", path.display(), e))?;
+
+    Ok(serde_json::from_str::<MscclExperimentParams>(contents.as_str())?)
+}
+
+/// Read a result manifest written by `write_manifest_json`
+///
+/// Note: Also accepts manifests written before metadata was added (a bare list of entries)
+pub fn read_manifest_json(path: &Path) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read manifest at {}: {}", path.display(), e))?;
+
+    match serde_json::from_str::<Manifest>(contents.as_str()) {
+        Ok(v) => Ok(v),
+        Err(e) => match serde_json::from_str::<Vec<ManifestEntry>>(contents.as_str()) {
+            Ok(entries) => Ok(Manifest { metadata: SweepMetadata::default(), entries }),
+            Err(_) => Err(e.into()),
+        },
+    }
+}
+
+/// MSCCL chunks and channels that XML files exist for, per communication algorithm, as (algorithm, chunks, channels)
+///
+/// Note: Every combination of an algorithm's chunks and channels is swept (at Ly's request). Uncomment (or add) an
+///       algorithm's entry to sweep it.
+pub const ALGORITHM_CHUNKS_CHANNELS: [(&str, &[u64], &[u64]); 2] = [
+    ("binary-tree", &[1, 2, 4, 8, 16], &[4, 8, 16]),
+    // ("binomial-tree", &[8, 16, 32, 64, 128], &[1, 2]),
+    // ("recursive-doubling-halving", &[8, 16, 32], &[1, 2]),
+    ("ring", &[1, 2], &[4, 8, 16]),
+    // ("double-binary-tree", &[8, 16, 32, 64, 128, 256], &[1, 2]),
+    // ("double-binomial-tree", &[8, 16, 32, 64, 128], &[1, 2]),
+    // ("trinomial-tree", &[8, 16, 32, 64, 128], &[1, 2]),
+    // ("recursive-doubling", &[8, 16, 32], &[1, 2]),
+];
+
+/// MSCCL chunks and channels of an algorithm (every combination of the two is swept)
+pub type ChunksChannels = (&'static [u64], &'static [u64]);
+
+/// Get the MSCCL chunks and channels to sweep for a communication algorithm (see `ALGORITHM_CHUNKS_CHANNELS`)
+///
+/// # Returns
+/// The chunks and the channels (every combination of the two is valid)
+pub fn algorithm_chunks_channels(algorithm: &str) -> Result<ChunksChannels, Box<dyn std::error::Error>> {
+    match ALGORITHM_CHUNKS_CHANNELS.iter().find(|(a, _, _)| *a == algorithm) {
+        Some((_, chunks, channels)) => Ok((chunks, channels)),
+        None => Err(format!(
+            "No chunks/channels defined for communication algorithm '{}' (defined for: {})",
+            algorithm,
+            ALGORITHM_CHUNKS_CHANNELS.iter().map(|(a, _, _)| *a).collect::<Vec<&str>>().join(", ")
+        ).into()),
+    }
+}
+
+/// Upper bound on the channels an MSCCL schedule can use (NCCL's `MAXCHANNELS`)
+pub const MSCCL_MAX_CHANNELS: u64 = 32;
+
+/// A combination of GPUs, MSCCL channels, and MSCCL chunks that is known to give a broken schedule, as
+/// (algorithm (`*` for all), check (total GPUs, channels, chunks), reason)
+pub type FeasibilityRule = (&'static str, fn(u64, u64, u64) -> bool, &'static str);
+
+/// Known-bad combinations of GPUs, MSCCL channels, and MSCCL chunks (see `check_chunks_channels_feasible`)
+///
+/// Note: Starts from the limits behind `ALGORITHM_CHUNKS_CHANNELS`. Add a rule when a combination turns out to always
+///       fail or hang.
+pub const INFEASIBLE_CHUNKS_CHANNELS: [FeasibilityRule; 3] = [
+    ("*", |_, channels, chunks| channels == 0 || chunks == 0, "an MSCCL schedule needs at least one channel and one chunk"),
+    ("*", |total_gpus, _, _| total_gpus < 2, "a collective needs at least 2 GPUs"),
+    ("*", |_, channels, _| channels > MSCCL_MAX_CHANNELS, "more channels than NCCL's MAXCHANNELS (32)"),
+];
+
+/// Check that a combination of GPUs, MSCCL channels, and MSCCL chunks can give a working schedule for an algorithm
+///
+/// # Returns
+/// The reason the combination is infeasible (see `INFEASIBLE_CHUNKS_CHANNELS`)
+pub fn check_chunks_channels_feasible(algorithm: &str, total_gpus: u64, channels: u64, chunks: u64) -> Result<(), String> {
+    match INFEASIBLE_CHUNKS_CHANNELS
+        .iter()
+        .find(|(a, check, _)| (*a == "*" || *a == algorithm) && check(total_gpus, channels, chunks))
+    {
+        Some((_, _, reason)) => Err(format!(
+            "{} with {} GPU(s), {} channel(s), and {} chunk(s) is infeasible: {}",
+            algorithm, total_gpus, channels, chunks, reason
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Communication algorithms that make sense for each collective (see `collective_algorithm_allowed`)
+///
+/// Note: Documents the valid design space, so that uncommenting an algorithm doesn't pair it with collectives it has no
+///       schedule for (e.g., a `ring` broadcast). Collectives that aren't listed allow every algorithm. Entries can be
+///       replaced per collective with `COLLECTIVE_ALGORITHMS` (see `parse_collective_algorithms`).
+pub const COLLECTIVE_ALGORITHMS: [(&str, &[&str]); 5] = [
+    ("all-reduce", &["binary-tree", "binomial-tree", "recursive-doubling", "recursive-halving-doubling", "ring", "trinomial-tree"]),
+    ("all-gather", &["recursive-doubling", "ring"]),
+    ("reduce-scatter", &["recursive-halving-doubling", "ring"]),
+    ("broadcast", &["binary-tree", "binomial-tree", "trinomial-tree"]),
+    ("reduce", &["binary-tree", "binomial-tree", "trinomial-tree"]),
+];
+
+/// The communication algorithms allowed for a collective, as (collective, algorithms (`*` for all))
+pub type CollectiveAlgorithms = (String, Vec<String>);
+
+/// Parse overrides of `COLLECTIVE_ALGORITHMS` given as comma-separated `collective=algorithm|algorithm` (e.g.,
+/// `broadcast=binary-tree|ring,all-to-all=*`)
+pub fn parse_collective_algorithms(overrides: &str) -> Result<Vec<CollectiveAlgorithms>, Box<dyn std::error::Error>> {
+    let mut parsed: Vec<CollectiveAlgorithms> = Vec::new();
+    for entry in overrides.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let (collective, algorithms) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid COLLECTIVE_ALGORITHMS entry '{}', expected 'collective=algorithm|algorithm'", entry))?;
+        let collective = collective.trim();
+        collective_to_test_exe(collective)?;
+        let algorithms = algorithms.split('|').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect::<Vec<String>>();
+        if algorithms.is_empty() {
+            return Err(format!("COLLECTIVE_ALGORITHMS entry '{}' allows no algorithms (use '*' for all)", entry).into());
+        }
+
+        if parsed.iter().any(|(c, _)| c == collective) {
+            return Err(format!("Duplicate COLLECTIVE_ALGORITHMS collective '{}'", collective).into());
+        }
+        parsed.push((collective.to_string(), algorithms));
+    }
+
+    Ok(parsed)
+}
+
+/// Get the table of allowed algorithms: `COLLECTIVE_ALGORITHMS` with the overrides of some of its collectives
+///
+/// Note: An override replaces the collective's entry of the default table (or adds one); the other entries are kept.
+pub fn collective_algorithms_table(overrides: &[CollectiveAlgorithms]) -> Vec<CollectiveAlgorithms> {
+    COLLECTIVE_ALGORITHMS
+        .iter()
+        .filter(|(c, _)| !overrides.iter().any(|(o, _)| o == c))
+        .map(|(c, algorithms)| (c.to_string(), algorithms.iter().map(|a| a.to_string()).collect::<Vec<String>>()))
+        .chain(overrides.iter().cloned())
+        .collect()
+}
+
+/// Check if a communication algorithm makes sense for a collective (collectives without an entry allow every algorithm)
+pub fn collective_algorithm_allowed(table: &[CollectiveAlgorithms], collective: &str, algorithm: &str) -> bool {
+    match table.iter().find(|(c, _)| c == collective) {
+        Some((_, algorithms)) => algorithms.iter().any(|a| a == "*" || a == algorithm),
+        None => true,
+    }
+}
+
 /// Give the (probable) name of the XML file for a given set of experiment parameters
 pub fn params_to_xml(
     collective: &str,
@@ -308,64 +1222,533 @@ pub fn params_to_xml(
     )))
 }
 
-pub fn verify_env() -> Result<(), Box<dyn std::error::Error>> {
-    // Verify environment variables are set and paths are accessible
-    let nccl_home = PathBuf::from(std::env::var("NCCL_HOME").expect("[ERROR] NCCL_HOME not set!"));
-    let cuda_home = PathBuf::from(std::env::var("CUDA_HOME").expect("[ERROR] CUDA_HOME not set!"));
-    let mpi_home = PathBuf::from(std::env::var("MPI_HOME").expect("[ERROR] MPI_HOME not set!"));
-    let nccl_tests_home = PathBuf::from(std::env::var("NCCL_TESTS_HOME").expect("[ERROR] NCCL_TESTS_HOME not set!"));
-    let _ = PathBuf::from(std::env::var("EXPERIMENTS_OUTPUT_DIR").expect("[ERROR] EXPERIMENTS_OUTPUT_DIR not set!"));
-    let mpi_hostfile = PathBuf::from(std::env::var("MPI_HOSTFILE").expect("[ERROR] MPI_HOSTFILE not set!"));
-    let msccl_xmls = PathBuf::from(std::env::var("MSCCL_XMLS").expect("[ERROR] MSCCL_XMLS not set!"));
-    if !nccl_home.exists() {
-        panic!("[ERROR] NCCL_HOME not found at: {}", nccl_home.to_str().unwrap());
+/// Get the value of a numeric token (e.g., `gpu64`) from the name of an XML file made by `params_to_xml`
+///
+/// # Arguments
+/// * `xml_file` - The path of the XML file
+/// * `prefix` - The prefix of the token (e.g., `gpu`, `mcl`, `mck`)
+///
+/// # Returns
+/// The value of the first matching token, or `None` if the name has no such token
+pub fn xml_filename_token(xml_file: &Path, prefix: &str) -> Option<u64> {
+    xml_file
+        .file_stem()?
+        .to_str()?
+        .split('_')
+        .find_map(|token| token.strip_prefix(prefix)?.parse::<u64>().ok())
+}
+
+/// Check that the GPU count in the name of an XML file matches the total number of GPUs of an experiment
+///
+/// Note: Much cheaper than parsing the XML itself, and catches the most common mismatch (overriding the number of
+///       GPUs without regenerating the XMLs). Names without a `gpu` token are not checked.
+pub fn check_xml_gpu_count(xml_file: &Path, total_gpus: u64) -> Result<(), Box<dyn std::error::Error>> {
+    match xml_filename_token(xml_file, "gpu") {
+        Some(xml_gpus) if xml_gpus != total_gpus => Err(format!(
+            "XML file {} is for {} GPUs, but the experiment uses {} GPUs",
+            xml_file.display(), xml_gpus, total_gpus
+        ).into()),
+        _ => Ok(()),
+    }
+}
+
+/// Load a dotenv-style file (`KEY=VALUE` lines) into the process environment
+///
+/// Note: Blank lines, comments, and `export ` prefixes are ignored, and matching quotes around values are removed.
+///       Variables that are already set in the environment take precedence over the file.
+///
+/// # Returns
+/// The names of the variables that were set from the file
+pub fn load_env_file(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read env file at {}: {}", path.display(), e))?;
+
+    let mut loaded = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                return Err(format!("Invalid line {} in env file {} (expected KEY=VALUE): {}", i + 1, path.display(), line).into());
+            }
+        };
+
+        // Remove matching quotes around the value
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+            .unwrap_or(value);
+
+        if std::env::var_os(key).is_none() {
+            std::env::set_var(key, value);
+            loaded.push(key.to_string());
+        }
     }
-    if !cuda_home.exists() {
-        panic!("[ERROR] CUDA_HOME not found at: {}", cuda_home.to_str().unwrap());
+
+    Ok(loaded)
+}
+
+/// Verify that the environment variables are set and that the paths they point to are accessible.
+///
+/// Note: Checks everything rather than stopping at the first problem.
+///
+/// # Returns
+/// A description of every problem found (empty if the environment is OK)
+pub fn verify_env() -> Vec<String> {
+    let mut problems = Vec::new();
+
+    // Verify environment variables are set and paths are accessible
+    let mut existing_paths = std::collections::HashMap::new();
+    for envvar in ["NCCL_HOME", "CUDA_HOME", "MPI_HOME", "NCCL_TESTS_HOME", "EXPERIMENTS_OUTPUT_DIR", "MPI_HOSTFILE", "MSCCL_XMLS"] {
+        let path = match std::env::var(envvar) {
+            Ok(v) => PathBuf::from(v),
+            Err(_) => {
+                problems.push(format!("{} not set!", envvar));
+                continue;
+            }
+        };
+
+        // Note: We don't need the output directory to exist as it will be created if it doesn't exist
+        if envvar != "EXPERIMENTS_OUTPUT_DIR" && !path.exists() {
+            problems.push(format!("{} not found at: {}", envvar, path.to_str().unwrap()));
+            continue;
+        }
+
+        existing_paths.insert(envvar, path);
     }
-    if !mpi_home.exists() {
-        panic!("[ERROR] MPI_HOME not found at: {}", mpi_home.to_str().unwrap());
+
+    // let path = std::env::var("PATH").unwrap();
+    let ld_library_path = std::env::var("LD_LIBRARY_PATH").unwrap_or_default();
+
+    // Verify that the necessary libraries exist and are in the LD_LIBRARY_PATH
+    for (envvar, lib_dir) in [("NCCL_HOME", "lib"), ("CUDA_HOME", "lib64"), ("MPI_HOME", "lib64")] {
+        let lib = match existing_paths.get(envvar) {
+            Some(v) => v.join(lib_dir),
+            None => continue,
+        };
+
+        if !lib.exists() {
+            problems.push(format!("{} lib not found at: {}", envvar, lib.to_str().unwrap()));
+        } else if !ld_library_path.contains(lib.to_str().unwrap()) {
+            problems.push(format!("{} not in LD_LIBRARY_PATH!", lib.to_str().unwrap()));
+        }
     }
-    if !nccl_tests_home.exists() {
-        panic!("[ERROR] NCCL_TESTS_HOME not found at: {}", nccl_tests_home.to_str().unwrap());
+
+    problems
+}
+
+/// Count the GPUs on this node with `nvidia-smi -L` (one line per GPU)
+///
+/// Note: Only probes the node the harness runs on, so it assumes that all nodes of the allocation are alike.
+pub fn probe_gpus_per_node() -> Result<u64, Box<dyn std::error::Error>> {
+    let output = std::process::Command::new("nvidia-smi").arg("-L").output()
+        .map_err(|e| format!("Could not run 'nvidia-smi -L' to count the GPUs: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("'nvidia-smi -L' failed with status: {}", output.status).into());
     }
-    // // Note: We don't need this to be created as it will be created if it doesn't exist
-    // if !experiments_output.exists() {
-    //     panic!("[ERROR] EXPERIMENTS_OUTPUT_DIR not found at: {}", experiments_output.to_str().unwrap());
-    // }
-    if !mpi_hostfile.exists() {
-        panic!("[ERROR] MPI_HOSTFILE not found at: {}", mpi_hostfile.to_str().unwrap());
+
+    let count = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| l.trim_start().starts_with("GPU "))
+        .count() as u64;
+    if count == 0 {
+        return Err("'nvidia-smi -L' did not list any GPUs".into());
     }
-    if !msccl_xmls.exists() {
-        panic!("[ERROR] MSCCL_XMLS not found at: {}", msccl_xmls.to_str().unwrap());
+
+    Ok(count)
+}
+
+/// Describes a single host in an MPI hostfile
+#[derive(Debug, Clone)]
+pub struct HostfileEntry {
+    pub host: String,
+    pub slots: Option<u64>,
+}
+
+impl fmt::Display for HostfileEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.slots {
+            Some(slots) => write!(f, "{} slots={}", self.host, slots),
+            None => write!(f, "{}", self.host),
+        }
     }
+}
 
-    let nccl_lib = nccl_home.join("lib");
-    let cuda_lib = cuda_home.join("lib64");
-    let mpi_lib = mpi_home.join("lib64");
-    if !nccl_lib.exists() {
-        panic!("[ERROR] NCCL lib not found at: {}", nccl_lib.to_str().unwrap());
+/// Split the hosts of a hostfile into disjoint slices of `nodes_per_slice` hosts each and write every slice to its own
+/// hostfile in `dir` (so that repetitions can run in parallel on distinct nodes)
+///
+/// Note: Makes at most `max_slices` slices. Hosts that don't fill a whole slice are left out.
+///
+/// # Returns
+/// The paths of the written hostfiles (one per slice)
+pub fn write_hostfile_slices(
+    hosts: &[HostfileEntry],
+    nodes_per_slice: u64,
+    max_slices: u64,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if nodes_per_slice == 0 {
+        return Err("Hostfile slices need at least one node each".into());
     }
-    if !cuda_lib.exists() {
-        panic!("[ERROR] CUDA lib not found at: {}", cuda_lib.to_str().unwrap());
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)?;
     }
-    if !mpi_lib.exists() {
-        panic!("[ERROR] MPI lib not found at: {}", mpi_lib.to_str().unwrap());
+
+    let mut paths = Vec::new();
+    for (i, slice) in hosts.chunks_exact(nodes_per_slice as usize).take(max_slices as usize).enumerate() {
+        let path = dir.join(format!("hostfile.slice{}", i));
+        let contents = slice.iter().map(|h| format!("{}\n", h)).collect::<String>();
+        std::fs::write(path.as_path(), contents)?;
+        paths.push(path);
     }
 
-    // let path = std::env::var("PATH").unwrap();
-    let ld_library_path = std::env::var("LD_LIBRARY_PATH").unwrap();
+    Ok(paths)
+}
+
+/// Parse an MPI hostfile (e.g., `node01 slots=8`), ignoring blank lines and comments.
+///
+/// # Returns
+/// The hosts and their number of slots (if given)
+pub fn parse_hostfile(path: &Path) -> Result<Vec<HostfileEntry>, Box<dyn std::error::Error>> {
+    let mut hosts = Vec::new();
 
-    // Verify that the necessary libraries are in the LD_LIBRARY_PATH
-    for lib in [nccl_lib, cuda_lib, mpi_lib].iter() {
-        if !ld_library_path.contains(lib.to_str().unwrap()) {
-            panic!("[ERROR] {} not in LD_LIBRARY_PATH!", lib.to_str().unwrap())
+    for line in std::fs::read_to_string(path)?.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
         }
 
-        if !ld_library_path.contains(lib.to_str().unwrap()) {
-            panic!("[ERROR] {} not in LD_LIBRARY_PATH!", lib.to_str().unwrap())
+        let mut tokens = line.split_whitespace();
+        let host = tokens.next().unwrap().to_string();
+        let mut slots = None;
+        for token in tokens {
+            match token.strip_prefix("slots=") {
+                Some(v) => slots = Some(v.parse::<u64>().map_err(|e| format!("Invalid slots in hostfile line '{}': {}", line, e))?),
+                None => return Err(format!("Unrecognized token '{}' in hostfile line: {}", token, line).into()),
+            }
         }
+
+        hosts.push(HostfileEntry { host, slots });
     }
 
+    Ok(hosts)
+}
+
+/// Print a pass/fail report for the `--validate-only` preflight check
+pub fn print_validation_report(problems: &[String], num_permutations: usize) {
+    println!("\n--- 🔍🔍🔍 VALIDATION REPORT 🔍🔍🔍 ---\n");
+    println!("Checked {} permutation(s).", num_permutations);
+    if problems.is_empty() {
+        println!("✅ PASS: No problems found.");
+    } else {
+        println!("❌ FAIL: Found {} problem(s):", problems.len());
+        for problem in problems {
+            println!("  • {}", problem);
+        }
+    }
+}
+
+/// Get a hash of an experiment's full params and the contents of its XML file
+///
+/// Note: Unlike the output filename, the hash changes when an XML file is regenerated under the same name, so it tells
+///       whether a finished experiment is still up to date. A missing XML file hashes as empty.
+pub fn config_hash(params: &MscclExperimentParams) -> String {
+    let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+    hasher.update(serde_json::to_string(params).unwrap_or_default().as_bytes());
+    hasher.update(std::fs::read(params.ms_xml_file.as_path()).unwrap_or_default().as_slice());
+
+    format!("{:016x}", hasher.digest())
+}
+
+/// Header line of an experiment's log that records its config hash
+pub const CONFIG_HASH_HEADER: &str = "# Config hash: ";
+
+/// Read the config hash recorded at the top of an experiment's log (if any)
+pub fn read_log_config_hash(path: &Path) -> Option<String> {
+    let log = std::fs::read_to_string(path).ok()?;
+    log.lines()
+        .take_while(|l| l.starts_with('#'))
+        .find_map(|l| l.strip_prefix(CONFIG_HASH_HEADER))
+        .map(|v| v.trim().to_string())
+}
+
+/// Trailer line appended to an experiment's log once the result of its repetition is known
+pub const RESULT_TRAILER: &str = "# Result: ";
+
+/// Record the result of a repetition at the end of its log (so that `SKIP_FINISHED` can tell finished runs apart)
+pub fn append_log_result(path: &Path, result: &ResultDescription) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+    writeln!(file, "{}{}", RESULT_TRAILER, result)?;
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Check if a repetition's log records a finished run of the given config (so the repetition can be skipped)
+///
+/// Note: A log only counts as finished if it was written with the same config hash and ends with a successful (or
+///       partially successful) result trailer. Logs of runs that crashed, were killed, or failed have no such trailer,
+///       so only those repetitions are rerun.
+///
+/// # Returns
+/// Why the repetition has to be rerun (if it does)
+pub fn check_log_finished(path: &Path, experiment_config_hash: &str) -> Result<(), String> {
+    let log = std::fs::read_to_string(path).map_err(|_| format!("{:?} does not exist", path))?;
+    match read_log_config_hash(path) {
+        Some(hash) if hash == experiment_config_hash => {}
+        Some(hash) => return Err(format!("its params or XML file changed since {:?} was written (config hash {} -> {})", path, hash, experiment_config_hash)),
+        None => return Err(format!("{:?} has no config hash to compare against (written by an older version)", path)),
+    }
+
+    let success = [ResultDescription::Success, ResultDescription::PartialFailure, ResultDescription::Warmup].map(|r| r.to_string());
+    match log.lines().rev().find_map(|l| l.strip_prefix(RESULT_TRAILER)).map(|v| v.trim()) {
+        Some(result) if success.iter().any(|s| s == result) => Ok(()),
+        Some(result) => Err(format!("its last run ended with: {}", result)),
+        None => Err(format!("{:?} is incomplete (the run was interrupted before its result was recorded)", path)),
+    }
+}
+
+/// Upper bound on the output files the harness keeps open at the same time
+///
+/// Note: Only the running experiment's log, stderr, and power sample files plus the incremental Parquet file stay open
+///       (the experiment's files are closed as soon as it finishes). Every other output (CSVs, tables, manifests, and
+///       reports) is written and closed in one go, so open files never accumulate over a sweep.
+pub const MAX_OPEN_OUTPUT_FILES: usize = 4;
+
+/// Create an output file, explaining the error if the open file descriptor limit was hit
+pub fn create_output_file(path: &Path) -> std::io::Result<std::fs::File> {
+    std::fs::File::create(path).map_err(explain_fd_limit_error)
+}
+
+/// Explain an I/O error caused by hitting the open file descriptor limit (other errors are returned unchanged)
+///
+/// Note: Returns an error of the same kind as the underlying one, so callers can keep their handling.
+pub fn explain_fd_limit_error(e: std::io::Error) -> std::io::Error {
+    // EMFILE (per process) or ENFILE (system wide)
+    if matches!(e.raw_os_error(), Some(23) | Some(24)) {
+        std::io::Error::new(
+            e.kind(),
+            format!(
+                "{} (the file descriptor limit was hit; the harness keeps at most {} output files open, so raise the limit with 'ulimit -n' (currently: {}) or check what else holds descriptors)",
+                e,
+                MAX_OPEN_OUTPUT_FILES,
+                open_files_limit().unwrap_or_else(|| "unknown".to_string())
+            ),
+        )
+    } else {
+        e
+    }
+}
+
+/// Get the soft limit of open files of this process (from `/proc/self/limits`, so only on Linux)
+fn open_files_limit() -> Option<String> {
+    let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+    let line = limits.lines().find(|l| l.starts_with("Max open files"))?;
+    line.split_whitespace().nth(3).map(|v| v.to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::launcher::ProcessBinding;
+
+    /// Params of a single-GPU all-reduce that runs the given executable
+    pub(crate) fn local_params(executable: &str) -> MscclExperimentParams {
+        MscclExperimentParams {
+            cuda_path: String::new(),
+            efa_path: None,
+            aws_ofi_nccl_path: None,
+            openmpi_path: String::new(),
+            msccl_path: String::new(),
+            ld_library_path_order: Vec::new(),
+            passthrough_env_vars: Vec::new(),
+            cuda_visible_devices: None,
+            executable: PathBuf::from(executable),
+            nc_build: None,
+            nsys_profile: None,
+            output_filename_format: FilenameFormat::default(),
+            algorithm: "ring".to_string(),
+            ms_xml_file: PathBuf::new(),
+            ms_channels: 1,
+            ms_chunks: 1,
+            ms_gen_xml: false,
+            ms_xml_fallback: true,
+            gpu_as_node: false,
+            num_nodes: 1,
+            total_gpus: 1,
+            buffer_size: 1,
+            mpi_hostfile_path: PathBuf::new(),
+            mpi_host_group: None,
+            mpi_proc_per_node: 1,
+            mpi_ppn_override: false,
+            mpi_binding: ProcessBinding::None,
+            nc_collective: "all-reduce".to_string(),
+            nc_op: "sum".to_string(),
+            nc_dtype: "float".to_string(),
+            nc_num_threads: 1,
+            nc_num_gpus: 1,
+            nc_min_bytes: "8".to_string(),
+            nc_max_bytes: "8".to_string(),
+            nc_step_factor: Some("2".to_string()),
+            nc_step_bytes: None,
+            nc_num_iters: 1,
+            nc_num_warmup_iters: 1,
+            nc_iter_regimes: Vec::new(),
+            nc_timeout: None,
+            nc_root: None,
+            nc_cudagraph: None,
+            extra_test_args: Vec::new(),
+            nccl_debug_level: "WARN".to_string(),
+            nccl_algo: "MSCCL,RING,TREE".to_string(),
+            nccl_proto: None,
+            nccl_min_nchannels: None,
+            nccl_max_nchannels: None,
+            nccl_net_plugin: None,
+        }
+    }
+
+    #[test]
+    fn gpus_per_proc_splits_evenly() {
+        assert_eq!(gpus_per_proc(8, 8).unwrap(), 1);
+        assert_eq!(gpus_per_proc(8, 2).unwrap(), 4);
+        assert_eq!(gpus_per_proc(4, 2).unwrap(), 2);
+        assert!(gpus_per_proc(8, 3).is_err());
+        assert!(gpus_per_proc(8, 0).is_err());
+    }
+
+    #[test]
+    fn ring_and_binary_tree_chunks_channels() {
+        let (chunks, channels) = algorithm_chunks_channels("ring").unwrap();
+        assert_eq!((chunks, channels), (&[1, 2][..], &[4, 8, 16][..]));
+        let (chunks, channels) = algorithm_chunks_channels("binary-tree").unwrap();
+        assert_eq!((chunks, channels), (&[1, 2, 4, 8, 16][..], &[4, 8, 16][..]));
+    }
+
+    #[test]
+    fn undefined_algorithm_chunks_channels_is_an_error() {
+        assert!(algorithm_chunks_channels("binomial-tree").is_err());
+    }
+
+    #[test]
+    fn proc_per_node_override_must_split_evenly() {
+        assert!(validate_proc_per_node_override(8, 8, &[]).is_ok());
+        assert!(validate_proc_per_node_override(8, 2, &[]).is_ok());
+        assert!(validate_proc_per_node_override(8, 3, &[]).is_err());
+        assert!(validate_proc_per_node_override(8, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn wrong_count_column_is_a_count() {
+        assert_eq!(WrongValues::parse("0", Some(WrongColumn::Count)), WrongValues::Count(0));
+        assert_eq!(WrongValues::parse("3", Some(WrongColumn::Count)), WrongValues::Count(3));
+        assert_eq!(WrongValues::parse("1e-07", Some(WrongColumn::Count)), WrongValues::Unchecked);
+    }
+
+    #[test]
+    fn error_column_is_a_magnitude() {
+        assert_eq!(WrongValues::parse("0", Some(WrongColumn::Magnitude)), WrongValues::Magnitude(0.0));
+        assert_eq!(WrongValues::parse("1e-07", Some(WrongColumn::Magnitude)), WrongValues::Magnitude(1e-07));
+        assert_eq!(WrongValues::parse("1e-07", Some(WrongColumn::Magnitude)).count(), None);
+    }
+
+    #[test]
+    fn unchecked_wrong_values() {
+        assert_eq!(WrongValues::parse("N/A", Some(WrongColumn::Count)), WrongValues::Unchecked);
+        assert_eq!(WrongValues::parse("N/A", Some(WrongColumn::Magnitude)), WrongValues::Unchecked);
+        assert_eq!(WrongValues::parse("N/A", None), WrongValues::Unchecked);
+    }
+
+    #[test]
+    fn wrong_values_without_header_are_guessed() {
+        assert_eq!(WrongValues::parse("0", None), WrongValues::Count(0));
+        assert_eq!(WrongValues::parse("1e-07", None), WrongValues::Magnitude(1e-07));
+    }
+
+    #[test]
+    fn scientific_notation_row_is_parsed() {
+        let line = "   134217728      33554432     float     sum      -1   1090.1  1.23e+02  2.15e+02  1e-07   1088.4  1.23e+02  2.16e+02      0";
+        let row = crate::parse::parse_line(line).unwrap().unwrap();
+        assert_eq!(row.oop_alg_bw, 123.0);
+        assert_eq!(row.oop_bus_bw, 215.0);
+        assert_eq!(row.oop_wrong, WrongValues::Magnitude(1e-07));
+        assert_eq!(row.ip_wrong, WrongValues::Count(0));
+    }
+
+    /// Write a log to a file of its own in the temp directory (removed by the caller)
+    fn write_temp_log(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("nccl_harness_test_{}_{}.log", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// A log of a single run with the given config hash (the table and footer of nccl-tests, but no result trailer)
+    fn run_log(hash: &str) -> String {
+        format!(
+            "# Sweep: test\n{}{}\n# Launch command: mpirun all_reduce_perf\n\
+             #       size         count      type   redop    root     time   algbw   busbw #wrong     time   algbw   busbw #wrong\n\
+             \x20      65536         16384     float     sum      -1    16.00    4.10    7.17      0    16.10    4.07    7.12      0\n\
+             # Out of bounds values : 0 OK\n# Avg bus bandwidth    : 7.14\n",
+            CONFIG_HASH_HEADER, hash
+        )
+    }
+
+    #[test]
+    fn log_with_successful_result_is_finished() {
+        let path = write_temp_log("success", format!("{}{}Success\n", run_log("abc"), RESULT_TRAILER).as_str());
+        let finished = check_log_finished(path.as_path(), "abc");
+        std::fs::remove_file(&path).unwrap();
+        assert!(finished.is_ok());
+    }
+
+    #[test]
+    fn half_completed_log_is_not_finished() {
+        // A run that was interrupted after its header (and some rows) were written, but before its result was known
+        let path = write_temp_log("half", run_log("abc").as_str());
+        let finished = check_log_finished(path.as_path(), "abc");
+        std::fs::remove_file(&path).unwrap();
+        assert!(finished.is_err());
+    }
+
+    #[test]
+    fn log_with_other_config_hash_is_not_finished() {
+        let path = write_temp_log("hash", format!("{}{}Success\n", run_log("abc"), RESULT_TRAILER).as_str());
+        let finished = check_log_finished(path.as_path(), "def");
+        std::fs::remove_file(&path).unwrap();
+        assert!(finished.is_err());
+    }
+
+    #[test]
+    fn log_with_failed_result_is_not_finished() {
+        let path = write_temp_log("failure", format!("{}{}Failure\n", run_log("abc"), RESULT_TRAILER).as_str());
+        let finished = check_log_finished(path.as_path(), "abc");
+        std::fs::remove_file(&path).unwrap();
+        assert!(finished.is_err());
+    }
+
+    #[test]
+    fn swept_ops_and_dtypes_get_their_own_file_names() {
+        let mut sum = local_params("all_reduce_perf");
+        sum.output_filename_format.op_token = true;
+        sum.output_filename_format.dtype_token = true;
+        let max = MscclExperimentParams { nc_op: "max".to_string(), ..sum.clone() };
+        let half = MscclExperimentParams { nc_dtype: "half".to_string(), ..sum.clone() };
+        let names = [&sum, &max, &half].map(|p| exp_params_to_output_filename(p, 0, "log"));
+        assert_ne!(names[0], names[1]);
+        assert_ne!(names[0], names[2]);
+        assert!(names[1].to_str().unwrap().contains("_opmax_"));
+        assert!(names[2].to_str().unwrap().contains("_dthalf_"));
+    }
+
+    #[test]
+    fn single_op_and_dtype_keep_the_usual_file_names() {
+        let params = local_params("all_reduce_perf");
+        let name = exp_params_to_config_name(&params);
+        assert!(!name.contains("_op") && !name.contains("_dt"));
+    }
+}
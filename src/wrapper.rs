@@ -1,113 +1,395 @@
+use std::fmt;
 use std::io::{BufRead, Write};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::process::Command;
-use log::{debug, info, warn, error};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tracing::{debug, info, warn, error};
 
 use crate::{Row, Permutation, MscclExperimentParams};
+use crate::util::{collective_to_test_exe, config_hash, create_output_file, split_iter_regimes, exp_params_to_output_filename, explain_fd_limit_error, parse_hostfile, SweepMetadata, CONFIG_HASH_HEADER};
+use crate::parse::TableParser;
+use crate::launcher::{render_command, TestLauncher};
 
-/// Run NCCL tests with MPI using a set of parameters
+/// Known error messages that come from the launcher (OpenMPI/ssh plumbing) rather than from NCCL itself
+const LAUNCHER_ERROR_PATTERNS: [&str; 8] = [
+    NOT_ENOUGH_SLOTS_PATTERN,
+    "mpirun was unable to launch",
+    "mpirun was unable to find the specified executable",
+    "ORTE was unable to reliably start",
+    "ORTE does not know how to route a message",
+    "A request was made to bind to that would result in binding more",
+    "Could not resolve hostname",
+    "Host key verification failed",
+];
+
+/// OpenMPI's error when `--map-by ppr:N:node` asks for more processes than a host has slots
+const NOT_ENOUGH_SLOTS_PATTERN: &str = "There are not enough slots available";
+
+/// Explain a "not enough slots" error by comparing the hostfile's slot counts to the requested processes per node
+fn explain_not_enough_slots(params: &MscclExperimentParams) -> String {
+    let mut explanation = format!(
+        "Not enough MPI slots: the experiment maps {} process(es) per node (ppr:{}:node) on {} node(s)",
+        params.mpi_proc_per_node, params.mpi_proc_per_node, params.num_nodes
+    );
+
+    match parse_hostfile(params.mpi_hostfile_path.as_path()) {
+        Ok(hosts) => {
+            let slots = hosts
+                .iter()
+                .map(|h| match h.slots {
+                    Some(n) => format!("{} (slots={})", h.host, n),
+                    None => format!("{} (slots not set, so OpenMPI uses the number of cores)", h.host),
+                })
+                .collect::<Vec<String>>();
+            explanation.push_str(format!(", but the hostfile {:?} lists: {}.", params.mpi_hostfile_path, slots.join(", ")).as_str());
+        }
+        Err(e) => {
+            explanation.push_str(format!(", but the hostfile {:?} could not be read: {}.", params.mpi_hostfile_path, e).as_str());
+        }
+    }
+    explanation.push_str(format!(
+        " Fix: set 'slots={}' (or more) for every host in the hostfile, or lower MPI_PROC_PER_NODE.",
+        params.mpi_proc_per_node
+    ).as_str());
+
+    explanation
+}
+
+/// Check whether a line of stderr looks like it came from the launcher rather than from NCCL
+pub fn is_launcher_error(line: &str) -> bool {
+    LAUNCHER_ERROR_PATTERNS.iter().any(|p| line.contains(p))
+}
+
+/// Default patterns of NCCL output that mean a run is doomed (as printed by nccl-tests, e.g.,
+/// `NCCL failure common.cu:958 'unhandled cuda error'`)
+pub const DEFAULT_FATAL_ERROR_PATTERNS: [&str; 4] = [
+    "'invalid usage'",
+    "'unhandled cuda error'",
+    "'unhandled system error'",
+    "'internal error'",
+];
+
+/// Kill the launcher if a line of its output contains one of the fatal patterns (only the first match is recorded)
+fn check_fatal_error(
+    line: &str,
+    fatal_patterns: &[String],
+    launcher: &Mutex<std::process::Child>,
+    fatal_error: &Mutex<Option<String>>,
+) {
+    let pattern = match fatal_patterns.iter().find(|p| line.contains(p.as_str())) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let mut fatal_error = fatal_error.lock().unwrap();
+    if fatal_error.is_none() {
+        if let Err(e) = launcher.lock().unwrap().kill() {
+            error!("Error killing the launcher after a fatal error: {}", e);
+        }
+        *fatal_error = Some(format!("NCCL tests were killed after output matched the fatal error pattern {:?}: {}", pattern, line.trim()));
+    }
+}
+
+/// Default number of lines at the end of stderr that are kept with a `HarnessError` (e.g., for the errors log)
+pub const DEFAULT_STDERR_TAIL_LINES: usize = 200;
+
+/// Longest line (in bytes) that is kept in memory for errors; longer lines are cut (the output files keep them whole)
+pub const MAX_RETAINED_LINE_LEN: usize = 4096;
+
+/// Cut a line of output down to `MAX_RETAINED_LINE_LEN` bytes before keeping it in memory
+fn truncate_retained_line(line: &str) -> String {
+    if line.len() <= MAX_RETAINED_LINE_LEN {
+        return line.to_string();
+    }
+    let mut end = MAX_RETAINED_LINE_LEN;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes cut)", &line[..end], line.len() - end)
+}
+
+/// Errors of a run of the NCCL tests that call for different handling (e.g., different retry policies)
+///
+/// Note: The errors of a run carry the last lines of stderr (`stderr_tail_lines` of them at most) (empty if the tests never
+///       started) for triage.
+#[derive(Debug)]
+pub enum HarnessError {
+    /// The tests did not finish within the total timeout, or produced no output for the idle timeout, and were killed
+    /// (probably a hang)
+    Timeout { seconds: u64, idle: bool, stderr_tail: Vec<String> },
+    /// The launcher or the tests failed (probably a crash)
+    LaunchFailed { status: Option<i32>, reason: String, stderr_tail: Vec<String> },
+    /// The tests ran, but reported wrong values in their `# Out of bounds values` footer (or `#wrong` columns)
+    WrongResults { out_of_bounds: u64, stderr_tail: Vec<String> },
+    /// The harness is misconfigured (e.g., a required envvar is missing or invalid), so nothing was run
+    Config { reason: String },
+}
+
+impl HarnessError {
+    /// Get the last lines of stderr captured before the error
+    pub fn stderr_tail(&self) -> &[String] {
+        match self {
+            HarnessError::Timeout { stderr_tail, .. }
+            | HarnessError::LaunchFailed { stderr_tail, .. }
+            | HarnessError::WrongResults { stderr_tail, .. } => stderr_tail,
+            HarnessError::Config { .. } => &[],
+        }
+    }
+}
+
+impl fmt::Display for HarnessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HarnessError::Timeout { seconds, idle: false, .. } => write!(f, "NCCL tests did not finish within {} s and were killed", seconds),
+            HarnessError::Timeout { seconds, idle: true, .. } => write!(f, "NCCL tests produced no output for {} s and were killed", seconds),
+            HarnessError::LaunchFailed { status: Some(status), reason, .. } => write!(f, "{} (exit code: {})", reason, status),
+            HarnessError::LaunchFailed { status: None, reason, .. } => write!(f, "{}", reason),
+            HarnessError::WrongResults { out_of_bounds, .. } => write!(f, "NCCL tests reported {} out-of-bounds value(s)", out_of_bounds),
+            HarnessError::Config { reason } => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for HarnessError {}
+
+/// Format the entry of a failed experiment for the errors log (params, exit code, timeout status, and stderr tail)
+///
+/// # Arguments
+/// * `params` - The params of the failed experiment
+/// * `iteration` - The repetition that failed
+/// * `error` - The error the run failed with (details are only available for a `HarnessError`)
+pub fn format_error_log_entry(params: &MscclExperimentParams, iteration: u64, error: &(dyn std::error::Error + 'static)) -> String {
+    let harness_error = error.downcast_ref::<HarnessError>();
+    let (exit_code, timed_out) = match harness_error {
+        Some(HarnessError::LaunchFailed { status, .. }) => (status.map(|s| s.to_string()), "no".to_string()),
+        Some(HarnessError::Timeout { seconds, idle, .. }) => {
+            (None, format!("yes ({} timeout of {} s)", if *idle { "idle" } else { "total" }, seconds))
+        }
+        Some(HarnessError::WrongResults { .. }) | Some(HarnessError::Config { .. }) | None => (None, "no".to_string()),
+    };
+
+    let mut entry = format!("### {} ###\n", exp_params_to_output_filename(params, iteration, "log").display());
+    entry.push_str(&format!("Params: {}\n", serde_json::to_string(params).unwrap_or_default()));
+    entry.push_str(&format!("Exit code: {}\n", exit_code.as_deref().unwrap_or("N/A")));
+    entry.push_str(&format!("Timed out: {}\n", timed_out));
+    entry.push_str(&format!("Error: {}\n", error));
+    let stderr_tail = harness_error.map(|e| e.stderr_tail()).unwrap_or_default();
+    entry.push_str(&format!("Stderr (last {} lines):\n", stderr_tail.len()));
+    for line in stderr_tail {
+        entry.push_str(&format!("    {}\n", line));
+    }
+    entry.push('\n');
+
+    entry
+}
+
+/// Open an output file, either overwriting it or appending to it
+fn open_output_file(path: &std::path::Path, append: bool) -> std::io::Result<std::fs::File> {
+    if append {
+        std::fs::OpenOptions::new().append(true).create(true).open(path).map_err(explain_fd_limit_error)
+    } else {
+        create_output_file(path)
+    }
+}
+
+/// Limits on how long a run of the NCCL tests may take before it is considered hung and killed
+///
+/// Note: The idle timeout resets with every line of output (on stdout or stderr, e.g., `NCCL_DEBUG` logs), so
+///       slow-but-progressing runs (e.g., large messages that print rarely) are not killed as long as they keep
+///       printing. Either timeout is optional. The heartbeat only logs that the run is still in flight (and how long
+///       ago it last printed), to tell slow runs from stuck ones.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Timeouts {
+    pub total: Option<Duration>,     // Hard limit on the wall clock time of a run
+    pub idle: Option<Duration>,      // Limit on the time without a new line of output
+    pub heartbeat: Option<Duration>, // How often to log that the run is still in flight
+    pub poll_interval: Duration,     // How often the timeouts (and the heartbeat) are checked
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts { total: None, idle: None, heartbeat: None, poll_interval: Duration::from_millis(100) }
+    }
+}
+
+/// How often to retry a run of the NCCL tests after a hang (`HarnessError::Timeout`) or a crash
+/// (`HarnessError::LaunchFailed`)
+///
+/// Note: Separate because a hang might clear up on a second try, while a crash usually won't
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RetryPolicy {
+    pub hang_retries: u64,
+    pub crash_retries: u64,
+}
+
+/// Run NCCL tests with `run_msccl_tests`, retrying hangs and crashes according to their own policies
+#[allow(clippy::too_many_arguments)]
+pub fn run_msccl_tests_with_retries(
+    launcher: &dyn TestLauncher,
+    exp_params: &MscclExperimentParams,
+    metadata: &SweepMetadata,
+    dry_run: bool,
+    timeouts: Timeouts,
+    retries: RetryPolicy,
+    tee_stdout: bool,
+    fatal_patterns: &[String],
+    stderr_tail_lines: usize,
+    output_path: PathBuf,
+    stderr_path: PathBuf,
+    experiment_config_hash: &str,
+) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+    let (mut num_hang_retries, mut num_crash_retries) = (0, 0);
+    loop {
+        let result = run_msccl_tests(
+            launcher,
+            exp_params,
+            metadata,
+            true, // Why? Well, Liuyao's testo sometimes return a nonzero status code
+            dry_run,
+            timeouts,
+            tee_stdout,
+            fatal_patterns,
+            stderr_tail_lines,
+            Some(output_path.clone()),
+            Some(stderr_path.clone()),
+            experiment_config_hash,
+        );
+
+        match result.as_ref().map_err(|e| e.downcast_ref::<HarnessError>()) {
+            Err(Some(HarnessError::Timeout { .. })) if num_hang_retries < retries.hang_retries => {
+                num_hang_retries += 1;
+                warn!("Experiment hung, retrying (hang policy: retry {} of {}).", num_hang_retries, retries.hang_retries);
+            }
+            Err(Some(HarnessError::LaunchFailed { .. })) if num_crash_retries < retries.crash_retries => {
+                num_crash_retries += 1;
+                warn!("Experiment crashed, retrying (crash policy: retry {} of {}).", num_crash_retries, retries.crash_retries);
+            }
+            _ => return result,
+        }
+    }
+}
+
+/// Run NCCL tests using the given launcher and set of parameters
+///
+/// Note: With iteration regimes (`nc_iter_regimes`), the message size range is split into one run per regime (see
+///       `split_iter_regimes`). The runs go one after another into the same output files, and their rows are
+///       returned together. The timeouts apply to each run on its own. See `run_msccl_tests_part` for the rest.
+///       The log records `experiment_config_hash` (of the experiment's full params, see `config_hash`) rather than a
+///       hash of `exp_params`, which may be changed for a single launch (e.g., the hostfile of a parallel repetition).
+#[allow(clippy::too_many_arguments)]
 pub fn run_msccl_tests(
-    executable: &Path,
+    launcher: &dyn TestLauncher,
     exp_params: &MscclExperimentParams,
+    metadata: &SweepMetadata,
     ignore_error_status_codes: bool,
     dry_run: bool,
+    timeouts: Timeouts,
+    tee_stdout: bool,
+    fatal_patterns: &[String],
+    stderr_tail_lines: usize,
     output_path: Option<PathBuf>,
     stderr_path: Option<PathBuf>,
+    experiment_config_hash: &str,
 ) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
-    // Build the LD_LIBRARY_PATH from the given environment variables
-    let mut ld_library_path = format!(
-        "{}/lib64:{}/lib:{}/lib64:{}/lib:{}/lib64:{}/lib",
-        exp_params.cuda_path,
-        exp_params.cuda_path,
-        exp_params.openmpi_path,
-        exp_params.openmpi_path,
-        exp_params.msccl_path,
-        exp_params.msccl_path
-    );
-    if let Some(efa_path) = exp_params.efa_path.clone() {
-        ld_library_path.push_str(format!(":{}/lib", efa_path).as_str());
+    if exp_params.nc_iter_regimes.is_empty() {
+        return run_msccl_tests_part(
+            launcher, exp_params, metadata, ignore_error_status_codes, dry_run, timeouts, tee_stdout, fatal_patterns, stderr_tail_lines,
+            output_path, stderr_path, experiment_config_hash, false,
+        );
     }
-    if let Some(aws_ofi_nccl_path) = exp_params.aws_ofi_nccl_path.clone() {
-        ld_library_path.push_str(format!(":{}/lib", aws_ofi_nccl_path).as_str());
+
+    let runs = split_iter_regimes(exp_params)?;
+    if runs.is_empty() {
+        return Err(format!("No message sizes between {} and {} to run", exp_params.nc_min_bytes, exp_params.nc_max_bytes).into());
     }
-    debug!("Will use `LD_LIBRARY_PATH`: {}", ld_library_path);
 
-    // MSCCL XML file handling (just use dummy envvar if not given an XML file)
-    let msccl_xml_envvar = {
-        debug!(
-            "Using MSCCL XML file at: {}",
-            exp_params.ms_xml_file.to_str().unwrap()
+    let mut rows = Vec::new();
+    for (i, run) in runs.iter().enumerate() {
+        info!(
+            "Running message sizes {} to {} bytes with {} warmup and {} timed iteration(s) (regime {} of {})...",
+            run.nc_min_bytes, run.nc_max_bytes, run.nc_num_warmup_iters, run.nc_num_iters, i + 1, runs.len()
         );
-        format!(
-            "MSCCL_XML_FILES={}",
-            exp_params.ms_xml_file.to_str().unwrap()
-        )
-    };
+        rows.extend(run_msccl_tests_part(
+            launcher, run, metadata, ignore_error_status_codes, dry_run, timeouts, tee_stdout, fatal_patterns, stderr_tail_lines,
+            output_path.clone(), stderr_path.clone(), experiment_config_hash, i > 0,
+        )?);
+    }
+
+    Ok(rows)
+}
 
-    // Run NCCL tests with MPI
+/// Run NCCL tests once using the given launcher and set of parameters
+///
+/// Note: The launcher only builds the command. Spawning, I/O, and status handling all happen here. If one of the
+///       `timeouts` is exceeded, the launcher is killed and `HarnessError::Timeout` is returned. With `tee_stdout`, the
+///       lines of stdout are also printed to the harness's own stdout as they arrive (prefixed with `[nccl-tests]`). If
+///       a line of stdout or stderr contains one of the `fatal_patterns`, the launcher is killed right away and
+///       `HarnessError::LaunchFailed` is returned (instead of waiting for the run to end or time out).
+///       With `append`, the output files are appended to rather than overwritten (for the later runs of an experiment).
+#[allow(clippy::too_many_arguments)]
+fn run_msccl_tests_part(
+    launcher: &dyn TestLauncher,
+    exp_params: &MscclExperimentParams,
+    metadata: &SweepMetadata,
+    ignore_error_status_codes: bool,
+    dry_run: bool,
+    timeouts: Timeouts,
+    tee_stdout: bool,
+    fatal_patterns: &[String],
+    stderr_tail_lines: usize,
+    output_path: Option<PathBuf>,
+    stderr_path: Option<PathBuf>,
+    experiment_config_hash: &str,
+    append: bool,
+) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+    // Build the launch command
     // TODO: Verify that OpenMPI passes through required environment variables
-    debug!("Running NCCL tests with 'MPI'...");
-    if dry_run {
-        info!("🌵 ONLY PRINTING OUT THE COMMAND BECAUSE THIS IS A DRY RUN! 🌵")
-    }
-    let mut res = Command::new(if !dry_run { "mpirun" } else { "echo" })
-        .args(["--hostfile", exp_params.mpi_hostfile_path.to_str().unwrap()])
-        .args([
-            "--map-by",
-            format!("ppr:{}:node", exp_params.mpi_proc_per_node).as_str(),
-        ])
-        .args([
-            "-x",
-            format!("LD_LIBRARY_PATH={}", ld_library_path).as_str(),
-        ])
-        .args(["-x", msccl_xml_envvar.as_str()])
-        .args(["-x", "GENMSCCLXML=1"])
-        .args([
-            "-x",
-            format!("NCCL_DEBUG={}", exp_params.nccl_debug_level).as_str(),
-        ])
-        .args(["-x", format!("NCCL_ALGO={}", exp_params.nccl_algo).as_str()])
-        .args(["-x", "FI_EFA_USE_DEVICE_RDMA=1"])
-        .args(["-x", "FI_EFA_FORK_SAFE=1"])
-        .args([
-            "--mca",
-            "btl",
-            "tcp,self",
-            "--mca",
-            "btl_tcp_if_exclude",
-            "lo,docker0",
-            "--bind-to",
-            "none",
-        ])
-        .arg(executable.to_str().unwrap())
-        .args([
-            "--nthreads",
-            format!("{}", exp_params.nc_num_threads).as_str(),
-        ])
-        .args(["--ngpus", exp_params.nc_num_gpus.to_string().as_str()])
-        .args(["--minbytes", exp_params.nc_min_bytes.as_str()])
-        .args(["--maxbytes", exp_params.nc_max_bytes.as_str()])
-        .args(["--stepfactor", exp_params.nc_step_factor.as_str()])
-        .args(["--op", exp_params.nc_op.as_str()])
-        .args(["--datatype", exp_params.nc_dtype.as_str()])
-        .args(["--iters", exp_params.nc_num_iters.to_string().as_str()])
-        .args([
-            "--warmup_iters",
-            exp_params.nc_num_warmup_iters.to_string().as_str(),
-        ])
+    let launch_command = launcher.build_command(exp_params);
+    let launcher_name = launch_command.get_program().to_string_lossy().to_string();
+    let rendered_command = render_command(&launch_command);
+    debug!("Launch command: {}", rendered_command);
+
+    // Run NCCL tests with the launcher
+    debug!("Running NCCL tests with '{}'...", launcher_name);
+    let mut command = if dry_run {
+        info!("🌵 ONLY PRINTING OUT THE COMMAND BECAUSE THIS IS A DRY RUN! 🌵");
+        let mut echo = Command::new("echo");
+        echo.arg(launch_command.get_program()).args(launch_command.get_args());
+        echo
+    } else {
+        launch_command
+    };
+
+    let mut res = match command
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
-        .expect("[ERROR] FAILED TO RUN WITH MPI!!!!");
+    {
+        Ok(v) => v,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                error!(
+                    "Failed to launch '{}': {}. Is it on your PATH (e.g., '{}/bin' for mpirun)?",
+                    launcher_name, e, exp_params.openmpi_path
+                );
+            }
+            let e = explain_fd_limit_error(e);
+            return Err(HarnessError::LaunchFailed { status: None, reason: format!("Failed to launch '{}': {}", launcher_name, e), stderr_tail: Vec::new() }.into());
+        }
+    };
 
-    // Create vector to store rows
+    // Create vector to store rows (and a parser that keeps track of which table each row belongs to)
     let mut rows = Vec::new();
+    let mut table_parser = TableParser::new();
 
     // Open output file for writing
     let mut output_file = match output_path {
         Some(path) => {
             debug!("Opening output file at: {}", path.to_str().unwrap());
-            let res = match std::fs::File::create(path.clone()) {
+            let res = match open_output_file(path.as_path(), append) {
                 Ok(f) => Some(f),
                 Err(e) => {
                     error!("Error creating output file {:?}. WILL NOT LOG OUTPUT AS SEPARATE FILE!: {}", path, e);
@@ -123,7 +405,7 @@ pub fn run_msccl_tests(
     let mut stderr_file = match stderr_path {
         Some(path) => {
             debug!("Opening stderr file at: {}", path.to_str().unwrap());
-            let res = match std::fs::File::create(path.clone()) {
+            let res = match open_output_file(path.as_path(), append) {
                 Ok(f) => Some(f),
                 Err(e) => {
                     error!("Error creating stderr file {:?}. WILL NOT LOG STDERR AS SEPARATE FILE!: {}", path, e);
@@ -136,12 +418,152 @@ pub fn run_msccl_tests(
         None => None,
     };
 
+    // Start the log with the sweep's metadata and the exact launch command (including the environment) so that it
+    // documents itself
+    if let Some(file) = &mut output_file {
+        for (name, value) in metadata.fields() {
+            if let Err(e) = writeln!(file, "# {}: {}", name, value) {
+                error!("Error writing metadata header to output file: {}", e);
+            }
+        }
+        if let Err(e) = writeln!(file, "{}{}", CONFIG_HASH_HEADER, experiment_config_hash) {
+            error!("Error writing config hash header to output file: {}", e);
+        }
+        if let Err(e) = writeln!(file, "# Launch command: {}", rendered_command) {
+            error!("Error writing launch command header to output file: {}", e);
+        }
+    }
+
+    // Kill the launcher if it exceeds a timeout (killing it also closes its output, which ends the reading) and log the heartbeat
+    let stdout = res.stdout.take().unwrap();
+    let stderr = res.stderr.take().unwrap();
+    let res = Arc::new(Mutex::new(res));
+    let start = Instant::now();
+    let last_output_ms = Arc::new(AtomicU64::new(0)); // Time of the last line of output on stdout or stderr (since `start`)
+    let finished = Arc::new(AtomicBool::new(false));
+    let timeout_error = Arc::new(Mutex::new(None));
+    let fatal_error = Arc::new(Mutex::new(None));
+    let watchdog = (timeouts.total.is_some() || timeouts.idle.is_some() || timeouts.heartbeat.is_some()).then(|| {
+        let (res, last_output_ms, finished, timeout_error) =
+            (res.clone(), last_output_ms.clone(), finished.clone(), timeout_error.clone());
+        let experiment_name = format!(
+            "{} {} (channels: {}, chunks: {}, GPUs: {})",
+            exp_params.nc_collective, exp_params.algorithm, exp_params.ms_channels, exp_params.ms_chunks, exp_params.total_gpus
+        );
+        std::thread::spawn(move || {
+            let mut last_heartbeat = Instant::now();
+            while !finished.load(Ordering::SeqCst) {
+                let idle_time = start.elapsed().saturating_sub(Duration::from_millis(last_output_ms.load(Ordering::SeqCst)));
+                if let Some(heartbeat) = timeouts.heartbeat {
+                    if last_heartbeat.elapsed() >= heartbeat {
+                        info!(
+                            "💓 Still running experiment {}, elapsed {} s, last output {} s ago.",
+                            experiment_name, start.elapsed().as_secs(), idle_time.as_secs()
+                        );
+                        last_heartbeat = Instant::now();
+                    }
+                }
+
+                let error = match (timeouts.total, timeouts.idle) {
+                    (Some(total), _) if start.elapsed() >= total => Some(HarnessError::Timeout { seconds: total.as_secs(), idle: false, stderr_tail: Vec::new() }),
+                    (_, Some(idle)) if idle_time >= idle => Some(HarnessError::Timeout { seconds: idle.as_secs(), idle: true, stderr_tail: Vec::new() }),
+                    _ => None,
+                };
+
+                if let Some(error) = error {
+                    if let Err(e) = res.lock().unwrap().kill() {
+                        error!("Error killing the timed out launcher: {}", e);
+                    }
+                    *timeout_error.lock().unwrap() = Some(error);
+                    break;
+                }
+                std::thread::sleep(timeouts.poll_interval);
+            }
+        })
+    });
+
+    // Print and handle stderr line by line on its own thread (so that it is seen as it arrives, e.g., to catch fatal
+    // errors early, and so that a full stderr pipe never blocks the tests)
+    let stderr_reader = {
+        let (res, last_output_ms, fatal_error, fatal_patterns) =
+            (res.clone(), last_output_ms.clone(), fatal_error.clone(), fatal_patterns.to_vec());
+        let span = tracing::Span::current();
+        std::thread::spawn(move || {
+            let _span = span.entered();
+            // Only a bounded number of (cut) lines is kept in memory, so runaway output (e.g., at `NCCL_DEBUG=TRACE`)
+            // can't exhaust it; everything else is streamed to the log and the stderr file
+            let mut launcher_errors = Vec::new();
+            let mut num_launcher_errors = 0;
+            let mut stderr_tail = std::collections::VecDeque::new();
+            let stderr_reader = std::io::BufReader::new(stderr);
+            for line in stderr_reader.lines() {
+                match line {
+                    Ok(line) => {
+                        last_output_ms.store(start.elapsed().as_millis() as u64, Ordering::SeqCst);
+
+                        // Keep the last lines for errors
+                        if stderr_tail_lines > 0 {
+                            if stderr_tail.len() == stderr_tail_lines {
+                                stderr_tail.pop_front();
+                            }
+                            stderr_tail.push_back(truncate_retained_line(line.as_str()));
+                        }
+
+                        // Print the line, tagged by where it (probably) came from
+                        if is_launcher_error(line.as_str()) {
+                            error!("[E:launcher]: {}", line);
+                            num_launcher_errors += 1;
+                            if launcher_errors.len() < stderr_tail_lines.max(1) {
+                                launcher_errors.push(truncate_retained_line(line.as_str()));
+                            }
+                        } else {
+                            debug!("[E]: {}", line);
+                        }
+                        check_fatal_error(line.as_str(), &fatal_patterns, &res, &fatal_error);
+
+                        // Write to stderr file
+                        if let Some(file) = &mut stderr_file {
+                            match file.write_all(line.as_bytes()) {
+                                Ok(_) => {}
+                                Err(e) => {
+                                    error!("Error writing line to stderr file: {}", e);
+                                }
+                    
+                            };
+                    
+                            match file.write_all(b"\n") {
+                                Ok(_) => {}
+                                Err(e) => {
+                                    error!("Error writing newline to stderr file: {}", e);
+                                }
+                            };
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error getting line from stderr BufReader: {}", e);
+                    }
+                }
+            }
+
+            if num_launcher_errors > launcher_errors.len() {
+                launcher_errors.push(format!("... and {} more", num_launcher_errors - launcher_errors.len()));
+            }
+
+            (launcher_errors, num_launcher_errors, Vec::from(stderr_tail))
+        })
+    };
+
     // Print and handle stdout line by line
-    let stdout_reader = std::io::BufReader::new(res.stdout.take().unwrap());
+    let stdout_reader = std::io::BufReader::new(stdout);
     for line in stdout_reader.lines() {
         match line {
             Ok(line) => {
                 debug!("[l]: {}", line);
+                if tee_stdout {
+                    println!("[nccl-tests] {}", line);
+                }
+                last_output_ms.store(start.elapsed().as_millis() as u64, Ordering::SeqCst);
+                check_fatal_error(line.as_str(), fatal_patterns, &res, &fatal_error);
 
                 // Write to output file
                 if let Some(file) = &mut output_file {
@@ -161,8 +583,14 @@ pub fn run_msccl_tests(
                     };
                 }
 
-                // Parse line
-                // TODO: Add function when stable
+                // Parse line, keeping it if it is a table data row
+                match table_parser.parse_line(line.as_str()) {
+                    Ok(Some(row)) => rows.push(row),
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!("Error parsing line from stdout: {}", e);
+                    }
+                }
             }
             Err(e) => {
                 error!("Error getting line from stdout BufReader: {}", e);
@@ -170,41 +598,64 @@ pub fn run_msccl_tests(
         }
     }
 
-    // Print stderr
-    // FIXME: Won't actually print if there's a hang-related error! The stdout reader never finishes reading!
-    let stderr_reader = std::io::BufReader::new(res.stderr.take().unwrap());
-    for line in stderr_reader.lines() {
-        match line {
-            Ok(line) => {
-                // Print the line
-                debug!("[E]: {}", line);
+    // Wait for stderr (read alongside stdout, see above)
+    let (launcher_errors, num_launcher_errors, stderr_tail) = stderr_reader.join().unwrap();
 
-                // Write to stderr file
-                if let Some(file) = &mut stderr_file {
-                    match file.write_all(line.as_bytes()) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            error!("Error writing line to stderr file: {}", e);
-                        }
-                    
-                    };
-                    
-                    match file.write_all(b"\n") {
-                        Ok(_) => {}
-                        Err(e) => {
-                            error!("Error writing newline to stderr file: {}", e);
-                        }
-                    };
-                }
-            }
-            Err(e) => {
-                error!("Error getting line from stderr BufReader: {}", e);
-            }
-        }
+    // Stop the watchdog
+    finished.store(true, Ordering::SeqCst);
+    if let Some(watchdog) = watchdog {
+        let _ = watchdog.join();
     }
 
     // Handle exit status
-    let status = res.wait()?;
+    let status = res.lock().unwrap().wait()?;
+    if let Some(mut error) = timeout_error.lock().unwrap().take() {
+        error!("{} (probably a hang).", error);
+        if let HarnessError::Timeout { stderr_tail: tail, .. } = &mut error {
+            *tail = stderr_tail;
+        }
+        return Err(error.into());
+    }
+    if let Some(reason) = fatal_error.lock().unwrap().take() {
+        error!("{}", reason);
+        return Err(HarnessError::LaunchFailed { status: None, reason, stderr_tail }.into());
+    }
+
+    // Launcher errors mean the tests never (fully) ran, so never ignore them
+    if !launcher_errors.is_empty() {
+        error!("The launcher reported {} error(s); check the hostfile and MPI settings.", num_launcher_errors);
+
+        // Explain the most common first-run failure instead of passing on OpenMPI's cryptic message
+        if launcher_errors.iter().any(|l| l.contains(NOT_ENOUGH_SLOTS_PATTERN)) {
+            let explanation = explain_not_enough_slots(exp_params);
+            error!("{}", explanation);
+            return Err(HarnessError::LaunchFailed { status: status.code(), reason: explanation, stderr_tail }.into());
+        }
+
+        return Err(HarnessError::LaunchFailed {
+            status: status.code(),
+            reason: format!("Launcher failed: {}", launcher_errors.join(" | ")),
+            stderr_tail,
+        }.into());
+    }
+
+    // nccl-tests' own verdict on correctness (it also exits with an error, but this says why)
+    if let Some(footer) = table_parser.footers().iter().find(|f| f.has_wrong_values()) {
+        let out_of_bounds = footer.out_of_bounds.unwrap_or_default();
+        error!("NCCL tests reported {} out-of-bounds value(s), so the results are wrong!", out_of_bounds);
+        return Err(HarnessError::WrongResults { out_of_bounds, stderr_tail }.into());
+    }
+
+    // Without an out-of-bounds footer (e.g., a truncated log), fall back to the `#wrong` counts of the rows
+    // Note: Error magnitudes (`error` columns) aren't counts, so they never fail a run
+    if !table_parser.footers().iter().any(|f| f.out_of_bounds.is_some()) {
+        let out_of_bounds = rows.iter().map(|r| r.oop_wrong.count().unwrap_or_default() + r.ip_wrong.count().unwrap_or_default()).sum::<u64>();
+        if out_of_bounds > 0 {
+            error!("NCCL tests reported {} wrong value(s) in the '#wrong' columns, so the results are wrong!", out_of_bounds);
+            return Err(HarnessError::WrongResults { out_of_bounds, stderr_tail }.into());
+        }
+    }
+
     match status.success() {
         true => info!("[SUCCESS] NCCL tests with MPI ran successfully."),
         false => {
@@ -213,7 +664,18 @@ pub fn run_msccl_tests(
                     "Running NCCL tests with MPI failed with exit code: {}",
                     status.code().unwrap()
                 );
-                return Err("NCCL tests with MPI failed.".into());
+                return Err(HarnessError::LaunchFailed { status: status.code(), reason: "NCCL tests with MPI failed.".to_string(), stderr_tail }.into());
+            } else if rows.is_empty() {
+                // Nothing to salvage, so this is a failure even when ignoring the exit code
+                error!(
+                    "Running NCCL tests with MPI failed with exit code: {} and produced no results.",
+                    status.code().unwrap()
+                );
+                return Err(HarnessError::LaunchFailed {
+                    status: status.code(),
+                    reason: "NCCL tests with MPI failed without producing any results.".to_string(),
+                    stderr_tail,
+                }.into());
             } else {
                 error!(
                     "Running NCCL tests with MPI failed with exit code: {}, but ignoring and continuing.",
@@ -223,5 +685,115 @@ pub fn run_msccl_tests(
         }
     }
 
+    // Check that the tests ran with the threads and GPUs they were given (the header echoes what they actually used)
+    let header = table_parser.header();
+    debug!("NCCL tests header: {:?}", header);
+    if header.num_threads.is_some_and(|n| n != exp_params.nc_num_threads) {
+        warn!(
+            "NCCL tests report {} thread(s) per process, but were launched with {}!",
+            header.num_threads.unwrap(), exp_params.nc_num_threads
+        );
+    }
+    if header.num_gpus.is_some_and(|n| n != exp_params.nc_num_gpus) {
+        warn!(
+            "NCCL tests report {} GPU(s) per thread, but were launched with {}!",
+            header.num_gpus.unwrap(), exp_params.nc_num_gpus
+        );
+    }
+
     Ok(rows)
-}
\ No newline at end of file
+}
+
+/// Run one tiny throwaway all-reduce to pay the one-time fabric/topology detection costs before the real sweep
+///
+/// Note: Reuses the environment, placement, and launcher settings of the sweep's first config (preferring an all-reduce
+///       config) so it warms up the same nodes and NCCL build. Its output is discarded and it is not recorded anywhere.
+///       A failed warmup is only logged; the real experiments report their own errors.
+pub fn run_cluster_warmup(
+    launcher: &dyn TestLauncher,
+    experiment_descriptors: &[MscclExperimentParams],
+    metadata: &SweepMetadata,
+    dry_run: bool,
+    timeouts: Timeouts,
+    fatal_patterns: &[String],
+    stderr_tail_lines: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let base = match experiment_descriptors.iter().find(|p| p.nc_collective == "all-reduce").or(experiment_descriptors.first()) {
+        Some(v) => v,
+        None => {
+            debug!("No experiments to run, skipping the cluster warmup.");
+            return Ok(());
+        }
+    };
+
+    let mut warmup_params = base.clone();
+    warmup_params.executable = base.executable.with_file_name(collective_to_test_exe("all-reduce")?);
+    warmup_params.nc_collective = "all-reduce".to_string();
+    warmup_params.nc_op = "sum".to_string();
+    warmup_params.nc_root = None;
+    warmup_params.nc_min_bytes = "8".to_string();
+    warmup_params.nc_max_bytes = "8".to_string();
+    warmup_params.nc_num_iters = 1;
+    warmup_params.nc_num_warmup_iters = 1;
+    warmup_params.nc_iter_regimes = Vec::new();
+
+    info!("Warming up the cluster with a tiny all-reduce on {} node(s) (results are discarded)...", warmup_params.num_nodes);
+    let warmup_config_hash = config_hash(&warmup_params);
+    match run_msccl_tests(
+        launcher, &warmup_params, metadata, true, dry_run, timeouts, false, fatal_patterns, stderr_tail_lines, None, None,
+        warmup_config_hash.as_str(),
+    ) {
+        Ok(_) => info!("Cluster warmup finished."),
+        Err(e) => warn!("Cluster warmup failed (continuing with the sweep anyway): {}", e),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launcher::Local;
+    use crate::util::tests::local_params;
+
+    /// A launcher whose program doesn't exist
+    struct MissingLauncher;
+
+    impl TestLauncher for MissingLauncher {
+        fn build_command(&self, _params: &MscclExperimentParams) -> Command {
+            Command::new("/nonexistent/nccl_harness_test/mpirun")
+        }
+
+        fn build_validation_command(&self, params: &MscclExperimentParams) -> Command {
+            self.build_command(params)
+        }
+    }
+
+    #[test]
+    fn missing_launcher_is_a_launch_failure() {
+        let params = local_params("all_reduce_perf");
+        let result = run_msccl_tests(
+            &MissingLauncher, &params, &SweepMetadata::default(), true, false, Timeouts::default(), false, &[],
+            DEFAULT_STDERR_TAIL_LINES, None, None, config_hash(&params).as_str(),
+        );
+        let error = result.unwrap_err();
+        assert!(matches!(error.downcast_ref::<HarnessError>(), Some(HarnessError::LaunchFailed { status: None, .. })));
+    }
+
+    #[test]
+    fn stderr_output_resets_the_idle_timeout() {
+        // Prints only to stderr, for longer than the idle timeout but never idle for that long
+        let script = std::env::temp_dir().join(format!("nccl_harness_test_{}_stderr_only.sh", std::process::id()));
+        std::fs::write(script.as_path(), "#!/bin/sh\nfor i in 1 2 3 4 5 6 7 8; do echo \"line $i\" >&2; sleep 0.1; done\n").unwrap();
+        std::fs::set_permissions(script.as_path(), std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+        let params = local_params(script.to_str().unwrap());
+        let timeouts = Timeouts { idle: Some(Duration::from_millis(500)), ..Timeouts::default() };
+        let result = run_msccl_tests(
+            &Local, &params, &SweepMetadata::default(), true, false, timeouts, false, &[], DEFAULT_STDERR_TAIL_LINES, None,
+            None, config_hash(&params).as_str(),
+        );
+        std::fs::remove_file(script.as_path()).unwrap();
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+}